@@ -0,0 +1,191 @@
+//! Append-only audit log of outbound requests.
+//!
+//! Every HTTP request made by the browser, the JS fetcher, the source map prober,
+//! the npm checker and the Telegram notifier is written as one JSON line, so it is
+//! possible to show exactly what was sent to a target.
+
+use crate::types::Result;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Component that made a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditComponent {
+    Browser,
+    JsFetcher,
+    SourceMapProber,
+    NpmChecker,
+    Telegram,
+}
+
+/// A single audit log line.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    /// RFC 3339 UTC timestamp of when the request finished.
+    pub timestamp: String,
+    pub component: AuditComponent,
+    pub method: String,
+    pub url: String,
+    /// HTTP status, if a response was received.
+    pub status: Option<u16>,
+    /// Response body size in bytes, if known.
+    pub bytes: Option<u64>,
+    /// Scan target the request was made on behalf of.
+    pub target: Option<String>,
+    /// Transport error, if the request failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    /// Create an entry timestamped now.
+    pub fn new(component: AuditComponent, method: &str, url: &str) -> Self {
+        Self {
+            timestamp: rfc3339_now(),
+            component,
+            method: method.to_string(),
+            url: url.to_string(),
+            status: None,
+            bytes: None,
+            target: None,
+            error: None,
+        }
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn with_bytes(mut self, bytes: u64) -> Self {
+        self.bytes = Some(bytes);
+        self
+    }
+
+    pub fn with_target(mut self, target: &str) -> Self {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    pub fn with_error(mut self, error: impl ToString) -> Self {
+        self.error = Some(error.to_string());
+        self
+    }
+}
+
+/// JSONL audit log opened in append mode.
+#[derive(Debug)]
+pub struct AuditLog {
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// Open (or create) the audit log at `path` for appending.
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Append an entry. Write failures are logged, never propagated into the scan.
+    pub fn record(&self, entry: AuditEntry) {
+        let mut line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to serialize audit entry: {}", e);
+                return;
+            }
+        };
+        line.push('\n');
+
+        // One write per line so concurrent entries never interleave
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = file.write_all(line.as_bytes()) {
+            warn!("Failed to write audit log: {}", e);
+        }
+    }
+}
+
+/// Current time as an RFC 3339 UTC timestamp with millisecond precision.
+fn rfc3339_now() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format_rfc3339(now.as_secs(), now.subsec_millis())
+}
+
+/// Format seconds since the Unix epoch as `YYYY-MM-DDTHH:MM:SS.mmmZ`.
+fn format_rfc3339(secs: u64, millis: u32) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let (hour, minute, second) = (rem / 3600, (rem % 3600) / 60, rem % 60);
+
+    // Civil date from days since epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year, month, day, hour, minute, second, millis
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_rfc3339() {
+        assert_eq!(format_rfc3339(0, 0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(format_rfc3339(951_825_600, 5), "2000-02-29T12:00:00.005Z");
+        assert_eq!(format_rfc3339(1_760_745_600, 0), "2025-10-18T00:00:00.000Z");
+    }
+
+    #[test]
+    fn test_record_appends_jsonl() {
+        let path = std::env::temp_dir().join(format!("depfused-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let log = AuditLog::open(&path).unwrap();
+        log.record(
+            AuditEntry::new(AuditComponent::JsFetcher, "GET", "https://example.com/app.js")
+                .with_status(200)
+                .with_bytes(1234)
+                .with_target("https://example.com"),
+        );
+        log.record(
+            AuditEntry::new(AuditComponent::SourceMapProber, "GET", "https://example.com/app.js.map")
+                .with_error("connection refused"),
+        );
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["component"], "js_fetcher");
+        assert_eq!(lines[0]["status"], 200);
+        assert_eq!(lines[0]["bytes"], 1234);
+        assert_eq!(lines[0]["target"], "https://example.com");
+        assert!(lines[0].get("error").is_none());
+        assert_eq!(lines[1]["error"], "connection refused");
+        assert!(lines[1]["status"].is_null());
+    }
+}
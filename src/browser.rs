@@ -1,11 +1,11 @@
 //! Browser management: auto-download Chromium, resolve executable paths, host grouping.
 
+use crate::discovery::politeness::host_key;
 use crate::types::{DepfusedError, Result};
 use chromiumoxide::fetcher::{BrowserFetcher, BrowserFetcherOptions};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use tracing::info;
-use url::Url;
 
 /// Returns the managed Chrome installation directory: `~/.depfused/chrome/`
 pub fn managed_chrome_dir() -> Result<PathBuf> {
@@ -73,7 +73,7 @@ pub async fn download_chrome(force: bool) -> Result<PathBuf> {
     Ok(info.executable_path)
 }
 
-/// Group a list of URLs by their host (scheme + host + port).
+/// Group a list of URLs by their host (scheme + host + port, see `politeness::host_key`).
 /// URLs that fail to parse go into a special "" key.
 pub fn group_by_host(urls: &[String]) -> Vec<(String, Vec<String>)> {
     let mut groups: HashMap<String, Vec<String>> = HashMap::new();
@@ -81,14 +81,7 @@ pub fn group_by_host(urls: &[String]) -> Vec<(String, Vec<String>)> {
     let mut order: Vec<String> = Vec::new();
 
     for url_str in urls {
-        let key = Url::parse(url_str)
-            .ok()
-            .and_then(|u| {
-                let host = u.host_str()?.to_string();
-                let port = u.port().map(|p| format!(":{}", p)).unwrap_or_default();
-                Some(format!("{}://{}{}", u.scheme(), host, port))
-            })
-            .unwrap_or_default();
+        let key = host_key(url_str).unwrap_or_default();
 
         let entry = groups.entry(key.clone()).or_default();
         if entry.is_empty() {
@@ -193,6 +186,17 @@ mod tests {
 
         assert_eq!(groups[3].0, "http://localhost:9002");
         assert_eq!(groups[3].1.len(), 1);
+
+        // Explicit default ports and IPv6 hosts use the same keys as the host limiter
+        let urls = vec![
+            "https://example.com:443/a".to_string(),
+            "https://example.com/b".to_string(),
+            "http://[::1]:8080/c".to_string(),
+        ];
+        let groups = group_by_host(&urls);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0], ("https://example.com".to_string(), urls[..2].to_vec()));
+        assert_eq!(groups[1].0, "http://[::1]:8080");
     }
 
     #[test]
@@ -1,8 +1,14 @@
 //! Configuration handling for the scanner.
 
-use crate::types::HttpConfig;
+use crate::audit::AuditLog;
+use crate::discovery::{EmulationProfile, PoolSize, ResourceBlocker};
+use crate::export::SourceExporter;
+use crate::parser::{BundlerRules, FilterRules};
+use crate::scope::ScopeGuard;
+use crate::types::{Confidence, HttpConfig, ResolveOverride};
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 /// High-performance dependency confusion scanner.
 #[derive(Parser, Debug, Clone)]
@@ -25,6 +31,8 @@ pub struct Config {
     pub telegram_chat_id: Option<String>,
 }
 
+// Parsed once at startup, so the size of the scan variant doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug, Clone)]
 pub enum Commands {
     /// Scan targets for dependency confusion vulnerabilities
@@ -45,7 +53,7 @@ pub struct SetupConfig {
 #[derive(Parser, Debug, Clone)]
 pub struct ScanConfig {
     /// Target URL(s) to scan
-    #[arg(required_unless_present = "file")]
+    #[arg(required_unless_present_any = ["file", "js_urls"])]
     pub targets: Vec<String>,
 
     /// Enable verbose output
@@ -56,6 +64,10 @@ pub struct ScanConfig {
     #[arg(short, long)]
     pub file: Option<PathBuf>,
 
+    /// File containing JS asset URLs to fetch directly, bypassing the browser ("-" for stdin)
+    #[arg(long)]
+    pub js_urls: Option<PathBuf>,
+
     /// Enable Telegram notifications for findings
     #[arg(long)]
     pub telegram: bool,
@@ -76,10 +88,14 @@ pub struct ScanConfig {
     #[arg(long, default_value = "3")]
     pub max_retries: u32,
 
-    /// Rate limit (requests per second)
+    /// Rate limit (requests per second, per host)
     #[arg(long, default_value = "10")]
     pub rate_limit: u32,
 
+    /// Maximum concurrent requests to a single host
+    #[arg(long, default_value = "6")]
+    pub max_per_host: usize,
+
     /// Skip npm registry checks (only extract packages)
     #[arg(long)]
     pub skip_npm_check: bool,
@@ -92,14 +108,34 @@ pub struct ScanConfig {
     #[arg(long)]
     pub user_agent: Option<String>,
 
-    /// Minimum confidence level to report (low, medium, high)
-    #[arg(long, default_value = "low")]
-    pub min_confidence: String,
+    /// Extra header sent with every request, e.g. -H 'X-Bug-Bounty: handle' (repeatable)
+    #[arg(short = 'H', long = "header", value_name = "HEADER", value_parser = parse_header)]
+    pub headers: Vec<(String, String)>,
+
+    /// Keep the -H headers off npm registry requests
+    #[arg(long)]
+    pub no_registry_headers: bool,
+
+    /// Pin a hostname to an IP for all requests and the browser, e.g. example.com:443:203.0.113.7 (repeatable)
+    #[arg(long, value_name = "HOST:PORT:IP")]
+    pub resolve: Vec<ResolveOverride>,
+
+    /// Minimum confidence to report: a score from 0 to 100, or low, medium, high
+    #[arg(long, value_name = "SCORE", default_value = "low", value_parser = parse_min_confidence)]
+    pub min_confidence: u8,
 
     /// Number of sites to scan in parallel (default: 1)
     #[arg(long, short = 'p', default_value = "1")]
     pub parallel: usize,
 
+    /// Capture with a pool of N browsers shared by all targets (enables the page pool)
+    #[arg(long, value_name = "N")]
+    pub browsers: Option<usize>,
+
+    /// Concurrent tabs per pooled browser (enables the page pool) [default: 4]
+    #[arg(long, value_name = "M")]
+    pub tabs: Option<usize>,
+
     /// Fast mode: reduce wait times for quicker scans (may miss some lazy-loaded JS)
     #[arg(long)]
     pub fast: bool,
@@ -111,6 +147,59 @@ pub struct ScanConfig {
     /// Path to Chrome/Chromium executable (overrides auto-detection)
     #[arg(long)]
     pub chrome_path: Option<PathBuf>,
+
+    /// Extra source map URL template to probe, e.g. '{dir}maps/{file}.map' (repeatable)
+    #[arg(long, value_name = "TEMPLATE", value_parser = parse_sourcemap_template)]
+    pub map_template: Vec<String>,
+
+    /// Emulation profile to load each target with: desktop, ios or android, optionally
+    /// with a locale (e.g. ios:ja-JP). Repeat to capture with several profiles
+    #[arg(long, value_name = "PROFILE")]
+    pub profile: Vec<EmulationProfile>,
+
+    /// Resource types the browser should not load, e.g. image,font,media,stylesheet
+    #[arg(long, value_name = "TYPES", value_delimiter = ',')]
+    pub block_types: Vec<String>,
+
+    /// Block requests to common analytics and ad hosts in the browser
+    #[arg(long)]
+    pub block_trackers: bool,
+
+    /// Block browser requests to this host or glob, including subdomains (repeatable)
+    #[arg(long, value_name = "HOST")]
+    pub block_host: Vec<String>,
+
+    /// Never block browser requests to this host or glob (repeatable)
+    #[arg(long, value_name = "HOST")]
+    pub allow_host: Vec<String>,
+
+    /// Connect to an existing Chrome over its DevTools WebSocket instead of launching one
+    #[arg(long, value_name = "URL")]
+    pub cdp_url: Option<String>,
+
+    /// Scope file (hosts, globs, CIDRs, !exclusions); out-of-scope requests are never made
+    #[arg(long)]
+    pub scope: Option<PathBuf>,
+
+    /// Append a JSONL record of every outbound request to this file
+    #[arg(long)]
+    pub audit_log: Option<PathBuf>,
+
+    /// Write the original sources embedded in source maps under this directory, one subdirectory per target
+    #[arg(long, value_name = "DIR")]
+    pub export_sources: Option<PathBuf>,
+
+    /// TOML file of allow/deny filter rules applied with the built-in filters (repeatable)
+    #[arg(long, value_name = "FILE")]
+    pub filter_rules: Vec<PathBuf>,
+
+    /// TOML file of bundler extraction rules run with the built-in bundler patterns (repeatable)
+    #[arg(long, value_name = "FILE")]
+    pub bundler_rules: Vec<PathBuf>,
+
+    /// Record every candidate the filters drop, with the rule and source URL ("filtered" in JSON)
+    #[arg(long)]
+    pub explain_filters: bool,
 }
 
 impl Default for ScanConfig {
@@ -119,26 +208,49 @@ impl Default for ScanConfig {
             targets: Vec::new(),
             verbose: false,
             file: None,
+            js_urls: None,
             telegram: false,
             json: false,
             output: None,
             timeout: 30,
             max_retries: 3,
             rate_limit: 10,
+            max_per_host: 6,
             skip_npm_check: false,
             scoped_only: false,
             user_agent: None,
-            min_confidence: "low".to_string(),
+            headers: Vec::new(),
+            no_registry_headers: false,
+            resolve: Vec::new(),
+            min_confidence: 0,
             parallel: 1,
+            browsers: None,
+            tabs: None,
             fast: false,
             quiet: false,
             chrome_path: None,
+            map_template: Vec::new(),
+            profile: Vec::new(),
+            block_types: Vec::new(),
+            block_trackers: false,
+            block_host: Vec::new(),
+            allow_host: Vec::new(),
+            cdp_url: None,
+            scope: None,
+            audit_log: None,
+            export_sources: None,
+            filter_rules: Vec::new(),
+            bundler_rules: Vec::new(),
+            explain_filters: false,
         }
     }
 }
 
 impl ScanConfig {
     /// Get HTTP configuration from scan config.
+    ///
+    /// The scope and audit log are left unset; they are loaded separately with
+    /// [`Self::load_scope`] and [`Self::open_audit_log`].
     pub fn http_config(&self) -> HttpConfig {
         HttpConfig {
             timeout_secs: self.timeout,
@@ -146,6 +258,11 @@ impl ScanConfig {
             user_agent: self.user_agent.clone().unwrap_or_else(|| {
                 "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36".to_string()
             }),
+            max_in_flight_per_host: self.max_per_host,
+            headers: self.headers.clone(),
+            resolve: self.resolve.clone(),
+            scope: None,
+            audit_log: None,
         }
     }
 
@@ -177,35 +294,348 @@ impl ScanConfig {
 
         Ok(normalized)
     }
-}
 
-/// Source map URL variations to try.
-pub fn get_sourcemap_variations(js_url: &str) -> Vec<String> {
-    let mut variations = Vec::new();
+    /// Load JS asset URLs from the `--js-urls` file, or stdin when the path is `-`.
+    ///
+    /// Lines that are empty, commented out, or not http(s) URLs are skipped.
+    pub fn load_js_urls(&self) -> crate::types::Result<Vec<String>> {
+        let Some(ref path) = self.js_urls else {
+            return Ok(Vec::new());
+        };
+
+        let content = if path.as_os_str() == "-" {
+            std::io::read_to_string(std::io::stdin())?
+        } else {
+            std::fs::read_to_string(path)?
+        };
+
+        Ok(parse_js_url_list(&content))
+    }
 
-    // Direct .map suffix
-    variations.push(format!("{}.map", js_url));
+    /// Load the `--scope` file, if one was given.
+    pub fn load_scope(&self) -> crate::types::Result<Option<Arc<ScopeGuard>>> {
+        self.scope
+            .as_deref()
+            .map(|path| ScopeGuard::from_file(path).map(Arc::new))
+            .transpose()
+    }
+
+    /// Build the browser resource blocker, if any blocking option was given.
+    pub fn resource_blocker(&self) -> crate::types::Result<Option<Arc<ResourceBlocker>>> {
+        let blocker = ResourceBlocker::new(&self.block_types)?
+            .with_trackers(self.block_trackers)
+            .with_block_hosts(&self.block_host)
+            .with_allow_hosts(&self.allow_host);
+        Ok((!blocker.is_empty()).then(|| Arc::new(blocker)))
+    }
 
-    // Try without .min if present
-    if js_url.contains(".min.js") {
-        let without_min = js_url.replace(".min.js", ".js");
-        variations.push(format!("{}.map", without_min));
+    /// Open the `--audit-log` file for appending, if one was given.
+    pub fn open_audit_log(&self) -> crate::types::Result<Option<Arc<AuditLog>>> {
+        self.audit_log
+            .as_deref()
+            .map(|path| AuditLog::open(path).map(Arc::new))
+            .transpose()
     }
 
-    // Try adding .min if not present
-    if js_url.ends_with(".js") && !js_url.contains(".min.") {
-        let with_min = js_url.replace(".js", ".min.js");
-        variations.push(format!("{}.map", with_min));
+    /// Load and merge the `--filter-rules` files, if any were given.
+    pub fn load_filter_rules(&self) -> crate::types::Result<Option<FilterRules>> {
+        let mut merged: Option<FilterRules> = None;
+        for path in &self.filter_rules {
+            let rules = FilterRules::from_file(path)?;
+            match merged.as_mut() {
+                Some(merged) => merged.merge(rules),
+                None => merged = Some(rules),
+            }
+        }
+        Ok(merged)
     }
 
-    // Try common sourcemap directories
-    if let Some(filename) = js_url.rsplit('/').next() {
-        if let Some(base_url) = js_url.strip_suffix(filename) {
-            variations.push(format!("{}sourcemaps/{}.map", base_url, filename));
-            variations.push(format!("{}_sourcemaps/{}.map", base_url, filename));
-            variations.push(format!("{}maps/{}.map", base_url, filename));
+    /// Load and merge the `--bundler-rules` files.
+    pub fn load_bundler_rules(&self) -> crate::types::Result<BundlerRules> {
+        let mut merged = BundlerRules::default();
+        for path in &self.bundler_rules {
+            merged.merge(BundlerRules::from_file(path)?);
         }
+        Ok(merged)
+    }
+
+    /// Create the `--export-sources` exporter, if a directory was given.
+    pub fn source_exporter(&self) -> crate::types::Result<Option<Arc<SourceExporter>>> {
+        self.export_sources
+            .as_deref()
+            .map(|dir| SourceExporter::new(dir).map(Arc::new))
+            .transpose()
+    }
+
+    /// Browser pool size, if `--browsers` or `--tabs` was given.
+    pub fn browser_pool_size(&self) -> Option<PoolSize> {
+        const DEFAULT_TABS: usize = 4;
+
+        if self.browsers.is_none() && self.tabs.is_none() {
+            return None;
+        }
+
+        Some(PoolSize {
+            browsers: self.browsers.unwrap_or(1).max(1),
+            tabs_per_browser: self.tabs.unwrap_or(DEFAULT_TABS).max(1),
+        })
+    }
+
+    /// Whether page targets were given (and a browser is therefore needed).
+    pub fn has_page_targets(&self) -> bool {
+        !self.targets.is_empty() || self.file.is_some()
+    }
+}
+
+/// Parse a `Name: value` header argument.
+fn parse_header(arg: &str) -> std::result::Result<(String, String), String> {
+    let (name, value) = arg
+        .split_once(':')
+        .ok_or_else(|| format!("expected 'Name: value', got '{}'", arg))?;
+    let (name, value) = (name.trim(), value.trim());
+
+    reqwest::header::HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| format!("invalid header name '{}'", name))?;
+    reqwest::header::HeaderValue::from_str(value)
+        .map_err(|_| format!("invalid value for header '{}'", name))?;
+
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Parse a newline-separated list of JS asset URLs.
+fn parse_js_url_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|line| {
+            let valid = line.starts_with("http://") || line.starts_with("https://");
+            if !valid {
+                tracing::debug!("Skipping non-HTTP JS URL: {}", line);
+            }
+            valid
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// Built-in source map URL templates, tried in order. See [`expand_sourcemap_template`].
+pub const DEFAULT_SOURCEMAP_TEMPLATES: &[&str] = &[
+    "{dir}{file}.map",
+    "{dir}{stem}.js.map",
+    "{dir}{stem}.min.js.map",
+    "{dir}sourcemaps/{file}.map",
+    "{dir}_sourcemaps/{file}.map",
+    "{dir}maps/{file}.map",
+];
+
+/// Placeholders understood by source map templates.
+const TEMPLATE_PLACEHOLDERS: &[&str] = &["{origin}", "{dir}", "{path}", "{file}", "{stem}"];
+
+/// Expand a source map URL template for a JS URL.
+///
+/// Placeholders, for `https://cdn.example.com/js/app.min.js?v=2`:
+/// - `{origin}`: `https://cdn.example.com`
+/// - `{dir}`: `https://cdn.example.com/js/`
+/// - `{path}`: `/js/` (directory path without the origin)
+/// - `{file}`: `app.min.js`
+/// - `{stem}`: `app` (file without `.min.js`/`.js`; templates using it only apply to `.js` files)
+///
+/// The query string and fragment are dropped.
+pub fn expand_sourcemap_template(template: &str, js_url: &str) -> Option<String> {
+    let parsed = url::Url::parse(js_url).ok()?;
+    let path = parsed.path();
+    let (dir_path, file) = path.rsplit_once('/').map(|(dir, file)| (format!("{}/", dir), file))?;
+    if file.is_empty() {
+        return None;
+    }
+
+    let stem = file.strip_suffix(".js").map(|s| s.strip_suffix(".min").unwrap_or(s));
+    if template.contains("{stem}") && stem.is_none() {
+        return None;
     }
 
-    variations
+    let origin = parsed.origin().ascii_serialization();
+    Some(
+        template
+            .replace("{origin}", &origin)
+            .replace("{dir}", &format!("{}{}", origin, dir_path))
+            .replace("{path}", &dir_path)
+            .replace("{file}", file)
+            .replace("{stem}", stem.unwrap_or_default()),
+    )
+}
+
+/// Parse `--min-confidence`: a 0-100 score, or a tier name for the tier's lowest score.
+fn parse_min_confidence(arg: &str) -> std::result::Result<u8, String> {
+    if let Ok(tier) = arg.parse::<Confidence>() {
+        return Ok(tier.min_score());
+    }
+    match arg.trim().parse::<u8>() {
+        Ok(score) if score <= 100 => Ok(score),
+        _ => Err(format!(
+            "expected a score from 0 to 100 or low, medium, high, got '{}'",
+            arg
+        )),
+    }
+}
+
+/// Validate a `--map-template` argument.
+fn parse_sourcemap_template(arg: &str) -> std::result::Result<String, String> {
+    let template = arg.trim();
+    if !template.starts_with("{origin}") && !template.starts_with("{dir}") {
+        return Err(format!(
+            "template must start with {{origin}} or {{dir}}, got '{}'",
+            template
+        ));
+    }
+
+    // Anything left in braces after removing known placeholders is a typo
+    let stripped = TEMPLATE_PLACEHOLDERS
+        .iter()
+        .fold(template.to_string(), |t, placeholder| t.replace(placeholder, ""));
+    if stripped.contains('{') || stripped.contains('}') {
+        return Err(format!(
+            "unknown placeholder in '{}' (use {})",
+            template,
+            TEMPLATE_PLACEHOLDERS.join(", ")
+        ));
+    }
+
+    Ok(template.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_js_url_list() {
+        let content = "\
+# crawler output
+https://cdn.example.com/app.js
+
+http://example.com/vendor.js
+/relative/chunk.js
+example.com/no-scheme.js
+";
+        let urls = parse_js_url_list(content);
+        assert_eq!(
+            urls,
+            vec![
+                "https://cdn.example.com/app.js".to_string(),
+                "http://example.com/vendor.js".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand_sourcemap_template() {
+        let expand = |js_url: &str| -> Vec<Option<String>> {
+            DEFAULT_SOURCEMAP_TEMPLATES
+                .iter()
+                .map(|template| expand_sourcemap_template(template, js_url))
+                .collect()
+        };
+        assert_eq!(
+            expand("https://example.com/js/app.min.js?v=2"),
+            vec![
+                Some("https://example.com/js/app.min.js.map".to_string()),
+                Some("https://example.com/js/app.js.map".to_string()),
+                Some("https://example.com/js/app.min.js.map".to_string()),
+                Some("https://example.com/js/sourcemaps/app.min.js.map".to_string()),
+                Some("https://example.com/js/_sourcemaps/app.min.js.map".to_string()),
+                Some("https://example.com/js/maps/app.min.js.map".to_string()),
+            ]
+        );
+        // {stem} templates only apply to .js files
+        assert_eq!(expand("https://example.com/app.mjs").iter().flatten().count(), 4);
+        assert_eq!(expand_sourcemap_template("{file}.map", "https://example.com/js/"), None);
+        assert_eq!(
+            expand_sourcemap_template("{origin}/static/maps{path}{file}.map", "https://example.com:8443/js/app.js"),
+            Some("https://example.com:8443/static/maps/js/app.js.map".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_min_confidence() {
+        assert_eq!(parse_min_confidence("low"), Ok(0));
+        assert_eq!(parse_min_confidence("Medium"), Ok(Confidence::MEDIUM_SCORE));
+        assert_eq!(parse_min_confidence("high"), Ok(Confidence::HIGH_SCORE));
+        assert_eq!(parse_min_confidence("55"), Ok(55));
+        assert!(parse_min_confidence("101").is_err());
+        assert!(parse_min_confidence("-5").is_err());
+        assert!(parse_min_confidence("very").is_err());
+    }
+
+    #[test]
+    fn test_parse_sourcemap_template() {
+        assert!(parse_sourcemap_template("{dir}maps/{stem}.map").is_ok());
+        assert!(parse_sourcemap_template("{origin}/__maps{path}{file}.map").is_ok());
+        assert!(parse_sourcemap_template("https://other.net/{file}.map").is_err());
+        assert!(parse_sourcemap_template("{dir}{name}.map").is_err());
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("X-Bug-Bounty: handle").unwrap(),
+            ("X-Bug-Bounty".to_string(), "handle".to_string())
+        );
+        assert_eq!(
+            parse_header("Authorization:Bearer a:b").unwrap(),
+            ("Authorization".to_string(), "Bearer a:b".to_string())
+        );
+        assert!(parse_header("no-colon").is_err());
+        assert!(parse_header("Bad Name: x").is_err());
+    }
+
+    #[test]
+    fn test_parse_resolve() {
+        let entry: ResolveOverride = "Origin.Example.com:443:203.0.113.7".parse().unwrap();
+        assert_eq!(entry.host, "origin.example.com");
+        assert_eq!(entry.port, 443);
+        assert_eq!(entry.host_resolver_rule(), "MAP origin.example.com 203.0.113.7");
+
+        let v6: ResolveOverride = "staging.example.com:8443:[2001:db8::1]".parse().unwrap();
+        assert_eq!(v6.host_resolver_rule(), "MAP staging.example.com [2001:db8::1]");
+
+        assert!("example.com:443".parse::<ResolveOverride>().is_err());
+        assert!("example.com:https:1.2.3.4".parse::<ResolveOverride>().is_err());
+        assert!("example.com:443:not-an-ip".parse::<ResolveOverride>().is_err());
+    }
+
+    #[test]
+    fn test_resource_blocker() {
+        let mut config = ScanConfig::default();
+        assert!(config.resource_blocker().unwrap().is_none());
+
+        config.allow_host = vec!["cdn.example.com".into()];
+        assert!(config.resource_blocker().unwrap().is_none());
+
+        config.block_types = vec!["image".into(), "font".into()];
+        assert!(config.resource_blocker().unwrap().is_some());
+
+        config.block_types = vec!["script".into()];
+        assert!(config.resource_blocker().is_err());
+    }
+
+    #[test]
+    fn test_browser_pool_size() {
+        let mut config = ScanConfig::default();
+        assert_eq!(config.browser_pool_size(), None);
+
+        config.tabs = Some(8);
+        assert_eq!(
+            config.browser_pool_size(),
+            Some(PoolSize { browsers: 1, tabs_per_browser: 8 })
+        );
+
+        config.tabs = None;
+        config.browsers = Some(3);
+        assert_eq!(
+            config.browser_pool_size(),
+            Some(PoolSize { browsers: 3, tabs_per_browser: 4 })
+        );
+    }
 }
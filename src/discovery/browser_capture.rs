@@ -5,11 +5,25 @@
 //!
 //! Requires: Chrome or Chromium browser installed
 
-use crate::discovery::js_fetcher::{JsFetcher, extract_sourcemap_url};
-use crate::types::{DepfusedError, JsFile, JsSource, Result};
+use crate::audit::{AuditComponent, AuditEntry, AuditLog};
+use crate::discovery::js_fetcher::{JsFetcher, find_sourcemap_url, recorded_headers};
+use crate::discovery::emulation::EmulationProfile;
+use crate::discovery::resource_blocker::ResourceBlocker;
+use crate::scope::ScopeGuard;
+use crate::types::{DepfusedError, JsFile, JsSource, ResolveOverride, Result};
 use chromiumoxide::browser::{Browser, BrowserConfig};
+use chromiumoxide::error::CdpError;
+use chromiumoxide::handler::HandlerConfig;
+use chromiumoxide::listeners::EventStream;
+use chromiumoxide::Page;
+use chromiumoxide::cdp::browser_protocol::fetch::{
+    self, ContinueRequestParams, EventRequestPaused, FailRequestParams, RequestPattern,
+    RequestStage,
+};
 use chromiumoxide::cdp::browser_protocol::network::{
-    EventResponseReceived, GetResponseBodyParams, ResourceType,
+    ErrorReason, EventLoadingFailed, EventLoadingFinished, EventRequestWillBeSent,
+    EventResponseReceived, GetResponseBodyParams, Headers, RequestId, ResourceType,
+    SetExtraHttpHeadersParams,
 };
 use futures::StreamExt;
 use std::collections::HashMap;
@@ -18,8 +32,9 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::LazyLock;
 use std::sync::Mutex as StdMutex;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 
 /// Counter for generating unique browser profile directories
@@ -30,13 +45,35 @@ static BROWSER_INSTANCE_COUNTER: AtomicU64 = AtomicU64::new(0);
 static CHROME_TEMP_DIRS: LazyLock<StdMutex<Vec<PathBuf>>> =
     LazyLock::new(|| StdMutex::new(Vec::new()));
 
-fn register_temp_dir(dir: &Path) {
+/// A running browser and the task driving its CDP handler.
+pub(super) type BrowserInstance = (Browser, JoinHandle<()>);
+
+/// Drive a browser's CDP handler on a background task until the connection ends.
+fn spawn_handler(
+    mut handler: impl futures::Stream<Item = std::result::Result<(), CdpError>> + Send + Unpin + 'static,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while let Some(event) = handler.next().await {
+            if event.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Unique profile directory for a new browser instance.
+pub(super) fn new_profile_dir() -> PathBuf {
+    let instance_id = BROWSER_INSTANCE_COUNTER.fetch_add(1, Ordering::SeqCst);
+    std::env::temp_dir().join(format!("depfused-browser-{}-{}", std::process::id(), instance_id))
+}
+
+pub(super) fn register_temp_dir(dir: &Path) {
     if let Ok(mut dirs) = CHROME_TEMP_DIRS.lock() {
         dirs.push(dir.to_path_buf());
     }
 }
 
-fn unregister_temp_dir(dir: &Path) {
+pub(super) fn unregister_temp_dir(dir: &Path) {
     if let Ok(mut dirs) = CHROME_TEMP_DIRS.lock() {
         dirs.retain(|d| d != dir);
     }
@@ -44,7 +81,7 @@ fn unregister_temp_dir(dir: &Path) {
 
 /// Kill all Chrome processes whose command line contains the given directory path.
 /// Uses pgrep to find PIDs and kill -9 to terminate them.
-fn kill_chrome_for_dir(dir: &Path) {
+pub(super) fn kill_chrome_for_dir(dir: &Path) {
     let dir_str = dir.display().to_string();
 
     let output = match std::process::Command::new("pgrep")
@@ -93,6 +130,55 @@ pub fn kill_all_chrome() {
         .status();
 }
 
+/// Event streams a page capture reads, subscribed before navigation.
+struct PageListeners {
+    responses: EventStream<EventResponseReceived>,
+    /// Requests paused for scope and blocking checks, if any are paused.
+    paused: Option<EventStream<EventRequestPaused>>,
+    /// Request lifecycle events, with an audit log.
+    audit: Option<AuditListeners>,
+}
+
+/// The request lifecycle events written to the audit log.
+struct AuditListeners {
+    sent: EventStream<EventRequestWillBeSent>,
+    received: EventStream<EventResponseReceived>,
+    finished: EventStream<EventLoadingFinished>,
+    failed: EventStream<EventLoadingFailed>,
+}
+
+impl AuditListeners {
+    async fn subscribe(page: &Page) -> Result<Self> {
+        let listener_error = |e: CdpError| {
+            DepfusedError::ConfigError(format!("Failed to set up request auditing: {}", e))
+        };
+        Ok(Self {
+            sent: page.event_listener().await.map_err(listener_error)?,
+            received: page.event_listener().await.map_err(listener_error)?,
+            finished: page.event_listener().await.map_err(listener_error)?,
+            failed: page.event_listener().await.map_err(listener_error)?,
+        })
+    }
+}
+
+/// Background task writing a page's requests to the audit log.
+struct AuditTask {
+    handle: tokio::task::JoinHandle<()>,
+    stop: tokio::sync::oneshot::Sender<()>,
+}
+
+impl AuditTask {
+    /// Stop listening and wait for pending requests to be written as incomplete.
+    async fn finish(self) {
+        let _ = self.stop.send(());
+        let abort = self.handle.abort_handle();
+        if tokio::time::timeout(Duration::from_secs(2), self.handle).await.is_err() {
+            debug!("Audit task did not finish in time, aborting");
+            abort.abort();
+        }
+    }
+}
+
 /// Browser-based network capture using Chrome DevTools Protocol.
 pub struct BrowserCapture {
     /// Timeout for page load in seconds
@@ -103,6 +189,20 @@ pub struct BrowserCapture {
     fast_mode: bool,
     /// Explicit path to Chrome/Chromium executable
     chrome_executable: Option<std::path::PathBuf>,
+    /// Scope enforced on every request the page makes
+    scope: Option<Arc<ScopeGuard>>,
+    /// Audit log every request the page makes is recorded to
+    audit_log: Option<Arc<AuditLog>>,
+    /// Extra static headers sent with every page request
+    extra_headers: Vec<(String, String)>,
+    /// Hostnames pinned to specific addresses (`--host-resolver-rules`)
+    resolve: Vec<ResolveOverride>,
+    /// DevTools endpoint of a browser we connect to instead of launching one
+    cdp_url: Option<String>,
+    /// Resource types and hosts the page is not allowed to load
+    blocker: Option<Arc<ResourceBlocker>>,
+    /// Device/locale profiles each target is loaded with (none: browser defaults)
+    profiles: Vec<EmulationProfile>,
 }
 
 impl BrowserCapture {
@@ -113,6 +213,13 @@ impl BrowserCapture {
             headless,
             fast_mode: false,
             chrome_executable: None,
+            scope: None,
+            audit_log: None,
+            extra_headers: Vec::new(),
+            resolve: Vec::new(),
+            cdp_url: None,
+            blocker: None,
+            profiles: Vec::new(),
         }
     }
 
@@ -128,6 +235,48 @@ impl BrowserCapture {
         self
     }
 
+    /// Block page requests that fall outside the given scope.
+    pub fn with_scope(mut self, scope: Option<Arc<ScopeGuard>>) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Record every request the page makes in the given audit log.
+    pub fn with_audit_log(mut self, audit_log: Option<Arc<AuditLog>>) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
+
+    /// Send extra static headers with every page request.
+    pub fn with_extra_headers(mut self, headers: Vec<(String, String)>) -> Self {
+        self.extra_headers = headers;
+        self
+    }
+
+    /// Pin hostnames to specific addresses, matching the reqwest clients.
+    pub fn with_resolve(mut self, resolve: Vec<ResolveOverride>) -> Self {
+        self.resolve = resolve;
+        self
+    }
+
+    /// Connect to an existing browser at this DevTools endpoint instead of launching one.
+    pub fn with_cdp_url(mut self, cdp_url: Option<String>) -> Self {
+        self.cdp_url = cdp_url;
+        self
+    }
+
+    /// Block resource types and hosts while capturing.
+    pub fn with_resource_blocker(mut self, blocker: Option<Arc<ResourceBlocker>>) -> Self {
+        self.blocker = blocker;
+        self
+    }
+
+    /// Load every target once per emulation profile.
+    pub fn with_profiles(mut self, profiles: Vec<EmulationProfile>) -> Self {
+        self.profiles = profiles;
+        self
+    }
+
     /// Build a BrowserConfig with the given temp directory.
     fn build_browser_config(
         &self,
@@ -149,13 +298,19 @@ impl BrowserCapture {
             .no_sandbox()
             .viewport(None);
 
+        if !self.resolve.is_empty() {
+            let rules: Vec<String> = self.resolve.iter().map(|r| r.host_resolver_rule()).collect();
+            config_builder =
+                config_builder.arg(format!("--host-resolver-rules={}", rules.join(", ")));
+        }
+
         config_builder.build().map_err(|e| {
             DepfusedError::ConfigError(format!("Failed to build browser config: {}", e))
         })
     }
 
     /// Launch a browser, with auto-download fallback if no Chrome is found.
-    async fn launch_browser(
+    pub(super) async fn launch_browser(
         &self,
         temp_dir: &std::path::Path,
     ) -> Result<(Browser, impl futures::Stream<Item = std::result::Result<(), chromiumoxide::error::CdpError>>)> {
@@ -173,11 +328,10 @@ impl BrowserCapture {
             Ok(pair) => Ok(pair),
             Err(e) => {
                 // If we had an explicit or resolved chrome path, don't try auto-download
-                if chrome_exe.is_some() {
+                if let Some(exe) = chrome_exe {
                     return Err(DepfusedError::ConfigError(format!(
                         "Failed to launch browser with Chrome at {:?}: {}",
-                        chrome_exe.unwrap(),
-                        e
+                        exe, e
                     )));
                 }
 
@@ -197,14 +351,69 @@ impl BrowserCapture {
         }
     }
 
+    /// Start a browser for capturing: connect to the `--cdp-url` endpoint if one is
+    /// set, otherwise launch our own. The CDP handler runs on a background task.
+    pub(super) async fn start_browser(&self, temp_dir: &Path) -> Result<BrowserInstance> {
+        let Some(ref cdp_url) = self.cdp_url else {
+            let (browser, handler) = self.launch_browser(temp_dir).await?;
+            return Ok((browser, spawn_handler(handler)));
+        };
+
+        info!("Connecting to remote browser at {}", cdp_url);
+        let handler_config = HandlerConfig {
+            viewport: None,
+            ..Default::default()
+        };
+        let (mut browser, handler) = Browser::connect_with_config(cdp_url.clone(), handler_config)
+            .await
+            .map_err(|e| {
+                DepfusedError::ConfigError(format!("Failed to connect to {}: {}", cdp_url, e))
+            })?;
+        let handler_task = spawn_handler(handler);
+
+        // Keep our pages, cookies and cache apart from other users of the shared browser
+        if let Err(e) = browser.start_incognito_context().await {
+            handler_task.abort();
+            return Err(DepfusedError::ConfigError(format!(
+                "Failed to create browser context on {}: {}",
+                cdp_url, e
+            )));
+        }
+
+        Ok((browser, handler_task))
+    }
+
+    /// Shut down a browser started with [`Self::start_browser`].
+    ///
+    /// A browser we launched is killed. A remote browser is never killed; only the
+    /// context we created is disposed, which closes every page we opened in it.
+    pub(super) async fn release_browser(&self, instance: BrowserInstance, temp_dir: &Path) {
+        let (mut browser, handler_task) = instance;
+
+        if self.cdp_url.is_some() {
+            match tokio::time::timeout(Duration::from_secs(5), browser.quit_incognito_context()).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => debug!("Failed to dispose remote browser context: {}", e),
+                Err(_) => debug!("Timed out disposing remote browser context"),
+            }
+            drop(browser);
+            handler_task.abort();
+            return;
+        }
+
+        // Drop browser handle, abort handler, then SIGKILL Chrome processes
+        drop(browser);
+        handler_task.abort();
+        kill_chrome_for_dir(temp_dir);
+    }
+
     /// Capture JavaScript files from a URL using headless browser.
     pub async fn capture(&self, url: &str) -> Result<Vec<JsFile>> {
         info!("Capturing with browser (native Rust): {}", url);
 
         // Create unique temporary directory for this browser instance
         // This allows multiple browser instances to run in parallel without conflicts
-        let instance_id = BROWSER_INSTANCE_COUNTER.fetch_add(1, Ordering::SeqCst);
-        let temp_dir = std::env::temp_dir().join(format!("depfused-browser-{}-{}", std::process::id(), instance_id));
+        let temp_dir = new_profile_dir();
 
         // Ensure directory exists
         if let Err(e) = std::fs::create_dir_all(&temp_dir) {
@@ -214,9 +423,9 @@ impl BrowserCapture {
         // Register temp dir so signal handler can find & kill Chrome
         register_temp_dir(&temp_dir);
 
-        // Launch browser (with auto-download fallback)
-        let (browser, mut handler) = match self.launch_browser(&temp_dir).await {
-            Ok(pair) => pair,
+        // Launch browser (with auto-download fallback) or connect to the remote one
+        let instance = match self.start_browser(&temp_dir).await {
+            Ok(instance) => instance,
             Err(e) => {
                 unregister_temp_dir(&temp_dir);
                 let _ = std::fs::remove_dir_all(&temp_dir);
@@ -224,18 +433,9 @@ impl BrowserCapture {
             }
         };
 
-        // Spawn handler task
-        let handler_task = tokio::spawn(async move {
-            while let Some(event) = handler.next().await {
-                if event.is_err() {
-                    break;
-                }
-            }
-        });
-
         // Capture JS files (with hard timeout to prevent hangs)
-        let page_timeout = Duration::from_secs(self.timeout_secs + 15);
-        let result = match tokio::time::timeout(page_timeout, self.capture_js_files(&browser, url)).await {
+        let page_timeout = self.page_timeout();
+        let result = match tokio::time::timeout(page_timeout, self.capture_js_files(&instance.0, url)).await {
             Ok(r) => r,
             Err(_) => {
                 warn!("Hard timeout after {}s for {}, skipping", page_timeout.as_secs(), url);
@@ -243,10 +443,7 @@ impl BrowserCapture {
             }
         };
 
-        // Clean up: drop browser handle, abort handler, then SIGKILL Chrome processes
-        drop(browser);
-        handler_task.abort();
-        kill_chrome_for_dir(&temp_dir);
+        self.release_browser(instance, &temp_dir).await;
 
         // Remove temp dir synchronously (small I/O, no need for background task)
         let _ = std::fs::remove_dir_all(&temp_dir);
@@ -264,9 +461,7 @@ impl BrowserCapture {
 
         info!("Capturing {} URLs with shared browser instance", urls.len());
 
-        let instance_id = BROWSER_INSTANCE_COUNTER.fetch_add(1, Ordering::SeqCst);
-        let temp_dir = std::env::temp_dir()
-            .join(format!("depfused-browser-{}-{}", std::process::id(), instance_id));
+        let temp_dir = new_profile_dir();
 
         if let Err(e) = std::fs::create_dir_all(&temp_dir) {
             debug!("Failed to create temp dir {:?}: {}", temp_dir, e);
@@ -278,9 +473,8 @@ impl BrowserCapture {
         let mut results = Vec::with_capacity(urls.len());
 
         // Launch browser
-        let browser_result = self.launch_browser(&temp_dir).await;
-        let (browser, mut handler) = match browser_result {
-            Ok(pair) => pair,
+        let mut current = match self.start_browser(&temp_dir).await {
+            Ok(instance) => Some(instance),
             Err(e) => {
                 // Return error for all URLs
                 for url in urls {
@@ -298,40 +492,21 @@ impl BrowserCapture {
             }
         };
 
-        let handler_task = tokio::spawn(async move {
-            while let Some(event) = handler.next().await {
-                if event.is_err() {
-                    break;
-                }
-            }
-        });
-
         // Process each URL with the shared browser, restarting every 50 pages
         const RESTART_EVERY: usize = 50;
         let mut pages_used = 0;
-        let mut current_browser = Some(browser);
-        let mut current_handler_task = handler_task;
 
         for url in urls {
             // Restart browser if we've processed too many pages (prevent memory buildup)
             if pages_used > 0 && pages_used % RESTART_EVERY == 0 {
                 info!("Restarting browser after {} pages to free memory", pages_used);
-                current_browser.take(); // drops the old browser
-                current_handler_task.abort();
-                // Explicitly kill old Chrome before launching new one
-                kill_chrome_for_dir(&temp_dir);
-
-                match self.launch_browser(&temp_dir).await {
-                    Ok((new_browser, mut new_handler)) => {
-                        current_handler_task = tokio::spawn(async move {
-                            while let Some(event) = new_handler.next().await {
-                                if event.is_err() {
-                                    break;
-                                }
-                            }
-                        });
-                        current_browser = Some(new_browser);
-                    }
+                // Shut down the old browser before launching the new one
+                if let Some(instance) = current.take() {
+                    self.release_browser(instance, &temp_dir).await;
+                }
+
+                match self.start_browser(&temp_dir).await {
+                    Ok(instance) => current = Some(instance),
                     Err(e) => {
                         results.push((
                             url.to_string(),
@@ -345,28 +520,20 @@ impl BrowserCapture {
                 }
             }
 
-            if let Some(ref browser) = current_browser {
-                let page_timeout = Duration::from_secs(self.timeout_secs + 15);
+            if let Some((ref browser, _)) = current {
+                let page_timeout = self.page_timeout();
                 let result = match tokio::time::timeout(page_timeout, self.capture_js_files(browser, url)).await {
                     Ok(r) => r,
                     Err(_) => {
                         warn!("Hard timeout after {}s for {}, killing browser and restarting", page_timeout.as_secs(), url);
                         // Kill the hung browser — Chrome may be spinning CPU
-                        current_browser.take();
-                        current_handler_task.abort();
-                        // Explicitly kill Chrome processes before restarting
-                        kill_chrome_for_dir(&temp_dir);
-
-                        match self.launch_browser(&temp_dir).await {
-                            Ok((new_browser, mut new_handler)) => {
-                                current_handler_task = tokio::spawn(async move {
-                                    while let Some(event) = new_handler.next().await {
-                                        if event.is_err() {
-                                            break;
-                                        }
-                                    }
-                                });
-                                current_browser = Some(new_browser);
+                        if let Some(instance) = current.take() {
+                            self.release_browser(instance, &temp_dir).await;
+                        }
+
+                        match self.start_browser(&temp_dir).await {
+                            Ok(instance) => {
+                                current = Some(instance);
                                 pages_used = 0;
                             }
                             Err(e) => {
@@ -388,10 +555,10 @@ impl BrowserCapture {
             }
         }
 
-        // Cleanup: drop browser, abort handler, then SIGKILL Chrome
-        drop(current_browser);
-        current_handler_task.abort();
-        kill_chrome_for_dir(&temp_dir);
+        // Cleanup: shut down the browser
+        if let Some(instance) = current.take() {
+            self.release_browser(instance, &temp_dir).await;
+        }
 
         // Remove temp dir synchronously
         let _ = std::fs::remove_dir_all(&temp_dir);
@@ -400,22 +567,88 @@ impl BrowserCapture {
         results
     }
 
-    /// Capture JavaScript files from the page.
-    async fn capture_js_files(&self, browser: &Browser, url: &str) -> Result<Vec<JsFile>> {
+    /// Hard per-target timeout after which the browser is considered hung.
+    /// Each emulation profile loads the page once.
+    pub(super) fn page_timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs + 15) * self.profiles.len().max(1) as u32
+    }
+
+    /// Capture JavaScript files from the page, once per emulation profile.
+    ///
+    /// Files served identically to several profiles are merged and list every
+    /// profile that loaded them. A failing profile is logged and skipped; the
+    /// capture only fails when every profile does.
+    pub(super) async fn capture_js_files(&self, browser: &Browser, url: &str) -> Result<Vec<JsFile>> {
+        if self.profiles.is_empty() {
+            return self.capture_page(browser, url, None).await;
+        }
+
+        // Same URL with different content (e.g. a mobile bundle) stays a separate file
+        let mut merged: HashMap<(String, String), JsFile> = HashMap::new();
+        let mut last_error = None;
+        let mut captured_any = false;
+        for profile in &self.profiles {
+            debug!("Capturing {} with profile {}", url, profile.name());
+            let files = match self.capture_page(browser, url, Some(profile)).await {
+                Ok(files) => files,
+                Err(e) => {
+                    warn!("Capture of {} with profile {} failed, skipping it: {}", url, profile.name(), e);
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            captured_any = true;
+            for file in files {
+                let key = (file.url.clone(), file.content_hash.clone());
+                match merged.get_mut(&key) {
+                    Some(existing) => existing.profiles.extend(file.profiles),
+                    None => {
+                        merged.insert(key, file);
+                    }
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if !captured_any => Err(e),
+            _ => Ok(merged.into_values().collect()),
+        }
+    }
+
+    /// Load the page once, optionally under an emulation profile, and capture its JS.
+    async fn capture_page(
+        &self,
+        browser: &Browser,
+        url: &str,
+        profile: Option<&EmulationProfile>,
+    ) -> Result<Vec<JsFile>> {
         // Create new page
         let page = browser.new_page("about:blank").await.map_err(|e| {
             DepfusedError::ConfigError(format!("Failed to create page: {}", e))
         })?;
 
+        // Everything that can fail is set up before any task is spawned, so a
+        // failure only has to close the page
+        let listeners = match self.prepare_page(&page, profile).await {
+            Ok(listeners) => listeners,
+            Err(e) => {
+                if let Err(close) = page.close().await {
+                    debug!("Failed to close page for {}: {}", url, close);
+                }
+                return Err(e);
+            }
+        };
+        let PageListeners {
+            responses: mut response_events,
+            paused,
+            audit,
+        } = listeners;
+        let profiles: Vec<String> = profile.map(EmulationProfile::name).into_iter().collect();
+
         // Storage for captured JS
         let js_files: Arc<Mutex<HashMap<String, JsFile>>> = Arc::new(Mutex::new(HashMap::new()));
         let js_files_clone = js_files.clone();
 
-        // Set up network event listener
-        let mut response_events = page.event_listener::<EventResponseReceived>().await.map_err(|e| {
-            DepfusedError::ConfigError(format!("Failed to set up event listener: {}", e))
-        })?;
-
         // Clone page for the event handler
         let page_clone = page.clone();
 
@@ -452,7 +685,17 @@ impl BrowserCapture {
                         };
 
                         if !content.is_empty() {
-                            let source_map_url = extract_sourcemap_url(&content, &url);
+                            let headers = response
+                                .headers
+                                .inner()
+                                .as_object()
+                                .map(|map| {
+                                    recorded_headers(map.iter().filter_map(|(name, value)| {
+                                        Some((name.as_str(), value.as_str()?))
+                                    }))
+                                })
+                                .unwrap_or_default();
+                            let source_map_url = find_sourcemap_url(&content, &url, &headers);
                             let content_hash = JsFetcher::hash_content(&content);
                             let js_file = JsFile {
                                 url: url.clone(),
@@ -460,6 +703,9 @@ impl BrowserCapture {
                                 content_hash,
                                 source: JsSource::Browser,
                                 source_map_url,
+                                profiles: profiles.clone(),
+                                status: Some(response.status as u16),
+                                headers,
                             };
 
                             let mut files = js_files_clone.lock().await;
@@ -470,8 +716,24 @@ impl BrowserCapture {
             }
         });
 
+        // Fail the paused out-of-scope and blocked requests, continue the rest
+        let intercept_task = paused.map(|paused| {
+            Self::intercept_requests(
+                &page,
+                paused,
+                self.scope.clone(),
+                self.blocker.clone(),
+                url,
+            )
+        });
+
+        let audit_task = audit
+            .zip(self.audit_log.clone())
+            .map(|(listeners, audit_log)| Self::audit_requests(listeners, audit_log, url));
+
         // Navigate to URL with timeout
         debug!("Navigating to: {}", url);
+        let load_start = Instant::now();
         let navigate_result = tokio::time::timeout(
             Duration::from_secs(self.timeout_secs),
             page.goto(url)
@@ -510,8 +772,24 @@ impl BrowserCapture {
             }
         }
 
+        if let Some(ref blocker) = self.blocker {
+            blocker.record_page_load(url, load_start.elapsed().as_secs_f64());
+        }
+
         // Stop capture task
         capture_task.abort();
+        if let Some(task) = intercept_task {
+            task.abort();
+        }
+        // Let the audit task write what is still pending instead of dropping it
+        if let Some(task) = audit_task {
+            task.finish().await;
+        }
+
+        // Close the tab so pooled browsers don't accumulate pages
+        if let Err(e) = page.close().await {
+            debug!("Failed to close page for {}: {}", url, e);
+        }
 
         // Collect results
         let files = js_files.lock().await;
@@ -520,6 +798,222 @@ impl BrowserCapture {
         info!("Captured {} JavaScript files from {}", result.len(), url);
         Ok(result)
     }
+
+    /// Apply `profile` and the extra headers, and subscribe to the events a
+    /// capture needs, pausing requests when scope or blocking rules apply.
+    async fn prepare_page(&self, page: &Page, profile: Option<&EmulationProfile>) -> Result<PageListeners> {
+        if let Some(profile) = profile {
+            profile.apply(page).await?;
+        }
+
+        if !self.extra_headers.is_empty() {
+            let headers: serde_json::Map<String, serde_json::Value> = self
+                .extra_headers
+                .iter()
+                .map(|(name, value)| (name.clone(), serde_json::Value::String(value.clone())))
+                .collect();
+            page.execute(SetExtraHttpHeadersParams::new(Headers::new(headers)))
+                .await
+                .map_err(|e| DepfusedError::BrowserError(format!("Failed to set extra headers: {}", e)))?;
+        }
+
+        let responses = page.event_listener::<EventResponseReceived>().await.map_err(|e| {
+            DepfusedError::ConfigError(format!("Failed to set up event listener: {}", e))
+        })?;
+        let paused = Self::enable_interception(page, self.scope.is_some(), self.blocker.as_deref()).await?;
+        let audit = match self.audit_log {
+            Some(_) => Some(AuditListeners::subscribe(page).await?),
+            None => None,
+        };
+
+        Ok(PageListeners {
+            responses,
+            paused,
+            audit,
+        })
+    }
+
+    /// Record every network request the page makes in the audit log.
+    ///
+    /// Entries are written once loading finishes or fails, so they carry the final
+    /// status and the number of bytes received. Requests still pending when the
+    /// task is finished are written as incomplete.
+    fn audit_requests(listeners: AuditListeners, audit_log: Arc<AuditLog>, target: &str) -> AuditTask {
+        let AuditListeners {
+            mut sent,
+            mut received,
+            mut finished,
+            mut failed,
+        } = listeners;
+        let target = target.to_string();
+        let (stop_tx, mut stop_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let handle = tokio::spawn(async move {
+            // Requests in flight: method, URL and status once the response arrives
+            let mut pending: HashMap<RequestId, (String, String, Option<u16>)> = HashMap::new();
+            let entry = |method: &str, url: &str| {
+                AuditEntry::new(AuditComponent::Browser, method, url).with_target(&target)
+            };
+
+            loop {
+                tokio::select! {
+                    // Events already delivered are handled before the stop signal
+                    biased;
+                    Some(event) = sent.next() => {
+                        if event.request.url.starts_with("data:") {
+                            continue;
+                        }
+                        // A redirect reuses the request id; the previous hop is complete
+                        if let Some(ref redirect) = event.redirect_response {
+                            if let Some((method, url, _)) = pending.remove(&event.request_id) {
+                                audit_log.record(entry(&method, &url).with_status(redirect.status as u16));
+                            }
+                        }
+                        pending.insert(
+                            event.request_id.clone(),
+                            (event.request.method.clone(), event.request.url.clone(), None),
+                        );
+                    }
+                    Some(event) = received.next() => {
+                        if let Some(request) = pending.get_mut(&event.request_id) {
+                            request.2 = Some(event.response.status as u16);
+                        }
+                    }
+                    Some(event) = finished.next() => {
+                        if let Some((method, url, status)) = pending.remove(&event.request_id) {
+                            let mut audit_entry = entry(&method, &url)
+                                .with_bytes(event.encoded_data_length as u64);
+                            if let Some(status) = status {
+                                audit_entry = audit_entry.with_status(status);
+                            }
+                            audit_log.record(audit_entry);
+                        }
+                    }
+                    Some(event) = failed.next() => {
+                        if let Some((method, url, status)) = pending.remove(&event.request_id) {
+                            // Requests blocked before leaving the browser were never sent
+                            if event.blocked_reason.is_some()
+                                || event.error_text == "net::ERR_BLOCKED_BY_CLIENT"
+                            {
+                                continue;
+                            }
+                            let mut audit_entry = entry(&method, &url).with_error(&event.error_text);
+                            if let Some(status) = status {
+                                audit_entry = audit_entry.with_status(status);
+                            }
+                            audit_log.record(audit_entry);
+                        }
+                    }
+                    _ = &mut stop_rx => break,
+                    else => break,
+                }
+            }
+
+            for (method, url, status) in pending.into_values() {
+                let mut audit_entry = entry(&method, &url).with_error("incomplete");
+                if let Some(status) = status {
+                    audit_entry = audit_entry.with_status(status);
+                }
+                audit_log.record(audit_entry);
+            }
+        });
+
+        AuditTask { handle, stop: stop_tx }
+    }
+
+    /// Pause page requests via the CDP Fetch domain when scope or blocking rules
+    /// apply, returning the paused request events.
+    ///
+    /// Requests are paused before they are sent: every request when scope or host
+    /// rules apply, otherwise only those of blocked resource types.
+    async fn enable_interception(
+        page: &Page,
+        scoped: bool,
+        blocker: Option<&ResourceBlocker>,
+    ) -> Result<Option<EventStream<EventRequestPaused>>> {
+        let patterns: Vec<RequestPattern> = if scoped || blocker.is_some_and(|b| b.has_host_rules()) {
+            vec![RequestPattern::builder().url_pattern("*").build()]
+        } else {
+            blocker
+                .iter()
+                .flat_map(|b| b.blocked_types())
+                .map(|resource_type| {
+                    RequestPattern::builder()
+                        .url_pattern("*")
+                        .resource_type(resource_type.clone())
+                        .request_stage(RequestStage::Request)
+                        .build()
+                })
+                .collect()
+        };
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+
+        let paused_events = page.event_listener::<EventRequestPaused>().await.map_err(|e| {
+            DepfusedError::ConfigError(format!("Failed to set up request interception: {}", e))
+        })?;
+
+        page.execute(fetch::EnableParams::builder().patterns(patterns).build())
+            .await
+            .map_err(|e| {
+                DepfusedError::ConfigError(format!("Failed to enable request interception: {}", e))
+            })?;
+
+        Ok(Some(paused_events))
+    }
+
+    /// Fail the paused requests outside `scope` or rejected by `blocker`,
+    /// recording them against `target`, and continue the rest.
+    fn intercept_requests(
+        page: &Page,
+        mut paused_events: EventStream<EventRequestPaused>,
+        scope: Option<Arc<ScopeGuard>>,
+        blocker: Option<Arc<ResourceBlocker>>,
+        target: &str,
+    ) -> JoinHandle<()> {
+        let page = page.clone();
+        let target = target.to_string();
+
+        tokio::spawn(async move {
+            while let Some(event) = paused_events.next().await {
+                let url = &event.request.url;
+                let block = if scope.as_ref().is_some_and(|scope| !scope.check(url, &target)) {
+                    true
+                } else if let Some((blocker, rule)) =
+                    blocker.as_ref().and_then(|b| Some((b, b.denied_host(url)?)))
+                {
+                    blocker.record_host(&target, rule, url);
+                    true
+                } else if let Some(blocker) =
+                    blocker.as_ref().filter(|b| b.blocks_type(&event.resource_type, url))
+                {
+                    blocker.record_type(&target, &event.resource_type, url);
+                    true
+                } else {
+                    false
+                };
+
+                // Every paused request must be continued or failed, or the page hangs
+                let outcome = if block {
+                    page.execute(FailRequestParams::new(
+                        event.request_id.clone(),
+                        ErrorReason::BlockedByClient,
+                    ))
+                    .await
+                    .map(|_| ())
+                } else {
+                    page.execute(ContinueRequestParams::new(event.request_id.clone()))
+                        .await
+                        .map(|_| ())
+                };
+
+                if let Err(e) = outcome {
+                    debug!("Failed to resolve paused request {}: {}", url, e);
+                }
+            }
+        })
+    }
 }
 
 impl Default for BrowserCapture {
@@ -529,6 +1023,13 @@ impl Default for BrowserCapture {
             headless: true,
             fast_mode: false,
             chrome_executable: None,
+            scope: None,
+            audit_log: None,
+            extra_headers: Vec::new(),
+            resolve: Vec::new(),
+            cdp_url: None,
+            blocker: None,
+            profiles: Vec::new(),
         }
     }
 }
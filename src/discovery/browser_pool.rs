@@ -0,0 +1,247 @@
+//! Browser page pool: N browsers with M concurrent tabs each.
+//!
+//! Every tab pulls the next target from one shared queue as soon as it is free, so
+//! a slow target never holds up the others and idle browsers take work from busy
+//! ones. Each browser keeps the recovery behaviour of `capture_multiple`: it is
+//! restarted after `RESTART_EVERY` pages and as soon as a page hits the hard timeout.
+
+use super::browser_capture::{
+    new_profile_dir, register_temp_dir, unregister_temp_dir, BrowserCapture,
+};
+use crate::types::{DepfusedError, JsFile, Result};
+use chromiumoxide::browser::Browser;
+use futures::channel::mpsc::UnboundedSender;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// Pages a browser serves before it is restarted to free memory.
+const RESTART_EVERY: usize = 50;
+
+/// Outcome of capturing one target.
+pub type CaptureResult = (String, Result<Vec<JsFile>>);
+
+/// Number of browsers and tabs in a pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolSize {
+    pub browsers: usize,
+    pub tabs_per_browser: usize,
+}
+
+/// Targets not yet picked up by any tab.
+struct WorkQueue {
+    targets: Mutex<VecDeque<String>>,
+}
+
+impl WorkQueue {
+    fn new(targets: Vec<String>) -> Self {
+        Self {
+            targets: Mutex::new(targets.into()),
+        }
+    }
+
+    fn pop(&self) -> Option<String> {
+        self.lock().pop_front()
+    }
+
+    fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn drain(&self) -> Vec<String> {
+        self.lock().drain(..).collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<String>> {
+        self.targets.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// State shared by the tabs of one browser between restarts.
+struct Generation<'a> {
+    browser: &'a Browser,
+    pages: AtomicUsize,
+    restart: AtomicBool,
+}
+
+impl BrowserCapture {
+    /// Capture many targets with a pool of browsers and tabs.
+    ///
+    /// Each result is sent on `results` as soon as its target finishes, so callers
+    /// can process targets while others are still loading.
+    pub async fn capture_pooled(
+        &self,
+        urls: Vec<String>,
+        size: PoolSize,
+        results: UnboundedSender<CaptureResult>,
+    ) {
+        if urls.is_empty() {
+            return;
+        }
+
+        let queue = WorkQueue::new(urls);
+        let launch_error: Mutex<Option<String>> = Mutex::new(None);
+
+        // No point launching browsers that would never get a tab's worth of work
+        let tabs = size.tabs_per_browser.max(1);
+        let browsers = size.browsers.max(1).min(queue.len().div_ceil(tabs));
+        info!(
+            "Capturing {} URLs with a pool of {} browser(s) x {} tab(s)",
+            queue.len(),
+            browsers,
+            tabs
+        );
+
+        futures::future::join_all(
+            (0..browsers).map(|_| self.run_pooled_browser(&queue, tabs, &results, &launch_error)),
+        )
+        .await;
+
+        // Anything still queued means every browser failed to (re)launch
+        let leftover = queue.drain();
+        if !leftover.is_empty() {
+            let error = launch_error
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .take()
+                .unwrap_or_else(|| "no browser available".to_string());
+            for url in leftover {
+                let _ = results.unbounded_send((
+                    url,
+                    Err(DepfusedError::ConfigError(format!(
+                        "Browser launch failed: {}",
+                        error
+                    ))),
+                ));
+            }
+        }
+    }
+
+    /// Run one pooled browser until the queue is empty, restarting it as needed.
+    async fn run_pooled_browser(
+        &self,
+        queue: &WorkQueue,
+        tabs: usize,
+        results: &UnboundedSender<CaptureResult>,
+        launch_error: &Mutex<Option<String>>,
+    ) {
+        let temp_dir = new_profile_dir();
+        if let Err(e) = std::fs::create_dir_all(&temp_dir) {
+            debug!("Failed to create temp dir {:?}: {}", temp_dir, e);
+        }
+        register_temp_dir(&temp_dir);
+
+        while !queue.is_empty() {
+            let instance = match self.start_browser(&temp_dir).await {
+                Ok(instance) => instance,
+                Err(e) => {
+                    warn!("Pooled browser launch failed: {}", e);
+                    *launch_error.lock().unwrap_or_else(|e| e.into_inner()) = Some(e.to_string());
+                    break;
+                }
+            };
+
+            let generation = Generation {
+                browser: &instance.0,
+                pages: AtomicUsize::new(0),
+                restart: AtomicBool::new(false),
+            };
+            futures::future::join_all(
+                (0..tabs).map(|_| self.run_pooled_tab(&generation, queue, results)),
+            )
+            .await;
+
+            self.release_browser(instance, &temp_dir).await;
+
+            if !queue.is_empty() {
+                info!("Restarting pooled browser to free memory");
+            }
+        }
+
+        cleanup_profile_dir(&temp_dir);
+    }
+
+    /// Capture targets in one tab until the queue is empty or the browser needs a restart.
+    async fn run_pooled_tab(
+        &self,
+        generation: &Generation<'_>,
+        queue: &WorkQueue,
+        results: &UnboundedSender<CaptureResult>,
+    ) {
+        loop {
+            if generation.restart.load(Ordering::SeqCst) {
+                break;
+            }
+            // Reserve a page in this browser's budget before taking work
+            if generation.pages.fetch_add(1, Ordering::SeqCst) >= RESTART_EVERY {
+                break;
+            }
+            let Some(url) = queue.pop() else {
+                break;
+            };
+
+            let page_timeout = self.page_timeout();
+            let result = match tokio::time::timeout(
+                page_timeout,
+                self.capture_js_files(generation.browser, &url),
+            )
+            .await
+            {
+                Ok(r) => r,
+                Err(_) => {
+                    // Chrome may be spinning; finish the other tabs, then restart it
+                    warn!(
+                        "Hard timeout after {}s for {}, restarting browser",
+                        page_timeout.as_secs(),
+                        url
+                    );
+                    generation.restart.store(true, Ordering::SeqCst);
+                    Ok(Vec::new())
+                }
+            };
+
+            if results.unbounded_send((url, result)).is_err() {
+                debug!("Capture results receiver dropped, stopping tab");
+                break;
+            }
+        }
+    }
+}
+
+/// Remove a browser profile directory once its Chrome is gone.
+fn cleanup_profile_dir(temp_dir: &Path) {
+    let _ = std::fs::remove_dir_all(temp_dir);
+    unregister_temp_dir(temp_dir);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[test]
+    fn test_work_queue_order_and_drain() {
+        let queue = WorkQueue::new(vec!["a".into(), "b".into(), "c".into()]);
+        assert_eq!(queue.pop().as_deref(), Some("a"));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.drain(), vec!["b".to_string(), "c".to_string()]);
+        assert!(queue.pop().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_capture_pooled_empty() {
+        let (tx, mut rx) = futures::channel::mpsc::unbounded();
+        let size = PoolSize {
+            browsers: 2,
+            tabs_per_browser: 4,
+        };
+        BrowserCapture::default().capture_pooled(Vec::new(), size, tx).await;
+        assert!(rx.next().await.is_none());
+    }
+}
@@ -0,0 +1,181 @@
+//! Device and locale emulation profiles for browser capture.
+//!
+//! Sites serve different bundles to phones, small viewports and other locales,
+//! and mobile bundles often reference different internal packages. A profile is
+//! applied to a fresh page through the CDP `Emulation` domain before navigation.
+
+use crate::types::{DepfusedError, Result};
+use chromiumoxide::cdp::browser_protocol::emulation::{
+    SetDeviceMetricsOverrideParams, SetLocaleOverrideParams, SetTouchEmulationEnabledParams,
+    SetUserAgentOverrideParams,
+};
+use chromiumoxide::Page;
+use std::str::FromStr;
+
+/// Locale used when a profile is given without one.
+const DEFAULT_LOCALE: &str = "en-US";
+
+/// Device a profile emulates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Device {
+    Desktop,
+    Ios,
+    Android,
+}
+
+impl Device {
+    fn name(self) -> &'static str {
+        match self {
+            Device::Desktop => "desktop",
+            Device::Ios => "ios",
+            Device::Android => "android",
+        }
+    }
+
+    fn user_agent(self) -> &'static str {
+        match self {
+            Device::Desktop => "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            Device::Ios => "Mozilla/5.0 (iPhone; CPU iPhone OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Mobile/15E148 Safari/604.1",
+            Device::Android => "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+        }
+    }
+
+    /// `navigator.platform` reported by the device.
+    fn platform(self) -> &'static str {
+        match self {
+            Device::Desktop => "Win32",
+            Device::Ios => "iPhone",
+            Device::Android => "Linux armv8l",
+        }
+    }
+
+    /// Viewport width, height and device scale factor.
+    fn metrics(self) -> (i64, i64, f64) {
+        match self {
+            Device::Desktop => (1920, 1080, 1.0),
+            Device::Ios => (390, 844, 3.0),
+            Device::Android => (412, 915, 2.625),
+        }
+    }
+
+    fn is_mobile(self) -> bool {
+        !matches!(self, Device::Desktop)
+    }
+}
+
+/// A named device plus locale, e.g. `ios:ja-JP`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmulationProfile {
+    pub device: Device,
+    pub locale: String,
+}
+
+impl EmulationProfile {
+    /// Name used to attribute captured files and findings to this profile.
+    pub fn name(&self) -> String {
+        format!("{}:{}", self.device.name(), self.locale)
+    }
+
+    /// `Accept-Language` header value for the profile's locale.
+    fn accept_language(&self) -> String {
+        match self.locale.split_once('-') {
+            Some((language, _)) => format!("{},{};q=0.9", self.locale, language),
+            None => self.locale.clone(),
+        }
+    }
+
+    /// Apply the profile to a page. Must run before the page navigates.
+    pub async fn apply(&self, page: &Page) -> Result<()> {
+        let emulation_error = |e: chromiumoxide::error::CdpError| {
+            DepfusedError::ConfigError(format!("Failed to apply profile {}: {}", self.name(), e))
+        };
+        let (width, height, scale) = self.device.metrics();
+        let mobile = self.device.is_mobile();
+
+        page.execute(SetDeviceMetricsOverrideParams::new(width, height, scale, mobile))
+            .await
+            .map_err(emulation_error)?;
+        page.execute(SetTouchEmulationEnabledParams {
+            enabled: mobile,
+            max_touch_points: mobile.then_some(5),
+        })
+        .await
+        .map_err(emulation_error)?;
+        page.execute(SetUserAgentOverrideParams {
+            user_agent: self.device.user_agent().to_string(),
+            accept_language: Some(self.accept_language()),
+            platform: Some(self.device.platform().to_string()),
+            user_agent_metadata: None,
+        })
+        .await
+        .map_err(emulation_error)?;
+        page.execute(SetLocaleOverrideParams {
+            locale: Some(self.locale.replace('-', "_")),
+        })
+        .await
+        .map_err(emulation_error)?;
+
+        Ok(())
+    }
+}
+
+impl FromStr for EmulationProfile {
+    type Err = String;
+
+    /// Parse `desktop`, `ios` or `android`, optionally followed by `:LOCALE`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (device, locale) = s.split_once(':').unwrap_or((s, DEFAULT_LOCALE));
+        let device = match device.trim().to_ascii_lowercase().as_str() {
+            "desktop" => Device::Desktop,
+            "ios" | "iphone" => Device::Ios,
+            "android" => Device::Android,
+            other => {
+                return Err(format!(
+                    "unknown profile '{}' (expected desktop, ios or android)",
+                    other
+                ))
+            }
+        };
+
+        let locale = locale.trim();
+        let valid_locale = !locale.is_empty()
+            && locale
+                .split('-')
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()));
+        if !valid_locale {
+            return Err(format!("invalid locale '{}' (expected e.g. en-US)", locale));
+        }
+
+        Ok(Self {
+            device,
+            locale: locale.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_profile() {
+        let profile: EmulationProfile = "ios:ja-JP".parse().unwrap();
+        assert_eq!(profile.device, Device::Ios);
+        assert_eq!(profile.name(), "ios:ja-JP");
+        assert_eq!(profile.accept_language(), "ja-JP,ja;q=0.9");
+
+        let profile: EmulationProfile = "Android".parse().unwrap();
+        assert_eq!(profile.name(), "android:en-US");
+
+        assert!("tablet".parse::<EmulationProfile>().is_err());
+        assert!("desktop:".parse::<EmulationProfile>().is_err());
+        assert!("desktop:en US".parse::<EmulationProfile>().is_err());
+    }
+
+    #[test]
+    fn test_device_metrics() {
+        assert!(!Device::Desktop.is_mobile());
+        assert!(Device::Ios.user_agent().contains("iPhone"));
+        assert_eq!(Device::Android.metrics().0, 412);
+    }
+}
@@ -1,57 +1,108 @@
 //! JavaScript file fetcher with retry support.
 
-use crate::types::{ContentHashSet, HttpConfig, JsFile, JsSource, Result};
-use governor::{Quota, RateLimiter};
+use crate::audit::{AuditComponent, AuditEntry};
+use crate::discovery::{build_http_client, record_stopped_redirect};
+use crate::discovery::politeness::{backoff_delay, parse_retry_after, HostLimiter};
+use crate::types::{ContentHashSet, DepfusedError, HttpConfig, JsFile, JsSource, Result};
 use reqwest::Client;
 use sha2::{Digest, Sha256};
-use std::num::NonZeroU32;
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use std::time::Duration;
 use tokio::sync::RwLock;
 use tracing::{debug, trace, warn};
 
-/// Fetcher for JavaScript files with rate limiting and deduplication.
+/// Response headers kept on every [`JsFile`].
+pub const RECORDED_HEADERS: &[&str] = &[
+    "content-type",
+    "content-length",
+    "etag",
+    "last-modified",
+    "server",
+    "sourcemap",
+    "x-sourcemap",
+];
+
+/// A successful response body with its status and recorded headers.
+struct FetchedBody {
+    status: u16,
+    headers: BTreeMap<String, String>,
+    content: String,
+}
+
+/// What became of a [`JsFetcher::fetch`] request.
+#[derive(Debug)]
+pub enum FetchOutcome {
+    /// New content was fetched.
+    Fetched(JsFile),
+    /// The content was already fetched from another URL.
+    Duplicate,
+    /// The URL is out of scope and was never requested.
+    OutOfScope,
+    /// The request failed, after any retries.
+    Failed,
+}
+
+impl FetchOutcome {
+    /// The fetched file, if new content was fetched.
+    pub fn into_file(self) -> Option<JsFile> {
+        match self {
+            FetchOutcome::Fetched(file) => Some(file),
+            _ => None,
+        }
+    }
+}
+
+/// Fetcher for JavaScript files with per-host rate limiting and deduplication.
 pub struct JsFetcher {
     client: Client,
     config: HttpConfig,
-    rate_limiter: Arc<RateLimiter<governor::state::NotKeyed, governor::state::InMemoryState, governor::clock::DefaultClock>>,
+    host_limiter: Arc<HostLimiter>,
     seen_hashes: Arc<RwLock<ContentHashSet>>,
 }
 
 impl JsFetcher {
     /// Create a new JS fetcher.
-    pub fn new(config: HttpConfig, rate_limit: u32) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout_secs))
-            .user_agent(&config.user_agent)
-            .redirect(reqwest::redirect::Policy::limited(5))
-            .http1_only()
-            .pool_max_idle_per_host(10)
-            .pool_idle_timeout(Duration::from_secs(30))
-            .build()?;
-
-        let quota = Quota::per_second(NonZeroU32::new(rate_limit).unwrap_or(NonZeroU32::new(10).unwrap()));
-        let rate_limiter = Arc::new(RateLimiter::direct(quota));
+    ///
+    /// The host limiter is shared with other components (e.g. the source map
+    /// prober) so requests to the same host are counted together.
+    pub fn new(config: HttpConfig, host_limiter: Arc<HostLimiter>) -> Result<Self> {
+        let client = build_http_client(&config, AuditComponent::JsFetcher)?;
 
         Ok(Self {
             client,
             config,
-            rate_limiter,
+            host_limiter,
             seen_hashes: Arc::new(RwLock::new(ContentHashSet::new())),
         })
     }
 
     /// Fetch a single JS file with retries.
-    pub async fn fetch_one(&self, url: &str, source: JsSource) -> Option<JsFile> {
-        // Rate limit
-        self.rate_limiter.until_ready().await;
+    ///
+    /// `target` is the scan target the request is made for; out-of-scope URLs are
+    /// recorded against it and never requested.
+    pub async fn fetch_one(&self, url: &str, source: JsSource, target: &str) -> Option<JsFile> {
+        self.fetch(url, source, target).await.into_file()
+    }
+
+    /// Like [`fetch_one`](Self::fetch_one), but says why nothing was returned.
+    pub async fn fetch(&self, url: &str, source: JsSource, target: &str) -> FetchOutcome {
+        if let Some(scope) = &self.config.scope {
+            if !scope.check(url, target) {
+                return FetchOutcome::OutOfScope;
+            }
+        }
 
         let mut retries = 0;
         let mut last_error = None;
 
         while retries <= self.config.max_retries {
-            match self.do_fetch(url).await {
-                Ok(content) => {
+            // Per-host rate limit and in-flight cap, held until the body is read
+            let permit = self.host_limiter.acquire(url).await;
+            let fetch_result = self.do_fetch(url, retries + 1, target).await;
+            drop(permit);
+
+            match fetch_result {
+                Ok(FetchedBody { status, headers, content }) => {
                     // Calculate content hash
                     let hash = Self::hash_content(&content);
 
@@ -60,7 +111,7 @@ impl JsFetcher {
                         let seen = self.seen_hashes.read().await;
                         if seen.contains(&hash) {
                             trace!("Skipping duplicate content: {}", url);
-                            return None;
+                            return FetchOutcome::Duplicate;
                         }
                     }
 
@@ -71,30 +122,38 @@ impl JsFetcher {
                     }
 
                     // Check for source map reference
-                    let source_map_url = self.extract_sourcemap_url(&content, url);
+                    let source_map_url = find_sourcemap_url(&content, url, &headers);
 
                     debug!("Fetched JS file: {} ({} bytes)", url, content.len());
 
-                    return Some(JsFile {
+                    return FetchOutcome::Fetched(JsFile {
                         url: url.to_string(),
                         content,
                         content_hash: hash,
                         source,
                         source_map_url,
+                        profiles: Vec::new(),
+                        status: Some(status),
+                        headers,
                     });
                 }
                 Err(e) => {
                     // Check if this is a client error (4xx) that shouldn't be retried
-                    let should_retry = if let crate::types::DepfusedError::HttpError(ref http_err) = e {
-                        if let Some(status) = http_err.status() {
-                            // Don't retry on 4xx client errors (404, 403, 401, etc.)
-                            // These won't succeed on retry
-                            !status.is_client_error()
-                        } else {
-                            true // Retry on network errors without status
+                    let should_retry = match e {
+                        DepfusedError::HttpError(ref http_err) => {
+                            if let Some(status) = http_err.status() {
+                                // Don't retry on 4xx client errors (404, 403, 401, etc.)
+                                // These won't succeed on retry. 429 is the exception: the
+                                // host has been put on cooldown and a later retry may work.
+                                !status.is_client_error()
+                                    || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                            } else {
+                                true // Retry on network errors without status
+                            }
                         }
-                    } else {
-                        true // Retry on other error types
+                        // A redirect out of scope will redirect the same way again
+                        DepfusedError::OutOfScope(_) => false,
+                        _ => true, // Retry on other error types
                     };
 
                     last_error = Some(e);
@@ -107,8 +166,9 @@ impl JsFetcher {
 
                     retries += 1;
                     if retries <= self.config.max_retries {
-                        trace!("Retry {} for {}", retries, url);
-                        tokio::time::sleep(Duration::from_millis(500 * retries as u64)).await;
+                        let delay = backoff_delay(retries);
+                        trace!("Retry {} for {} in {:?}", retries, url, delay);
+                        tokio::time::sleep(delay).await;
                     }
                 }
             }
@@ -123,26 +183,73 @@ impl JsFetcher {
             }
         }
 
-        None
+        FetchOutcome::Failed
     }
 
     /// Perform the actual HTTP fetch.
-    async fn do_fetch(&self, url: &str) -> Result<String> {
-        let response = self.client.get(url).send().await?;
+    ///
+    /// Puts the host on cooldown when it answers 429 or 503.
+    async fn do_fetch(&self, url: &str, attempt: u32, target: &str) -> Result<FetchedBody> {
+        let audit_entry =
+            |url: &str| AuditEntry::new(AuditComponent::JsFetcher, "GET", url).with_target(target);
+
+        let response = match self.client.get(url).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                if let Some(audit_log) = &self.config.audit_log {
+                    audit_log.record(audit_entry(url).with_error(&e));
+                }
+                return Err(e.into());
+            }
+        };
+        // Redirect hops are audited by the client; this entry is the final response
+        let final_url = response.url().to_string();
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+        {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_retry_after);
+            // Redirects may have led to another host; that is the one to slow down for
+            self.host_limiter.back_off(&final_url, attempt, retry_after);
+        }
 
-        if !response.status().is_success() {
-            return Err(crate::types::DepfusedError::HttpError(
+        if !status.is_success() {
+            if let Some(audit_log) = &self.config.audit_log {
+                audit_log.record(audit_entry(&final_url).with_status(status.as_u16()));
+            }
+            if let Some(skipped) = record_stopped_redirect(self.config.scope.as_deref(), &response, target) {
+                return Err(DepfusedError::OutOfScope(skipped));
+            }
+            return Err(DepfusedError::HttpError(
                 response.error_for_status().unwrap_err(),
             ));
         }
 
-        let content = response.text().await?;
-        Ok(content)
-    }
+        let headers = recorded_headers(
+            response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| Some((name.as_str(), value.to_str().ok()?))),
+        );
+        let content = response.text().await;
+        if let Some(audit_log) = &self.config.audit_log {
+            let entry = audit_entry(&final_url).with_status(status.as_u16());
+            audit_log.record(match &content {
+                Ok(content) => entry.with_bytes(content.len() as u64),
+                Err(e) => entry.with_error(e),
+            });
+        }
 
-    /// Extract source map URL from JS content (delegates to standalone function).
-    fn extract_sourcemap_url(&self, content: &str, base_url: &str) -> Option<String> {
-        extract_sourcemap_url(content, base_url)
+        Ok(FetchedBody {
+            status: status.as_u16(),
+            headers,
+            content: content?,
+        })
     }
 
     /// Calculate SHA256 hash of content.
@@ -168,23 +275,8 @@ pub fn extract_sourcemap_url(content: &str, base_url: &str) -> Option<String> {
         if let Ok(re) = regex::Regex::new(pattern) {
             if let Some(caps) = re.captures(content) {
                 if let Some(url_match) = caps.get(1) {
-                    let map_url = url_match.as_str().trim();
-
-                    // Handle inline base64 source maps
-                    if map_url.starts_with("data:") {
-                        return Some(map_url.to_string());
-                    }
-
-                    // Resolve relative URL
-                    if map_url.starts_with("http://") || map_url.starts_with("https://") {
-                        return Some(map_url.to_string());
-                    }
-
-                    // Resolve relative to base URL
-                    if let Ok(base) = url::Url::parse(base_url) {
-                        if let Ok(resolved) = base.join(map_url) {
-                            return Some(resolved.to_string());
-                        }
+                    if let Some(resolved) = resolve_sourcemap_reference(url_match.as_str(), base_url) {
+                        return Some(resolved);
                     }
                 }
             }
@@ -194,6 +286,59 @@ pub fn extract_sourcemap_url(content: &str, base_url: &str) -> Option<String> {
     None
 }
 
+/// Find the source map for a JS response.
+///
+/// A `SourceMap` (or `X-SourceMap`) response header wins over the
+/// `sourceMappingURL` comment.
+pub fn find_sourcemap_url(
+    content: &str,
+    base_url: &str,
+    headers: &BTreeMap<String, String>,
+) -> Option<String> {
+    headers
+        .get("sourcemap")
+        .or_else(|| headers.get("x-sourcemap"))
+        .and_then(|reference| resolve_sourcemap_reference(reference, base_url))
+        .or_else(|| extract_sourcemap_url(content, base_url))
+}
+
+/// Resolve a source map reference (absolute, relative or `data:`) against the JS URL.
+fn resolve_sourcemap_reference(map_url: &str, base_url: &str) -> Option<String> {
+    let map_url = map_url.trim();
+    if map_url.is_empty() {
+        return None;
+    }
+
+    // Handle inline base64 source maps
+    if map_url.starts_with("data:") {
+        return Some(map_url.to_string());
+    }
+
+    // Resolve relative URL
+    if map_url.starts_with("http://") || map_url.starts_with("https://") {
+        return Some(map_url.to_string());
+    }
+
+    // Resolve relative to base URL
+    let base = url::Url::parse(base_url).ok()?;
+    base.join(map_url).ok().map(|resolved| resolved.to_string())
+}
+
+/// Keep the response headers listed in [`RECORDED_HEADERS`], with lowercase names.
+pub fn recorded_headers<'a>(
+    headers: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> BTreeMap<String, String> {
+    headers
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let name = name.to_ascii_lowercase();
+            RECORDED_HEADERS
+                .contains(&name.as_str())
+                .then(|| (name, value.trim().to_string()))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -207,18 +352,42 @@ mod tests {
 
     #[test]
     fn test_extract_sourcemap_url() {
-        let config = HttpConfig::default();
-        let fetcher = JsFetcher::new(config, 10).unwrap();
-
         let content = r#"
             console.log('test');
             //# sourceMappingURL=main.js.map
         "#;
 
-        let result = fetcher.extract_sourcemap_url(content, "https://example.com/js/main.js");
+        let result = extract_sourcemap_url(content, "https://example.com/js/main.js");
         assert_eq!(
             result,
             Some("https://example.com/js/main.js.map".to_string())
         );
     }
+
+    #[test]
+    fn test_sourcemap_header_preferred() {
+        let content = "console.log('test');\n//# sourceMappingURL=main.js.map";
+        let headers = recorded_headers([
+            ("SourceMap", "/maps/main.js.map"),
+            ("X-SourceMap", "legacy.js.map"),
+            ("Set-Cookie", "session=1"),
+        ]);
+        assert!(!headers.contains_key("set-cookie"));
+
+        assert_eq!(
+            find_sourcemap_url(content, "https://example.com/js/main.js", &headers),
+            Some("https://example.com/maps/main.js.map".to_string())
+        );
+
+        let legacy = recorded_headers([("x-sourcemap", "legacy.js.map")]);
+        assert_eq!(
+            find_sourcemap_url(content, "https://example.com/js/main.js", &legacy),
+            Some("https://example.com/js/legacy.js.map".to_string())
+        );
+
+        assert_eq!(
+            find_sourcemap_url(content, "https://example.com/js/main.js", &BTreeMap::new()),
+            Some("https://example.com/js/main.js.map".to_string())
+        );
+    }
 }
@@ -5,10 +5,125 @@
 //! - Source map references
 
 pub mod browser_capture;
+pub mod browser_pool;
+pub mod emulation;
 pub mod js_fetcher;
+pub mod politeness;
+pub mod resource_blocker;
 pub mod sourcemap_probe;
 
 pub use browser_capture::BrowserCapture;
 pub use browser_capture::kill_all_chrome;
-pub use js_fetcher::{JsFetcher, extract_sourcemap_url};
+pub use browser_pool::{CaptureResult, PoolSize};
+pub use emulation::EmulationProfile;
+pub use js_fetcher::{
+    FetchOutcome, JsFetcher, RECORDED_HEADERS, extract_sourcemap_url, find_sourcemap_url, recorded_headers,
+};
+pub use politeness::HostLimiter;
+pub use resource_blocker::ResourceBlocker;
 pub use sourcemap_probe::SourceMapProber;
+
+use crate::audit::{AuditComponent, AuditEntry};
+use crate::scope::ScopeGuard;
+use crate::types::{DepfusedError, HttpConfig, ResolveOverride, Result};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::time::Duration;
+use tracing::debug;
+
+/// Maximum redirects followed by the fetcher and prober clients.
+const MAX_REDIRECTS: usize = 5;
+
+/// Convert `(name, value)` pairs into a header map.
+pub fn header_map(headers: &[(String, String)]) -> Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| DepfusedError::ConfigError(format!("Invalid header name: {}", name)))?;
+        let value = HeaderValue::from_str(value).map_err(|_| {
+            DepfusedError::ConfigError(format!("Invalid value for header {}", name))
+        })?;
+        map.append(name, value);
+    }
+    Ok(map)
+}
+
+/// Pin hostnames to the addresses given with `--resolve`.
+///
+/// reqwest overrides DNS per hostname only; the connection port always comes from
+/// the URL, so mappings apply on every port (as the browser rules do).
+pub fn apply_resolve_overrides(
+    builder: reqwest::ClientBuilder,
+    overrides: &[ResolveOverride],
+) -> reqwest::ClientBuilder {
+    let mut by_host: HashMap<&str, Vec<SocketAddr>> = HashMap::new();
+    for entry in overrides {
+        let addrs = by_host.entry(entry.host.as_str()).or_default();
+        if !addrs.iter().any(|addr| addr.ip() == entry.ip) {
+            addrs.push(SocketAddr::new(entry.ip, entry.port));
+        }
+    }
+
+    by_host
+        .into_iter()
+        .fold(builder, |builder, (host, addrs)| builder.resolve_to_addrs(host, &addrs))
+}
+
+/// Record the redirect target of a response the client refused to follow.
+///
+/// The redirect policy stops at out-of-scope hops and hands back the 3xx response,
+/// but only the caller knows which target the request was made for. Returns the
+/// skipped URL, if the response was such a redirect.
+pub(crate) fn record_stopped_redirect(
+    scope: Option<&ScopeGuard>,
+    response: &reqwest::Response,
+    target: &str,
+) -> Option<String> {
+    let scope = scope?;
+    if !response.status().is_redirection() {
+        return None;
+    }
+    let location = response.headers().get(reqwest::header::LOCATION)?.to_str().ok()?;
+    let next = response.url().join(location).ok()?;
+    (!scope.check(next.as_str(), target)).then(|| next.to_string())
+}
+
+/// Build the HTTP client for the JS fetcher or the source map prober.
+///
+/// Redirects are only followed while they stay in scope. Every redirect hop is
+/// written to the audit log as `component`; callers audit the final response.
+pub fn build_http_client(config: &HttpConfig, component: AuditComponent) -> Result<reqwest::Client> {
+    let scope = config.scope.clone();
+    let audit_log = config.audit_log.clone();
+    let redirect_policy = reqwest::redirect::Policy::custom(move |attempt| {
+        if let (Some(audit_log), Some(from)) = (&audit_log, attempt.previous().last()) {
+            audit_log.record(
+                AuditEntry::new(component, "GET", from.as_str()).with_status(attempt.status().as_u16()),
+            );
+        }
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+        match &scope {
+            Some(scope) if !scope.is_allowed(attempt.url().as_str()) => {
+                debug!("Not following out-of-scope redirect to {}", attempt.url());
+                attempt.stop()
+            }
+            _ => attempt.follow(),
+        }
+    });
+
+    let builder = apply_resolve_overrides(reqwest::Client::builder(), &config.resolve);
+    let client = builder
+        .timeout(Duration::from_secs(config.timeout_secs))
+        .user_agent(&config.user_agent)
+        .default_headers(header_map(&config.headers)?)
+        .redirect(redirect_policy)
+        .http1_only() // Force HTTP/1.1 to avoid HTTP/2 stream limit issues
+        .pool_max_idle_per_host(10)
+        .pool_idle_timeout(Duration::from_secs(30))
+        .build()?;
+
+    Ok(client)
+}
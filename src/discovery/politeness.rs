@@ -0,0 +1,262 @@
+//! Per-host politeness: keyed rate limiting, in-flight caps and jittered backoff.
+//!
+//! A single `HostLimiter` is shared by `JsFetcher` and `SourceMapProber` so that
+//! requests to the same host are counted together, while requests to different
+//! hosts never wait on each other.
+
+use dashmap::DashMap;
+use governor::clock::DefaultClock;
+use governor::state::keyed::DefaultKeyedStateStore;
+use governor::{Jitter, Quota, RateLimiter};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::debug;
+
+/// Base delay for the first retry; doubled on each further attempt.
+const BACKOFF_BASE_MS: u64 = 500;
+
+/// Upper bound for a single backoff delay.
+const BACKOFF_MAX_MS: u64 = 30_000;
+
+/// Idle host state is pruned once every this many acquisitions.
+const PRUNE_EVERY: u64 = 512;
+
+/// Per-host rate limiter and concurrency cap.
+pub struct HostLimiter {
+    rate_limiter: RateLimiter<String, DefaultKeyedStateStore<String>, DefaultClock>,
+    max_in_flight: usize,
+    in_flight: DashMap<String, Arc<Semaphore>>,
+    /// Hosts that asked us to slow down (429/503), mapped to when they may be contacted again.
+    cooldowns: DashMap<String, Instant>,
+    acquisitions: AtomicU64,
+}
+
+/// Permit held for the duration of one request to a host.
+pub struct HostPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl HostLimiter {
+    /// Create a limiter allowing `requests_per_second` and `max_in_flight` concurrent
+    /// requests for each host.
+    pub fn new(requests_per_second: u32, max_in_flight: usize) -> Self {
+        let quota = Quota::per_second(
+            NonZeroU32::new(requests_per_second).unwrap_or(NonZeroU32::new(10).unwrap()),
+        );
+
+        Self {
+            rate_limiter: RateLimiter::keyed(quota),
+            max_in_flight: max_in_flight.max(1),
+            in_flight: DashMap::new(),
+            cooldowns: DashMap::new(),
+            acquisitions: AtomicU64::new(0),
+        }
+    }
+
+    /// Wait until a request to `url` is allowed, then hold a slot for it.
+    ///
+    /// Waits out any cooldown for the host, then the host's rate limit, then a free
+    /// in-flight slot. The returned permit must be kept alive until the response
+    /// body has been read.
+    pub async fn acquire(&self, url: &str) -> HostPermit {
+        if self.acquisitions.fetch_add(1, Ordering::Relaxed) % PRUNE_EVERY == PRUNE_EVERY - 1 {
+            self.prune();
+        }
+
+        let host = host_key(url).unwrap_or_else(|| url.to_string());
+
+        let cooldown_until = self.cooldowns.get(&host).map(|entry| *entry);
+        if let Some(until) = cooldown_until {
+            let now = Instant::now();
+            if until > now {
+                debug!("Host {} is cooling down for {:?}", host, until - now);
+                tokio::time::sleep(until - now).await;
+            }
+            self.cooldowns.remove_if(&host, |_, until| *until <= Instant::now());
+        }
+
+        // A little jitter spreads out bursts from many tasks hitting the same host
+        self.rate_limiter
+            .until_key_ready_with_jitter(&host, Jitter::up_to(Duration::from_millis(50)))
+            .await;
+
+        let semaphore = self
+            .in_flight
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.max_in_flight)))
+            .clone();
+
+        // The semaphore is never closed, so acquiring cannot fail
+        let permit = semaphore
+            .acquire_owned()
+            .await
+            .expect("host semaphore closed");
+
+        HostPermit { _permit: permit }
+    }
+
+    /// Record that a host asked us to slow down (HTTP 429/503).
+    ///
+    /// Uses the server's `Retry-After` when given, otherwise a jittered exponential
+    /// delay for the attempt. Returns the delay that was applied.
+    pub fn back_off(&self, url: &str, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let delay = retry_after
+            .map(|d| d.min(Duration::from_millis(BACKOFF_MAX_MS)))
+            .unwrap_or_else(|| backoff_delay(attempt));
+        let until = Instant::now() + delay;
+
+        let host = host_key(url).unwrap_or_else(|| url.to_string());
+        debug!("Backing off {} for {:?}", host, delay);
+        self.cooldowns
+            .entry(host)
+            .and_modify(|existing| {
+                if until > *existing {
+                    *existing = until;
+                }
+            })
+            .or_insert(until);
+
+        delay
+    }
+
+    /// Drop state for hosts that are idle: rate limiter keys that have fully
+    /// replenished, semaphores nobody holds or waits on, and expired cooldowns.
+    ///
+    /// Called periodically from `acquire` so long target lists don't grow the
+    /// maps without bound.
+    pub fn prune(&self) {
+        self.rate_limiter.retain_recent();
+        self.rate_limiter.shrink_to_fit();
+        // Permits and waiters hold a clone, so a lone reference means idle
+        self.in_flight.retain(|_, semaphore| Arc::strong_count(semaphore) > 1);
+        let now = Instant::now();
+        self.cooldowns.retain(|_, until| *until > now);
+        debug!("Pruned host limiter state, {} hosts tracked", self.in_flight.len());
+    }
+
+    /// Number of hosts with in-flight or cooldown state.
+    #[cfg(test)]
+    fn tracked_hosts(&self) -> usize {
+        self.in_flight.len() + self.cooldowns.len()
+    }
+}
+
+impl Default for HostLimiter {
+    fn default() -> Self {
+        Self::new(10, 6)
+    }
+}
+
+/// Jittered exponential backoff ("full jitter") for a retry attempt (1-based).
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let exp = attempt.saturating_sub(1).min(16);
+    let ceiling = BACKOFF_BASE_MS.saturating_mul(1 << exp).min(BACKOFF_MAX_MS);
+    // Keep at least half the ceiling so retries never fire back-to-back
+    let floor = ceiling / 2;
+    Duration::from_millis(floor + random_u64() % (ceiling - floor + 1))
+}
+
+/// Parse a `Retry-After` header value given in seconds.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+/// Key grouping URLs by host: scheme, host and non-default port, e.g.
+/// `http://[::1]:8080`. Shared by rate limiting, source map probing and browser
+/// host groups so they all agree on what a host is.
+pub(crate) fn host_key(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+    // `Host` displays IPv6 addresses in brackets; `port` omits the scheme's default
+    let host = parsed.host()?;
+    Some(match parsed.port() {
+        Some(port) => format!("{}://{}:{}", parsed.scheme(), host, port),
+        None => format!("{}://{}", parsed.scheme(), host),
+    })
+}
+
+/// Cheap non-cryptographic randomness for jitter (avoids pulling in `rand`).
+fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default(),
+    );
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_key() {
+        let key = |url| host_key(url).unwrap_or_default();
+        assert_eq!(key("https://cdn.example.com/a/b.js"), "https://cdn.example.com");
+        assert_eq!(key("http://localhost:9001/x.js"), "http://localhost:9001");
+        assert_eq!(key("https://cdn.example.com:443/a.js"), "https://cdn.example.com");
+        assert_eq!(key("http://[::1]:8080/a.js"), "http://[::1]:8080");
+        assert_eq!(key("http://[::1]/a.js"), "http://[::1]");
+        assert_eq!(host_key("not a url"), None);
+    }
+
+    #[test]
+    fn test_backoff_delay_bounds() {
+        for attempt in 1..=20 {
+            let ceiling = (BACKOFF_BASE_MS << (attempt - 1).min(16)).min(BACKOFF_MAX_MS);
+            let delay = backoff_delay(attempt).as_millis() as u64;
+            assert!(delay >= ceiling / 2 && delay <= ceiling, "attempt {}: {}ms", attempt, delay);
+        }
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("5"), Some(Duration::from_secs(5)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_cap_is_per_host() {
+        let limiter = HostLimiter::new(1000, 1);
+
+        let _a = limiter.acquire("https://a.example.com/1.js").await;
+        // A different host is not blocked by the held permit
+        let other = tokio::time::timeout(
+            Duration::from_millis(500),
+            limiter.acquire("https://b.example.com/1.js"),
+        )
+        .await;
+        assert!(other.is_ok());
+
+        // The same host has to wait for the first permit
+        let same = tokio::time::timeout(
+            Duration::from_millis(100),
+            limiter.acquire("https://a.example.com/2.js"),
+        )
+        .await;
+        assert!(same.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_prune_keeps_busy_hosts() {
+        let limiter = HostLimiter::new(1000, 2);
+
+        let held = limiter.acquire("https://a.example.com/1.js").await;
+        drop(limiter.acquire("https://b.example.com/1.js").await);
+        limiter.back_off("https://c.example.com/1.js", 1, Some(Duration::ZERO));
+        assert_eq!(limiter.tracked_hosts(), 3);
+
+        limiter.prune();
+        assert_eq!(limiter.tracked_hosts(), 1);
+
+        drop(held);
+        limiter.prune();
+        assert_eq!(limiter.tracked_hosts(), 0);
+    }
+}
@@ -0,0 +1,262 @@
+//! Resource blocking for browser capture.
+//!
+//! Only scripts matter for package discovery, so images, fonts, media and
+//! analytics beacons can be failed through CDP `Fetch` interception instead of
+//! being downloaded. Blocked resource types and denied hosts are failed before
+//! the request leaves the browser, so the server never sees them. As no response
+//! arrives, the bandwidth saved is estimated from typical sizes per type.
+
+use crate::scope::glob_match;
+use crate::types::{BlockStats, DepfusedError, Result};
+use chromiumoxide::cdp::browser_protocol::network::ResourceType;
+use dashmap::DashMap;
+use tracing::debug;
+
+/// Analytics and ad hosts blocked by `--block-trackers`.
+pub const TRACKER_HOSTS: &[&str] = &[
+    "google-analytics.com",
+    "googletagmanager.com",
+    "googleadservices.com",
+    "googlesyndication.com",
+    "doubleclick.net",
+    "connect.facebook.net",
+    "bat.bing.com",
+    "clarity.ms",
+    "hotjar.com",
+    "segment.com",
+    "segment.io",
+    "mixpanel.com",
+    "amplitude.com",
+    "fullstory.com",
+    "nr-data.net",
+    "scorecardresearch.com",
+    "quantserve.com",
+    "adnxs.com",
+    "criteo.com",
+    "taboola.com",
+    "outbrain.com",
+];
+
+/// Decides which browser requests are blocked and keeps per-target statistics.
+///
+/// Host rules are globs as in the scope file; a plain host also matches its
+/// subdomains. Allowed hosts are never blocked, whatever their resource type.
+#[derive(Debug, Default)]
+pub struct ResourceBlocker {
+    types: Vec<ResourceType>,
+    block_hosts: Vec<String>,
+    allow_hosts: Vec<String>,
+    stats: DashMap<String, BlockStats>,
+}
+
+impl ResourceBlocker {
+    /// Create a blocker for the given resource type names (`image`, `font`, ...).
+    pub fn new(types: &[String]) -> Result<Self> {
+        let mut blocker = Self::default();
+        for name in types {
+            let resource_type: ResourceType = name.trim().parse().map_err(|_| {
+                DepfusedError::ConfigError(format!("Unknown resource type to block: {}", name))
+            })?;
+            // Blocking these would leave nothing to capture
+            if matches!(resource_type, ResourceType::Document | ResourceType::Script) {
+                return Err(DepfusedError::ConfigError(format!(
+                    "Cannot block resource type {}: it is needed to capture JavaScript",
+                    name
+                )));
+            }
+            if !blocker.types.contains(&resource_type) {
+                blocker.types.push(resource_type);
+            }
+        }
+        Ok(blocker)
+    }
+
+    /// Block requests to these hosts.
+    pub fn with_block_hosts(mut self, hosts: &[String]) -> Self {
+        self.block_hosts
+            .extend(hosts.iter().map(|h| h.trim().to_ascii_lowercase()));
+        self
+    }
+
+    /// Block the built-in list of analytics and ad hosts.
+    pub fn with_trackers(mut self, enabled: bool) -> Self {
+        if enabled {
+            self.block_hosts
+                .extend(TRACKER_HOSTS.iter().map(|h| h.to_string()));
+        }
+        self
+    }
+
+    /// Never block requests to these hosts.
+    pub fn with_allow_hosts(mut self, hosts: &[String]) -> Self {
+        self.allow_hosts
+            .extend(hosts.iter().map(|h| h.trim().to_ascii_lowercase()));
+        self
+    }
+
+    /// Whether this blocker would never block anything.
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty() && self.block_hosts.is_empty()
+    }
+
+    /// Resource types intercepted before they are sent.
+    pub fn blocked_types(&self) -> &[ResourceType] {
+        &self.types
+    }
+
+    /// Whether any host rules require pausing requests before they are sent.
+    pub fn has_host_rules(&self) -> bool {
+        !self.block_hosts.is_empty()
+    }
+
+    /// The deny rule matching this URL's host, unless the host is allowed.
+    pub fn denied_host(&self, url: &str) -> Option<&str> {
+        let host = request_host(url)?;
+        if self.is_allowed_host(&host) {
+            return None;
+        }
+        self.block_hosts
+            .iter()
+            .find(|rule| host_matches(rule, &host))
+            .map(String::as_str)
+    }
+
+    /// Whether a request of `resource_type` to `url` should be blocked by type.
+    pub fn blocks_type(&self, resource_type: &ResourceType, url: &str) -> bool {
+        self.types.contains(resource_type)
+            && !request_host(url).is_some_and(|host| self.is_allowed_host(&host))
+    }
+
+    fn is_allowed_host(&self, host: &str) -> bool {
+        self.allow_hosts.iter().any(|rule| host_matches(rule, host))
+    }
+
+    /// Record a request blocked by resource type for `target`.
+    pub fn record_type(&self, target: &str, resource_type: &ResourceType, url: &str) {
+        debug!("Blocked {} request: {}", resource_type.as_ref(), url);
+        let mut stats = self.stats.entry(target.to_string()).or_default();
+        stats.blocked_requests += 1;
+        *stats
+            .by_type
+            .entry(resource_type.as_ref().to_string())
+            .or_default() += 1;
+        stats.estimated_bytes_saved += typical_size(resource_type);
+    }
+
+    /// Record a request blocked by host rule for `target`.
+    pub fn record_host(&self, target: &str, rule: &str, url: &str) {
+        debug!("Blocked request to {}: {}", rule, url);
+        let mut stats = self.stats.entry(target.to_string()).or_default();
+        stats.blocked_requests += 1;
+        *stats.by_host.entry(rule.to_string()).or_default() += 1;
+    }
+
+    /// Record the time spent loading `target` in the browser.
+    pub fn record_page_load(&self, target: &str, secs: f64) {
+        self.stats.entry(target.to_string()).or_default().page_load_secs += secs;
+    }
+
+    /// Take the statistics collected so far for `target`.
+    ///
+    /// `None` when the target never went through the browser, e.g. `--js-urls` scans.
+    pub fn take_stats(&self, target: &str) -> Option<BlockStats> {
+        self.stats.remove(target).map(|(_, stats)| stats)
+    }
+}
+
+/// Lowercased host of a URL, without IPv6 brackets.
+fn request_host(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+    let host = parsed.host_str()?;
+    Some(host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase())
+}
+
+/// Match a host rule: a glob, or a plain host that also covers its subdomains.
+fn host_matches(rule: &str, host: &str) -> bool {
+    if rule.contains('*') {
+        return glob_match(rule, host);
+    }
+    host == rule
+        || host
+            .strip_suffix(rule)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Rough typical transfer size of one response of a resource type, to estimate
+/// the bandwidth blocking saves. Other types count nothing.
+fn typical_size(resource_type: &ResourceType) -> u64 {
+    match resource_type {
+        ResourceType::Image => 16 * 1024,
+        ResourceType::Stylesheet => 8 * 1024,
+        ResourceType::Font => 32 * 1024,
+        ResourceType::Media => 256 * 1024,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_types() {
+        let blocker = ResourceBlocker::new(&types(&["image", "Font", "media", "image"])).unwrap();
+        assert_eq!(
+            blocker.blocked_types(),
+            &[ResourceType::Image, ResourceType::Font, ResourceType::Media]
+        );
+        assert!(ResourceBlocker::new(&types(&["script"])).is_err());
+        assert!(ResourceBlocker::new(&types(&["document"])).is_err());
+        assert!(ResourceBlocker::new(&types(&["pictures"])).is_err());
+    }
+
+    #[test]
+    fn test_host_rules() {
+        let blocker = ResourceBlocker::new(&types(&["image"]))
+            .unwrap()
+            .with_trackers(true)
+            .with_block_hosts(&types(&["*.ads.example.net"]))
+            .with_allow_hosts(&types(&["img.example.com", "stats.g.doubleclick.net"]));
+
+        assert_eq!(
+            blocker.denied_host("https://www.google-analytics.com/analytics.js"),
+            Some("google-analytics.com")
+        );
+        assert_eq!(
+            blocker.denied_host("https://x.ads.example.net/pixel"),
+            Some("*.ads.example.net")
+        );
+        assert_eq!(blocker.denied_host("https://stats.g.doubleclick.net/collect"), None);
+        assert_eq!(blocker.denied_host("https://notgoogle-analytics.com/a.js"), None);
+
+        assert!(blocker.blocks_type(&ResourceType::Image, "https://cdn.example.com/logo.png"));
+        assert!(!blocker.blocks_type(&ResourceType::Image, "https://img.example.com/logo.png"));
+        assert!(!blocker.blocks_type(&ResourceType::Script, "https://cdn.example.com/app.js"));
+    }
+
+    #[test]
+    fn test_stats_per_target() {
+        let blocker = ResourceBlocker::new(&types(&["image"])).unwrap();
+        blocker.record_type("https://a.com", &ResourceType::Image, "https://a.com/logo.png");
+        blocker.record_type("https://a.com", &ResourceType::Image, "https://a.com/hero.jpg");
+        blocker.record_type("https://a.com", &ResourceType::Ping, "https://a.com/beacon");
+        blocker.record_host("https://a.com", "hotjar.com", "https://static.hotjar.com/c.js");
+        blocker.record_page_load("https://a.com", 1.5);
+
+        let stats = blocker.take_stats("https://a.com").unwrap();
+        assert_eq!(stats.blocked_requests, 4);
+        assert_eq!(stats.by_type.get("Image"), Some(&2));
+        assert_eq!(stats.by_type.get("Ping"), Some(&1));
+        assert_eq!(stats.by_host.get("hotjar.com"), Some(&1));
+        assert_eq!(stats.estimated_bytes_saved, 2 * 16 * 1024);
+        assert_eq!(stats.page_load_secs, 1.5);
+
+        // Targets that never loaded in the browser have no stats
+        assert!(blocker.take_stats("https://a.com").is_none());
+        assert!(ResourceBlocker::default().take_stats("https://a.com").is_none());
+    }
+}
@@ -1,82 +1,267 @@
 //! Source map probing - tries to find .map files even when not referenced.
+//!
+//! The prober learns per host: once a map is found with one URL template, sibling
+//! files on that host try the same template first. Hosts that answer every guess
+//! with SPA fallback HTML stop being probed.
 
-use crate::config::get_sourcemap_variations;
-use crate::types::Result;
+use crate::audit::{AuditComponent, AuditEntry, AuditLog};
+use crate::config::{expand_sourcemap_template, DEFAULT_SOURCEMAP_TEMPLATES};
+use crate::discovery::{build_http_client, record_stopped_redirect};
+use crate::discovery::politeness::{host_key, parse_retry_after, HostLimiter};
+use crate::scope::ScopeGuard;
+use crate::types::{HttpConfig, Result};
+use dashmap::DashMap;
 use reqwest::Client;
-use std::time::Duration;
+use std::sync::Arc;
 use tracing::{debug, trace};
 
+/// Guesses that must all hit SPA fallback HTML before a host stops being probed.
+const FALLBACK_GIVE_UP: usize = 6;
+
+/// Attempts for one map URL when the host answers 429/503.
+const MAX_RATE_LIMITED_ATTEMPTS: u32 = 3;
+
+/// Outcome of one probe request.
+enum ProbeOutcome {
+    Found(String),
+    /// 200 with an HTML page: the server answers every path with the app shell.
+    Fallback,
+    /// The host kept asking us to slow down; stop guessing for this file.
+    RateLimited,
+    Miss,
+}
+
+/// What the prober has learned about one host.
+#[derive(Debug, Default)]
+struct HostProbeState {
+    /// Template that found a map on this host.
+    learned: Option<String>,
+    guesses: usize,
+    fallbacks: usize,
+}
+
+impl HostProbeState {
+    /// Every guess so far was SPA fallback and nothing was ever found.
+    fn is_fallback_only(&self) -> bool {
+        self.learned.is_none() && self.guesses >= FALLBACK_GIVE_UP && self.fallbacks == self.guesses
+    }
+}
+
 /// Prober for discovering source map files.
 #[derive(Clone)]
 pub struct SourceMapProber {
     client: Client,
+    host_limiter: Arc<HostLimiter>,
+    scope: Option<Arc<ScopeGuard>>,
+    audit_log: Option<Arc<AuditLog>>,
+    /// User templates, tried before the built-in ones
+    templates: Vec<String>,
+    /// Per-host learned state, shared by all clones
+    hosts: Arc<DashMap<String, HostProbeState>>,
 }
 
 impl SourceMapProber {
-    /// Create a new source map prober.
-    pub fn new(timeout_secs: u64, user_agent: &str) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(timeout_secs))
-            .user_agent(user_agent)
-            .http1_only() // Force HTTP/1.1 to avoid HTTP/2 stream limit issues
-            .pool_max_idle_per_host(10)
-            .pool_idle_timeout(Duration::from_secs(30))
-            .build()?;
-
-        Ok(Self { client })
+    /// Create a new source map prober sharing `host_limiter` with other components
+    /// (e.g. the JS fetcher).
+    pub fn new(config: &HttpConfig, host_limiter: Arc<HostLimiter>) -> Result<Self> {
+        Ok(Self {
+            client: build_http_client(config, AuditComponent::SourceMapProber)?,
+            host_limiter,
+            scope: config.scope.clone(),
+            audit_log: config.audit_log.clone(),
+            templates: Vec::new(),
+            hosts: Arc::new(DashMap::new()),
+        })
+    }
+
+    /// Add custom URL templates (see `config::expand_sourcemap_template`).
+    pub fn with_templates(mut self, templates: Vec<String>) -> Self {
+        self.templates = templates;
+        self
     }
 
     /// Try to find a source map for a given JS file URL.
-    pub async fn probe(&self, js_url: &str) -> Option<(String, String)> {
-        let variations = get_sourcemap_variations(js_url);
+    ///
+    /// Out-of-scope variations are recorded against `target` and skipped.
+    pub async fn probe(&self, js_url: &str, target: &str) -> Option<(String, String)> {
+        let host = host_key(js_url)?;
+        if self.hosts.get(&host).is_some_and(|state| state.is_fallback_only()) {
+            trace!("Skipping source map probe for {}: host serves SPA fallback", js_url);
+            return None;
+        }
+
+        for (template, map_url) in self.candidates(&host, js_url) {
+            if let Some(scope) = &self.scope {
+                if !scope.check(&map_url, target) {
+                    continue;
+                }
+            }
+
+            let outcome = self.fetch_with_retry(&map_url, target).await;
 
-        for map_url in variations {
-            if let Some(content) = self.try_fetch_map(&map_url).await {
-                debug!("Found source map at: {}", map_url);
-                return Some((map_url, content));
+            let mut state = self.hosts.entry(host.clone()).or_default();
+            state.guesses += 1;
+            match outcome {
+                ProbeOutcome::Found(content) => {
+                    debug!("Found source map at: {}", map_url);
+                    if state.learned.as_deref() != Some(template.as_str()) {
+                        debug!("Learned source map template {} for {}", template, host);
+                        state.learned = Some(template);
+                    }
+                    return Some((map_url, content));
+                }
+                ProbeOutcome::Fallback => {
+                    state.fallbacks += 1;
+                    if state.is_fallback_only() {
+                        debug!("{} answers every source map guess with HTML, no longer probing", host);
+                        return None;
+                    }
+                }
+                ProbeOutcome::RateLimited => return None,
+                ProbeOutcome::Miss => {}
             }
         }
 
         None
     }
 
-    /// Try to fetch a source map URL.
-    async fn try_fetch_map(&self, url: &str) -> Option<String> {
+    /// Fetch a known source map URL, such as a remote section of an index map.
+    ///
+    /// Out-of-scope URLs are recorded against `target` and skipped.
+    pub async fn fetch_map(&self, map_url: &str, target: &str) -> Option<String> {
+        if let Some(scope) = &self.scope {
+            if !scope.check(map_url, target) {
+                return None;
+            }
+        }
+
+        match self.fetch_with_retry(map_url, target).await {
+            ProbeOutcome::Found(content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Map URLs to try for `js_url`, with the template they came from: the template
+    /// learned for this host first, then user templates, then the built-in ones.
+    fn candidates(&self, host: &str, js_url: &str) -> Vec<(String, String)> {
+        let learned = self.hosts.get(host).and_then(|state| state.learned.clone());
+        let templates = learned
+            .into_iter()
+            .chain(self.templates.iter().cloned())
+            .chain(DEFAULT_SOURCEMAP_TEMPLATES.iter().map(|t| t.to_string()));
+
+        let mut candidates: Vec<(String, String)> = Vec::new();
+        for template in templates {
+            if let Some(map_url) = expand_sourcemap_template(&template, js_url) {
+                if !candidates.iter().any(|(_, url)| *url == map_url) {
+                    candidates.push((template, map_url));
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Fetch a map URL, waiting out the host's cooldown and retrying when it
+    /// answers 429/503.
+    async fn fetch_with_retry(&self, url: &str, target: &str) -> ProbeOutcome {
+        let mut attempt = 1;
+        loop {
+            match self.try_fetch_map(url, target, attempt).await {
+                ProbeOutcome::RateLimited if attempt < MAX_RATE_LIMITED_ATTEMPTS => {
+                    // The limiter holds the next acquire until the cooldown has passed
+                    debug!("Rate limited probing {}, retrying (attempt {})", url, attempt + 1);
+                    attempt += 1;
+                }
+                outcome => return outcome,
+            }
+        }
+    }
+
+    /// Try to fetch a source map URL once; `attempt` scales the backoff on 429/503.
+    async fn try_fetch_map(&self, url: &str, target: &str, attempt: u32) -> ProbeOutcome {
         trace!("Probing source map: {}", url);
 
+        let audit_entry = |url: &str| {
+            AuditEntry::new(AuditComponent::SourceMapProber, "GET", url).with_target(target)
+        };
+
+        // Held until the body is read so the per-host in-flight cap is accurate
+        let _permit = self.host_limiter.acquire(url).await;
+
         let response = match self.client.get(url).send().await {
             Ok(r) => r,
-            Err(_) => return None,
+            Err(e) => {
+                self.audit(|| audit_entry(url).with_error(&e));
+                return ProbeOutcome::Miss;
+            }
         };
 
-        if !response.status().is_success() {
-            return None;
+        let status = response.status();
+        // Redirect hops are audited by the client; these entries are the final response
+        let final_url = response.url().to_string();
+        let audit_status = || audit_entry(&final_url).with_status(status.as_u16());
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+        {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_retry_after);
+            // Redirects may have led to another host; that is the one to slow down for
+            self.host_limiter.back_off(&final_url, attempt, retry_after);
+            self.audit(audit_status);
+            return ProbeOutcome::RateLimited;
+        }
+
+        if !status.is_success() {
+            self.audit(audit_status);
+            record_stopped_redirect(self.scope.as_deref(), &response, target);
+            return ProbeOutcome::Miss;
         }
 
         // Check content type
         if let Some(content_type) = response.headers().get("content-type") {
             if let Ok(ct) = content_type.to_str() {
+                if ct.contains("text/html") {
+                    self.audit(audit_status);
+                    return ProbeOutcome::Fallback;
+                }
                 // Accept JSON or source map content types
                 if !ct.contains("json")
                     && !ct.contains("sourcemap")
                     && !ct.contains("text/plain")
                     && !ct.contains("application/octet-stream")
                 {
-                    return None;
+                    self.audit(audit_status);
+                    return ProbeOutcome::Miss;
                 }
             }
         }
 
         let content = match response.text().await {
             Ok(c) => c,
-            Err(_) => return None,
+            Err(e) => {
+                self.audit(|| audit_status().with_error(&e));
+                return ProbeOutcome::Miss;
+            }
         };
+        self.audit(|| audit_status().with_bytes(content.len() as u64));
 
         // Verify it looks like a source map
         if self.is_valid_sourcemap(&content) {
-            Some(content)
+            ProbeOutcome::Found(content)
+        } else if content.trim_start().starts_with('<') {
+            ProbeOutcome::Fallback
         } else {
-            None
+            ProbeOutcome::Miss
+        }
+    }
+
+    /// Record a request in the audit log, if one is configured.
+    fn audit(&self, entry: impl FnOnce() -> AuditEntry) {
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.record(entry());
         }
     }
 
@@ -127,7 +312,7 @@ mod tests {
 
     #[test]
     fn test_is_valid_sourcemap() {
-        let prober = SourceMapProber::new(30, "test").unwrap();
+        let prober = SourceMapProber::new(&HttpConfig::default(), Arc::new(HostLimiter::default())).unwrap();
 
         let valid = r#"{"version":3,"sources":["src/main.js"],"mappings":"AAAA"}"#;
         assert!(prober.is_valid_sourcemap(valid));
@@ -139,6 +324,53 @@ mod tests {
         assert!(!prober.is_valid_sourcemap(html));
     }
 
+    #[test]
+    fn test_candidates_learned_first() {
+        let prober = SourceMapProber::new(&HttpConfig::default(), Arc::new(HostLimiter::default()))
+            .unwrap()
+            .with_templates(vec!["{origin}/__maps{path}{file}.map".to_string()]);
+        let js_url = "https://example.com/js/vendor.js";
+
+        let urls: Vec<String> = prober
+            .candidates("https://example.com", js_url)
+            .into_iter()
+            .map(|(_, url)| url)
+            .collect();
+        assert_eq!(urls[0], "https://example.com/__maps/js/vendor.js.map");
+        assert_eq!(urls[1], "https://example.com/js/vendor.js.map");
+
+        prober.hosts.entry("https://example.com".to_string()).or_default().learned =
+            Some("{dir}maps/{file}.map".to_string());
+        let urls: Vec<String> = prober
+            .candidates("https://example.com", js_url)
+            .into_iter()
+            .map(|(_, url)| url)
+            .collect();
+        assert_eq!(urls[0], "https://example.com/js/maps/vendor.js.map");
+        // Learned URL is not tried twice
+        assert_eq!(urls.iter().filter(|u| u.ends_with("/maps/vendor.js.map")).count(), 1);
+    }
+
+    #[test]
+    fn test_fallback_only_host() {
+        let mut state = HostProbeState {
+            guesses: FALLBACK_GIVE_UP,
+            fallbacks: FALLBACK_GIVE_UP,
+            ..Default::default()
+        };
+        assert!(state.is_fallback_only());
+
+        state.guesses += 1; // one real 404 means paths are not all rewritten
+        assert!(!state.is_fallback_only());
+
+        let learned = HostProbeState {
+            learned: Some("{dir}{file}.map".to_string()),
+            guesses: FALLBACK_GIVE_UP,
+            fallbacks: FALLBACK_GIVE_UP,
+        };
+        assert!(!learned.is_fallback_only());
+    }
+
     #[test]
     fn test_decode_inline_sourcemap() {
         // Base64 encoded: {"version":3}
@@ -0,0 +1,283 @@
+//! Export of original sources recovered from source maps.
+//!
+//! Every `sourcesContent` entry of every parsed map is written under
+//! `<dir>/<target>/`, at its source path with bundler prefixes removed. Paths
+//! never leave the target directory: `..` segments and absolute roots are
+//! dropped, and characters unsafe in file names are replaced. A `manifest.json`
+//! next to the files maps each one back to the map it came from.
+
+use crate::parser::sourcemap::embedded_sources;
+use crate::types::Result;
+use dashmap::DashMap;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Name of the per-target manifest file.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Longest file or directory name written, in bytes.
+const MAX_COMPONENT_LEN: usize = 120;
+
+/// One exported file, as listed in the manifest.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ExportedSource {
+    /// Path of the written file, relative to the target directory.
+    pub path: String,
+    /// Source path as written in the map's `sources`.
+    pub source: String,
+    /// Map the file was embedded in, or `inline` for a `data:` URL map.
+    pub map_url: String,
+    pub bytes: usize,
+    pub sha256: String,
+}
+
+/// Files exported so far for one target.
+#[derive(Debug, Default)]
+struct TargetExport {
+    files: Vec<ExportedSource>,
+    /// Content hash of each written path, to skip copies and rename clashes.
+    written: HashMap<PathBuf, String>,
+}
+
+/// Writes embedded sources to disk, one directory per target.
+#[derive(Debug)]
+pub struct SourceExporter {
+    root: PathBuf,
+    targets: DashMap<String, TargetExport>,
+}
+
+impl SourceExporter {
+    /// Export into `root`, creating it if needed.
+    pub fn new(root: &Path) -> Result<Self> {
+        std::fs::create_dir_all(root)?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            targets: DashMap::new(),
+        })
+    }
+
+    /// Directory the sources of `target` are written to.
+    pub fn target_dir(&self, target: &str) -> PathBuf {
+        self.root.join(target_dir_name(target))
+    }
+
+    /// Write the embedded sources of a source map found while scanning `target`.
+    ///
+    /// Paths are reserved under the target's entry, which is released before any
+    /// file is written.
+    pub async fn export(&self, target: &str, map_url: &str, map_content: &str) {
+        let sources = match embedded_sources(map_content, map_url) {
+            Ok(sources) => sources,
+            Err(e) => {
+                debug!("Not exporting sources of {}: {}", map_url, e);
+                return;
+            }
+        };
+        if sources.is_empty() {
+            return;
+        }
+
+        // Inline maps are attributed to their whole data: URL elsewhere; keep the manifest readable
+        let inline = map_url.starts_with("data:");
+
+        let dir = self.target_dir(target);
+        let mut pending = Vec::new();
+        let mut export = self.targets.entry(target.to_string()).or_default();
+        for source in sources {
+            let Some(clean) = clean_source_path(&source.path) else {
+                debug!("Skipping source with no usable path: {:?}", source.path);
+                continue;
+            };
+            let hash = hex::encode(Sha256::digest(source.content.as_bytes()));
+
+            // Identical copies are written once; different files on one path get a suffix
+            let mut path = clean.clone();
+            let mut n = 0;
+            while let Some(existing) = export.written.get(&path) {
+                if *existing == hash {
+                    break;
+                }
+                n += 1;
+                path = with_suffix(&clean, n);
+            }
+            if export.written.contains_key(&path) {
+                continue;
+            }
+
+            export.written.insert(path.clone(), hash.clone());
+            export.files.push(ExportedSource {
+                path: path.to_string_lossy().replace('\\', "/"),
+                source: source.path,
+                map_url: if inline { "inline".to_string() } else { source.map_url },
+                bytes: source.content.len(),
+                sha256: hash,
+            });
+            pending.push((path, source.content));
+        }
+        drop(export);
+
+        let mut failed = Vec::new();
+        for (path, content) in pending {
+            let file = dir.join(&path);
+            let written = async {
+                if let Some(parent) = file.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(&file, content).await
+            };
+            if let Err(e) = written.await {
+                warn!("Failed to export {}: {}", file.display(), e);
+                failed.push(path);
+            }
+        }
+
+        // Release the paths that could not be written so the manifest stays accurate
+        if !failed.is_empty() {
+            if let Some(mut export) = self.targets.get_mut(target) {
+                for path in &failed {
+                    export.written.remove(path);
+                    let listed = path.to_string_lossy().replace('\\', "/");
+                    export.files.retain(|file| file.path != listed);
+                }
+            }
+        }
+    }
+
+    /// Write the manifest for `target` and return the number of exported files.
+    pub async fn finish(&self, target: &str) -> usize {
+        let Some((_, export)) = self.targets.remove(target) else {
+            return 0;
+        };
+        let manifest = self.target_dir(target).join(MANIFEST_FILE);
+        let written = match serde_json::to_vec_pretty(&export.files) {
+            Ok(json) => tokio::fs::write(&manifest, json).await,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = written {
+            warn!("Failed to write {}: {}", manifest.display(), e);
+        }
+        export.files.len()
+    }
+}
+
+/// Directory name for a target: its host, port and path, with unsafe characters replaced.
+fn target_dir_name(target: &str) -> String {
+    let name = match url::Url::parse(target) {
+        Ok(url) => {
+            let mut name = url.host_str().unwrap_or("target").to_string();
+            if let Some(port) = url.port() {
+                name.push_str(&format!("_{}", port));
+            }
+            let path = url.path().trim_matches('/');
+            if !path.is_empty() {
+                name.push('_');
+                name.push_str(&path.replace('/', "_"));
+            }
+            name
+        }
+        Err(_) => target.replace('/', "_"),
+    };
+    sanitize_component(&name).unwrap_or_else(|| "target".to_string())
+}
+
+/// Turn a `sources` entry into a relative path that stays inside the export directory.
+///
+/// `webpack://app/./src/a.ts?1234` becomes `app/src/a.ts`, and
+/// `../../etc/passwd` becomes `etc/passwd`.
+pub fn clean_source_path(source: &str) -> Option<PathBuf> {
+    let source = source.split(['?', '#']).next().unwrap_or_default();
+    let source = source.split_once("://").map_or(source, |(_, rest)| rest);
+
+    let path: PathBuf = source
+        .split(['/', '\\'])
+        .filter(|part| !matches!(*part, "" | "." | ".."))
+        .filter_map(sanitize_component)
+        .collect();
+    (path.components().next().is_some()).then_some(path)
+}
+
+/// Replace characters that are unsafe in file names and cap the length.
+fn sanitize_component(part: &str) -> Option<String> {
+    let mut clean: String = part
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    if clean.len() > MAX_COMPONENT_LEN {
+        let mut end = MAX_COMPONENT_LEN;
+        while !clean.is_char_boundary(end) {
+            end -= 1;
+        }
+        clean.truncate(end);
+    }
+    // A name made only of dots would still walk up the tree on some systems
+    (!clean.trim_matches('.').is_empty()).then_some(clean)
+}
+
+/// `dir/name.ext` with `~n` inserted before the extension.
+fn with_suffix(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}~{}.{}", stem, n, ext.to_string_lossy()),
+        None => format!("{}~{}", stem, n),
+    };
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_source_path() {
+        let clean = |s| clean_source_path(s).map(|p| p.to_string_lossy().replace('\\', "/"));
+        assert_eq!(clean("webpack://acme-app/./src/auth/login.ts?a1b2").as_deref(), Some("acme-app/src/auth/login.ts"));
+        assert_eq!(clean("webpack:///./src/index.js").as_deref(), Some("src/index.js"));
+        assert_eq!(clean("../../../../etc/passwd").as_deref(), Some("etc/passwd"));
+        assert_eq!(clean("/abs/path/a.js").as_deref(), Some("abs/path/a.js"));
+        assert_eq!(clean("C:\\build\\src\\a.js").as_deref(), Some("C_/build/src/a.js"));
+        assert_eq!(clean("src/.../x.js").as_deref(), Some("src/x.js"));
+        assert_eq!(clean("webpack:///.."), None);
+    }
+
+    #[test]
+    fn test_target_dir_name() {
+        assert_eq!(target_dir_name("https://example.com"), "example.com");
+        assert_eq!(target_dir_name("http://localhost:8080/app/"), "localhost_8080_app");
+    }
+
+    #[tokio::test]
+    async fn test_export_and_manifest() {
+        let root = std::env::temp_dir().join(format!("depfused-export-{}", std::process::id()));
+        let exporter = SourceExporter::new(&root).unwrap();
+        let map = r#"{
+            "version": 3,
+            "sources": ["webpack:///./src/a.js", "webpack:///../src/a.js", "webpack:///./src/a.js", "webpack:///../../secret.js"],
+            "sourcesContent": ["one", "two", "one", "three"],
+            "mappings": "",
+            "names": []
+        }"#;
+
+        exporter.export("https://example.com", "https://example.com/main.js.map", map).await;
+        assert_eq!(exporter.finish("https://example.com").await, 3);
+
+        let dir = root.join("example.com");
+        assert_eq!(std::fs::read_to_string(dir.join("src/a.js")).unwrap(), "one");
+        assert_eq!(std::fs::read_to_string(dir.join("src/a~1.js")).unwrap(), "two");
+        assert_eq!(std::fs::read_to_string(dir.join("secret.js")).unwrap(), "three");
+
+        let manifest: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(dir.join(MANIFEST_FILE)).unwrap()).unwrap();
+        assert_eq!(manifest[1]["path"], "src/a~1.js");
+        assert_eq!(manifest[1]["source"], "webpack:///../src/a.js");
+        assert_eq!(manifest[1]["map_url"], "https://example.com/main.js.map");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
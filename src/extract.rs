@@ -0,0 +1,431 @@
+//! Package extractors.
+//!
+//! Every fetched JS file is run through the scanner's extractors in order. The
+//! built-in ones wrap the parsers: source maps first, since they also decide
+//! which names are workspace-only and provide the map that locates extractions
+//! in the original sources, then AST, webpack, bundler patterns and
+//! deobfuscation. Library users can add, remove and reorder extractors through
+//! [`Scanner::extractors_mut`](crate::Scanner::extractors_mut).
+
+use crate::discovery::{JsFetcher, SourceMapProber};
+use crate::export::SourceExporter;
+use crate::parser::{
+    attach_evidence, AstParser, BundlerParser, Deobfuscator, ParsedSourceMap, SourceMapLookup,
+    SourceMapParser, WebpackParser,
+};
+use crate::types::{DepfusedError, JsFile, JsSource, Package, Result};
+use futures::future::{self, BoxFuture, FutureExt};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// Extracts package names from a JS file.
+pub trait Extractor: Send + Sync {
+    /// Short name identifying the extractor in the registry.
+    fn name(&self) -> &str;
+
+    /// Packages found in `file`. Extractors may fetch related resources through
+    /// `ctx.fetcher`.
+    fn extract<'a>(&'a self, file: &'a JsFile, ctx: &'a ExtractContext) -> BoxFuture<'a, Vec<Package>>;
+}
+
+/// What the extractors of one file share.
+pub struct ExtractContext {
+    /// Target the file was loaded for.
+    pub target: String,
+    pub fetcher: Arc<JsFetcher>,
+    workspace_only: Mutex<HashSet<String>>,
+    lookup: Mutex<Option<SourceMapLookup>>,
+}
+
+impl ExtractContext {
+    pub fn new(target: &str, fetcher: Arc<JsFetcher>) -> Self {
+        Self {
+            target: target.to_string(),
+            fetcher,
+            workspace_only: Mutex::default(),
+            lookup: Mutex::default(),
+        }
+    }
+
+    /// Mark names as workspace-only; they are dropped from every extractor's results.
+    pub fn add_workspace_only(&self, names: impl IntoIterator<Item = String>) {
+        if let Ok(mut workspace_only) = self.workspace_only.lock() {
+            workspace_only.extend(names);
+        }
+    }
+
+    /// Locate the file's extractions through `lookup`, unless a map was offered before.
+    pub fn offer_lookup(&self, lookup: SourceMapLookup) {
+        if let Ok(mut current) = self.lookup.lock() {
+            current.get_or_insert(lookup);
+        }
+    }
+
+    /// Workspace-only names and the source map lookup gathered for the file.
+    pub fn into_parts(self) -> (HashSet<String>, Option<SourceMapLookup>) {
+        (
+            self.workspace_only.into_inner().unwrap_or_default(),
+            self.lookup.into_inner().unwrap_or_default(),
+        )
+    }
+}
+
+/// The extractors a scanner runs, in order.
+#[derive(Clone, Default)]
+pub struct Extractors {
+    extractors: Vec<Arc<dyn Extractor>>,
+}
+
+impl Extractors {
+    /// Names of the extractors, in the order they run.
+    pub fn names(&self) -> Vec<&str> {
+        self.extractors.iter().map(|e| e.name()).collect()
+    }
+
+    /// Run `extractor` after the existing ones.
+    pub fn add(&mut self, extractor: impl Extractor + 'static) {
+        self.extractors.push(Arc::new(extractor));
+    }
+
+    /// Run `extractor` at position `index`, or last if `index` is past the end.
+    pub fn insert(&mut self, index: usize, extractor: impl Extractor + 'static) {
+        let index = index.min(self.extractors.len());
+        self.extractors.insert(index, Arc::new(extractor));
+    }
+
+    /// Remove the extractor named `name`, returning whether there was one.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.extractors.len();
+        self.extractors.retain(|e| e.name() != name);
+        self.extractors.len() < before
+    }
+
+    /// Run the named extractors first, in the given order, then the others as before.
+    pub fn reorder(&mut self, names: &[&str]) -> Result<()> {
+        if let Some(unknown) = names.iter().find(|name| !self.names().contains(name)) {
+            return Err(DepfusedError::ConfigError(format!(
+                "Unknown extractor '{}' (have {})",
+                unknown,
+                self.names().join(", ")
+            )));
+        }
+        let rank = |e: &Arc<dyn Extractor>| {
+            names
+                .iter()
+                .position(|name| *name == e.name())
+                .unwrap_or(names.len())
+        };
+        // Stable, so unnamed extractors keep their relative order
+        self.extractors.sort_by_key(rank);
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn Extractor>> {
+        self.extractors.iter()
+    }
+}
+
+/// The built-in extractors, in their default order.
+pub fn builtin_extractors(
+    include_low: bool,
+    prober: SourceMapProber,
+    exporter: Option<Arc<SourceExporter>>,
+    bundler: BundlerParser,
+) -> Extractors {
+    let mut extractors = Extractors::default();
+    extractors.add(SourceMapExtractor {
+        parser: SourceMapParser::new(),
+        prober,
+        exporter,
+    });
+    extractors.add(AstParser::new(include_low));
+    extractors.add(WebpackExtractor {
+        parser: WebpackParser::new(),
+        ast: AstParser::new(include_low),
+    });
+    extractors.add(bundler);
+    extractors.add(Deobfuscator::new().with_include_low(include_low));
+    extractors
+}
+
+impl Extractor for AstParser {
+    fn name(&self) -> &str {
+        "ast"
+    }
+
+    fn extract<'a>(&'a self, file: &'a JsFile, _ctx: &'a ExtractContext) -> BoxFuture<'a, Vec<Package>> {
+        future::ready(self.parse(&file.content, &file.url).unwrap_or_default()).boxed()
+    }
+}
+
+impl Extractor for BundlerParser {
+    fn name(&self) -> &str {
+        "bundler"
+    }
+
+    fn extract<'a>(&'a self, file: &'a JsFile, _ctx: &'a ExtractContext) -> BoxFuture<'a, Vec<Package>> {
+        future::ready(self.extract_packages(&file.content, &file.url)).boxed()
+    }
+}
+
+impl Extractor for Deobfuscator {
+    fn name(&self) -> &str {
+        "deobfuscate"
+    }
+
+    fn extract<'a>(&'a self, file: &'a JsFile, _ctx: &'a ExtractContext) -> BoxFuture<'a, Vec<Package>> {
+        let packages = if self.is_likely_obfuscated(&file.content) {
+            debug!("Detected obfuscated content in {}, running deobfuscation", file.url);
+            self.extract_packages(&file.content, &file.url)
+        } else {
+            Vec::new()
+        };
+        future::ready(packages).boxed()
+    }
+}
+
+/// Webpack chunk manifests, plus the build manifests of Next.js apps.
+pub struct WebpackExtractor {
+    parser: WebpackParser,
+    ast: AstParser,
+}
+
+impl Extractor for WebpackExtractor {
+    fn name(&self) -> &str {
+        "webpack"
+    }
+
+    fn extract<'a>(&'a self, file: &'a JsFile, ctx: &'a ExtractContext) -> BoxFuture<'a, Vec<Package>> {
+        async move {
+            if !self.parser.is_webpack_bundle(&file.content) {
+                return Vec::new();
+            }
+            let mut packages = self.parser.extract_packages(&file.content, &file.url);
+
+            // Check for Next.js and extract build manifests
+            if let Some(build_id) = self.parser.extract_nextjs_build_id(&file.content) {
+                for url in self.parser.get_nextjs_manifest_urls(&ctx.target, &build_id) {
+                    if let Some(manifest) = ctx.fetcher.fetch_one(&url, JsSource::Probe, &ctx.target).await {
+                        if let Ok(mut pkgs) = self.ast.parse(&manifest.content, &manifest.url) {
+                            attach_evidence(&mut pkgs, &manifest.content);
+                            packages.extend(pkgs);
+                        }
+                    }
+                }
+            }
+            packages
+        }
+        .boxed()
+    }
+}
+
+/// Source maps the file declares or that probing finds next to it.
+///
+/// Also reports workspace-only names and the map's lookup through the context.
+pub struct SourceMapExtractor {
+    parser: SourceMapParser,
+    prober: SourceMapProber,
+    exporter: Option<Arc<SourceExporter>>,
+}
+
+impl Extractor for SourceMapExtractor {
+    fn name(&self) -> &str {
+        "sourcemap"
+    }
+
+    fn extract<'a>(&'a self, file: &'a JsFile, ctx: &'a ExtractContext) -> BoxFuture<'a, Vec<Package>> {
+        async move {
+            let mut packages = Vec::new();
+            let mut take = |parsed: ParsedSourceMap| {
+                packages.extend(parsed.packages);
+                ctx.add_workspace_only(parsed.workspace_only);
+                if let Some(lookup) = parsed.lookup {
+                    ctx.offer_lookup(lookup);
+                }
+            };
+
+            let mut declared_map_parsed = false;
+            if let Some(ref map_url) = file.source_map_url {
+                let content = if map_url.starts_with("data:") {
+                    // Inline source map
+                    SourceMapProber::decode_inline_sourcemap(map_url)
+                } else {
+                    // Fetch external source map
+                    ctx.fetcher
+                        .fetch_one(map_url, JsSource::Probe, &ctx.target)
+                        .await
+                        .map(|map_js| map_js.content)
+                };
+                if let Some(content) = content {
+                    if let Some(parsed) = self.parse(&content, map_url, &ctx.target).await {
+                        take(parsed);
+                        declared_map_parsed = true;
+                    }
+                }
+            }
+
+            // Also probe for .map even if not referenced (but only for bundled files)
+            // A map the server announced in a header beats guessed URL variations
+            let header_map_found = declared_map_parsed && file.sourcemap_header().is_some();
+            if is_likely_bundled(file) && !header_map_found {
+                if let Some((map_url, content)) = self.prober.probe(&file.url, &ctx.target).await {
+                    if let Some(parsed) = self.parse(&content, &map_url, &ctx.target).await {
+                        take(parsed);
+                    }
+                }
+            }
+
+            packages
+        }
+        .boxed()
+    }
+}
+
+/// Remote sections of one index map that are fetched at most.
+const MAX_REMOTE_SECTIONS: usize = 32;
+
+impl SourceMapExtractor {
+    /// Parse a source map, fetching and parsing the remote sections of an index map.
+    /// Embedded sources are written out when `--export-sources` is set.
+    ///
+    /// Packages and workspace-only names of all sections are merged into the
+    /// returned map, whose lookup covers the top-level map.
+    async fn parse(&self, content: &str, map_url: &str, target: &str) -> Option<ParsedSourceMap> {
+        let mut parsed = match self.parser.parse_map(content, map_url) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                debug!("Failed to parse source map {}: {}", map_url, e);
+                return None;
+            }
+        };
+        if let Some(exporter) = &self.exporter {
+            exporter.export(target, map_url, content).await;
+        }
+
+        if parsed.remote_sections.len() > MAX_REMOTE_SECTIONS {
+            debug!(
+                "Index map {} has {} remote sections, fetching the first {}",
+                map_url,
+                parsed.remote_sections.len(),
+                MAX_REMOTE_SECTIONS
+            );
+        }
+        let remote_sections = std::mem::take(&mut parsed.remote_sections);
+        for section_url in remote_sections.iter().take(MAX_REMOTE_SECTIONS) {
+            let Some(section) = self.prober.fetch_map(section_url, target).await else {
+                debug!("Could not fetch source map section {}", section_url);
+                continue;
+            };
+            if let Some(exporter) = &self.exporter {
+                exporter.export(target, section_url, &section).await;
+            }
+            // Sections are fetched one level deep; nested remote sections are not followed
+            match self.parser.parse_map(&section, section_url) {
+                Ok(section) => parsed.merge_section(section),
+                Err(e) => debug!("Failed to parse source map section {}: {}", section_url, e),
+            }
+        }
+
+        Some(parsed)
+    }
+}
+
+/// Whether a file looks bundled (webpack/vite/bundler patterns), so worth probing for a map.
+fn is_likely_bundled(file: &JsFile) -> bool {
+    file.content.contains("webpackChunk")
+        || file.content.contains("__vite__")
+        || file.content.contains("parcelRequire")
+        || file.content.contains("__commonJS")
+        || file.content.contains("__toESM")
+        || file.content.contains("__require")
+        || file.content.contains("/*#__PURE__*/")
+        || file.url.contains(".bundle.js")
+        || file.url.ends_with("/bundle.js")
+        || file.url.contains("chunk")
+        || file.url.contains("vendor")
+        || file.url.contains("/main-")
+        || file.url.contains("/main.")
+        || file.content.len() > 50_000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Confidence, ExtractionMethod, HttpConfig};
+    use crate::discovery::politeness::HostLimiter;
+
+    /// Reports every `@acme/` string it sees.
+    struct AcmeExtractor;
+
+    impl Extractor for AcmeExtractor {
+        fn name(&self) -> &str {
+            "acme"
+        }
+
+        fn extract<'a>(&'a self, file: &'a JsFile, _ctx: &'a ExtractContext) -> BoxFuture<'a, Vec<Package>> {
+            let packages = file
+                .content
+                .match_indices("@acme/")
+                .map(|(i, _)| {
+                    let end = file.content[i..].find(['"', '\'']).map_or(file.content.len(), |n| i + n);
+                    Package::new(
+                        file.content[i..end].to_string(),
+                        ExtractionMethod::Comment,
+                        file.url.clone(),
+                        Confidence::Low,
+                    )
+                })
+                .collect();
+            future::ready(packages).boxed()
+        }
+    }
+
+    fn builtins() -> Extractors {
+        let prober =
+            SourceMapProber::new(&HttpConfig::default(), Arc::new(HostLimiter::default())).unwrap();
+        builtin_extractors(true, prober, None, BundlerParser::new())
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut extractors = builtins();
+        assert_eq!(extractors.names(), ["sourcemap", "ast", "webpack", "bundler", "deobfuscate"]);
+
+        extractors.insert(1, AcmeExtractor);
+        assert!(extractors.remove("deobfuscate"));
+        assert!(!extractors.remove("deobfuscate"));
+        assert_eq!(extractors.names(), ["sourcemap", "acme", "ast", "webpack", "bundler"]);
+
+        extractors.reorder(&["bundler", "ast"]).unwrap();
+        assert_eq!(extractors.names(), ["bundler", "ast", "sourcemap", "acme", "webpack"]);
+        assert!(extractors.reorder(&["nope"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_custom_extractor() {
+        let mut extractors = builtins();
+        extractors.remove("sourcemap");
+        extractors.add(AcmeExtractor);
+
+        let file = JsFile {
+            url: "https://example.com/app.js".to_string(),
+            content: r#"import x from "lodash-es"; const theme = "@acme/theme";"#.to_string(),
+            content_hash: String::new(),
+            source: JsSource::Probe,
+            source_map_url: None,
+            profiles: Vec::new(),
+            status: None,
+            headers: Default::default(),
+        };
+        let fetcher = Arc::new(JsFetcher::new(HttpConfig::default(), Arc::new(HostLimiter::default())).unwrap());
+        let ctx = ExtractContext::new("https://example.com", fetcher);
+
+        let mut names = Vec::new();
+        for extractor in extractors.iter() {
+            names.extend(extractor.extract(&file, &ctx).await.into_iter().map(|p| p.name));
+        }
+        assert!(names.contains(&"lodash-es".to_string()));
+        assert!(names.contains(&"@acme/theme".to_string()));
+    }
+}
@@ -20,12 +20,17 @@
 //! }
 //! ```
 
+pub mod audit;
 pub mod config;
 pub mod discovery;
+pub mod export;
+pub mod extract;
 pub mod notify;
 pub mod parser;
 pub mod registry;
 pub mod scanner;
+pub mod scope;
+pub mod scoring;
 pub mod types;
 
 pub mod browser;
@@ -76,8 +76,16 @@ async fn run_scan(scan_config: ScanConfig, global_config: &Config) -> Result<(),
         }
     };
 
-    if targets.is_empty() {
-        error!("No targets specified. Use positional arguments or -f <file>.");
+    let js_urls = match scan_config.load_js_urls() {
+        Ok(u) => u,
+        Err(e) => {
+            error!("Failed to load JS URLs: {}", e);
+            return Err(ExitCode::FAILURE);
+        }
+    };
+
+    if targets.is_empty() && js_urls.is_empty() {
+        error!("No targets specified. Use positional arguments, -f <file> or --js-urls <file>.");
         return Err(ExitCode::FAILURE);
     }
 
@@ -116,7 +124,13 @@ async fn run_scan(scan_config: ScanConfig, global_config: &Config) -> Result<(),
     }
 
     // Run scans
-    let results = scanner.scan_multiple(targets).await;
+    let mut results = Vec::new();
+    if !targets.is_empty() {
+        results.extend(scanner.scan_multiple(targets).await);
+    }
+    if !js_urls.is_empty() {
+        results.extend(scanner.scan_js_urls(js_urls).await);
+    }
 
     // Output results
     if scan_config.json {
@@ -145,13 +159,7 @@ async fn run_scan(scan_config: ScanConfig, global_config: &Config) -> Result<(),
         .map(|r| {
             r.findings
                 .iter()
-                .filter(|f| {
-                    matches!(
-                        f.npm_result,
-                        depfused::NpmCheckResult::NotFound { .. }
-                            | depfused::NpmCheckResult::ScopeNotClaimed { .. }
-                    )
-                })
+                .filter(|f| f.is_potential_vulnerability())
                 .count()
         })
         .sum();
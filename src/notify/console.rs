@@ -1,5 +1,6 @@
 //! Colored console output for scan results.
 
+use super::{format_breakdown, format_methods};
 use crate::types::{Confidence, Finding, NpmCheckResult, ScanResult, Severity};
 use colored::Colorize;
 use indicatif::{ProgressBar, ProgressStyle};
@@ -67,6 +68,13 @@ impl ConsoleOutput {
             NpmCheckResult::ScopeNotClaimed { scope, .. } => {
                 format!("SCOPE {} UNCLAIMED", scope).on_red().white().bold()
             }
+            NpmCheckResult::Exists { .. } if finding.internal_version_ahead() => format!(
+                "exists (public {} < bundled {})",
+                finding.highest_public_version().unwrap_or_default(),
+                finding.package.version.as_deref().unwrap_or_default()
+            )
+            .yellow()
+            .bold(),
             NpmCheckResult::Exists { .. } => "exists".green(),
             NpmCheckResult::Error { error, .. } => format!("error: {}", error).yellow(),
         };
@@ -80,8 +88,44 @@ impl ConsoleOutput {
         );
         println!("    |-- Status: {}", status);
         println!("    |-- Source: {}", finding.package.source_url.dimmed());
+        if let Some(original) = &finding.package.original {
+            println!("    |-- Location: {}", original.to_string().bright_white());
+        }
+        if let Some(evidence) = &finding.package.evidence {
+            println!(
+                "    |-- Evidence: {} {}",
+                format!("{}:{}", evidence.line, evidence.column).dimmed(),
+                evidence.snippet
+            );
+        }
         println!("    |-- Method: {:?}", finding.package.extraction_method);
-        println!("    +-- Confidence: {}", format_confidence(finding.package.confidence));
+        if finding.package.occurrences.len() > 1 {
+            println!(
+                "    |-- Seen: {} times via {}",
+                finding.package.occurrences.len(),
+                format_methods(&finding.package)
+            );
+        }
+        if let Some(version) = &finding.package.version {
+            println!("    |-- Version: {}", version);
+        }
+        if let Some(registry) = &finding.package.registry {
+            println!("    |-- Registry: {}", registry.yellow());
+        }
+        if !finding.package.profiles.is_empty() {
+            println!("    |-- Profiles: {}", finding.package.profiles.join(", "));
+        }
+        match &finding.package.score {
+            Some(score) => {
+                println!("    |-- Score: {}", format_breakdown(score).dimmed());
+                println!(
+                    "    +-- Confidence: {} ({}/100)",
+                    format_confidence(finding.package.confidence),
+                    score.value
+                );
+            }
+            None => println!("    +-- Confidence: {}", format_confidence(finding.package.confidence)),
+        }
 
         for note in &finding.notes {
             println!("        {}", note.dimmed());
@@ -100,12 +144,7 @@ impl ConsoleOutput {
         let vulnerability_count = result
             .findings
             .iter()
-            .filter(|f| {
-                matches!(
-                    f.npm_result,
-                    NpmCheckResult::NotFound { .. } | NpmCheckResult::ScopeNotClaimed { .. }
-                )
-            })
+            .filter(|f| f.is_potential_vulnerability())
             .count();
 
         // In quiet mode, only print if there are vulnerabilities
@@ -129,6 +168,45 @@ impl ConsoleOutput {
         println!("  Duration:  {:.2}s", result.duration_secs);
         println!("  JS files:  {}", result.js_files_count);
         println!("  Packages:  {}", result.packages_found);
+        if !result.skipped_requests.is_empty() {
+            println!(
+                "  Skipped:   {} out-of-scope requests",
+                result.skipped_requests.len()
+            );
+            if self.verbose {
+                for url in &result.skipped_requests {
+                    println!("    {}", url.dimmed());
+                }
+            }
+        }
+
+        if !result.filtered.is_empty() {
+            println!("  Filtered:  {} candidates", result.filtered.len());
+            if self.verbose {
+                for dropped in &result.filtered {
+                    println!(
+                        "    {} {} {}",
+                        dropped.name,
+                        dropped.rule.dimmed(),
+                        dropped.source_url.as_deref().unwrap_or_default().dimmed()
+                    );
+                }
+            }
+        }
+
+        if let Some(ref stats) = result.resource_blocking {
+            println!(
+                "  Blocked:   {} requests, ~{} saved, page load {:.2}s",
+                stats.blocked_requests,
+                format_bytes(stats.estimated_bytes_saved),
+                stats.page_load_secs
+            );
+            if self.verbose {
+                for (kind, count) in stats.by_type.iter().chain(stats.by_host.iter()) {
+                    println!("    {} {}", count, kind.dimmed());
+                }
+            }
+        }
 
         if vulnerability_count > 0 {
             println!(
@@ -182,6 +260,15 @@ fn format_confidence(confidence: Confidence) -> colored::ColoredString {
     }
 }
 
+/// Format a byte count as B, KB or MB.
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
 impl Default for ConsoleOutput {
     fn default() -> Self {
         Self::new(false, false, false)
@@ -206,4 +293,11 @@ mod tests {
         format_confidence(Confidence::Medium);
         format_confidence(Confidence::Low);
     }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(2048), "2.0 KB");
+        assert_eq!(format_bytes(3 * 1_048_576), "3.0 MB");
+    }
 }
@@ -10,3 +10,25 @@ pub mod telegram;
 
 pub use console::ConsoleOutput;
 pub use telegram::TelegramNotifier;
+
+use crate::types::{ConfidenceScore, Package};
+
+/// Extraction methods that found a package, e.g. `Import, SourceMap`.
+fn format_methods(package: &Package) -> String {
+    package
+        .methods()
+        .iter()
+        .map(|m| format!("{:?}", m))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Signals of a score, e.g. `+50 Import extraction (Medium), -10 third-party file`.
+fn format_breakdown(score: &ConfidenceScore) -> String {
+    score
+        .breakdown
+        .iter()
+        .map(|s| format!("{:+} {}", s.points, s.signal))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
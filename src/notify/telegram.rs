@@ -1,8 +1,12 @@
 //! Telegram bot notifications for scan findings.
 
-use crate::types::{DepfusedError, Finding, NpmCheckResult, Result, Severity};
+use super::{format_breakdown, format_methods};
+use crate::audit::{AuditComponent, AuditEntry, AuditLog};
+use crate::discovery::apply_resolve_overrides;
+use crate::types::{DepfusedError, Finding, NpmCheckResult, ResolveOverride, Result, Severity};
 use reqwest::Client;
 use serde::Serialize;
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, error};
 
@@ -19,26 +23,50 @@ pub struct TelegramNotifier {
     client: Client,
     token: String,
     chat_id: String,
+    audit_log: Option<Arc<AuditLog>>,
 }
 
 impl TelegramNotifier {
     /// Create a new Telegram notifier.
     pub fn new(token: &str, chat_id: &str) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()?;
+        let client = Self::build_client(&[])?;
 
         Ok(Self {
             client,
             token: token.to_string(),
             chat_id: chat_id.to_string(),
+            audit_log: None,
         })
     }
 
+    /// Pin hostnames to specific addresses (`--resolve`).
+    pub fn with_resolve_overrides(mut self, overrides: &[ResolveOverride]) -> Result<Self> {
+        if !overrides.is_empty() {
+            self.client = Self::build_client(overrides)?;
+        }
+        Ok(self)
+    }
+
+    /// Build the Bot API client, so overrides keep the same timeout and user agent.
+    fn build_client(overrides: &[ResolveOverride]) -> Result<Client> {
+        let client = apply_resolve_overrides(Client::builder(), overrides)
+            .timeout(Duration::from_secs(30))
+            .user_agent("depfused/0.1")
+            .build()?;
+
+        Ok(client)
+    }
+
+    /// Record every Bot API request in the given audit log (with the token redacted).
+    pub fn with_audit_log(mut self, audit_log: Option<Arc<AuditLog>>) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
+
     /// Send a finding notification.
     pub async fn send_finding(&self, finding: &Finding, target: &str) -> Result<()> {
         let message = self.format_finding_message(finding, target);
-        self.send_message(&message).await
+        self.send_message(&message, target).await
     }
 
     /// Send a scan summary notification.
@@ -62,7 +90,7 @@ impl TelegramNotifier {
             emoji, target, findings_count, vulnerabilities_count
         );
 
-        self.send_message(&message).await
+        self.send_message(&message, target).await
     }
 
     /// Format a finding as a Telegram message.
@@ -80,10 +108,59 @@ impl TelegramNotifier {
             NpmCheckResult::ScopeNotClaimed { scope, .. } => {
                 format!("⚠️ SCOPE {} UNCLAIMED", scope)
             }
+            NpmCheckResult::Exists { .. } if finding.internal_version_ahead() => format!(
+                "⚠️ public {} < bundled {}",
+                finding.highest_public_version().unwrap_or_default(),
+                finding.package.version.as_deref().unwrap_or_default()
+            ),
             NpmCheckResult::Exists { .. } => "✓ exists".to_string(),
             NpmCheckResult::Error { error, .. } => format!("⚠️ error: {}", error),
         };
 
+        let version = finding
+            .package
+            .version
+            .as_ref()
+            .map(|v| format!("\n*Version:* `{}`", v))
+            .unwrap_or_default();
+        let location = finding
+            .package
+            .original
+            .as_ref()
+            .map(|o| format!("\n*Location:* `{}`", o))
+            .unwrap_or_default();
+        // Backticks would end the code span early
+        let evidence = finding
+            .package
+            .evidence
+            .as_ref()
+            .map(|e| {
+                format!(
+                    "\n*Evidence:* line {}, col {}\n`{}`",
+                    e.line,
+                    e.column,
+                    e.snippet.replace('`', "'")
+                )
+            })
+            .unwrap_or_default();
+        let (score, breakdown) = match &finding.package.score {
+            Some(score) => (
+                format!(" ({}/100)", score.value),
+                format!("\n*Score:* {}", format_breakdown(score)),
+            ),
+            None => Default::default(),
+        };
+        let seen = match finding.package.occurrences.len() {
+            0 | 1 => String::new(),
+            n => format!("\n*Seen:* {} times via {}", n, format_methods(&finding.package)),
+        };
+        let registry = finding
+            .package
+            .registry
+            .as_ref()
+            .map(|r| format!("\n*Private registry:* `{}`", r))
+            .unwrap_or_default();
+
         format!(
             "{} *Dependency Confusion Finding*\n\n\
              *Package:* `{}`\n\
@@ -91,19 +168,26 @@ impl TelegramNotifier {
              *Severity:* {:?}\n\
              *Target:* `{}`\n\
              *Source:* `{}`\n\
-             *Confidence:* {:?}",
+             *Confidence:* {:?}{}{}{}{}{}{}{}",
             severity_emoji,
             finding.package.name,
             status,
             finding.severity,
             target,
             finding.package.source_url,
-            finding.package.confidence
+            finding.package.confidence,
+            score,
+            breakdown,
+            location,
+            seen,
+            version,
+            registry,
+            evidence
         )
     }
 
     /// Send a raw message via Telegram Bot API.
-    async fn send_message(&self, text: &str) -> Result<()> {
+    async fn send_message(&self, text: &str, target: &str) -> Result<()> {
         let url = format!(
             "https://api.telegram.org/bot{}/sendMessage",
             self.token
@@ -115,12 +199,29 @@ impl TelegramNotifier {
             parse_mode: "Markdown",
         };
 
-        let response = self
-            .client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await?;
+        let result = self.client.post(&url).json(&body).send().await;
+
+        if let Some(ref audit_log) = self.audit_log {
+            let entry = AuditEntry::new(
+                AuditComponent::Telegram,
+                "POST",
+                "https://api.telegram.org/bot<redacted>/sendMessage",
+            )
+            .with_target(target);
+            audit_log.record(match &result {
+                Ok(response) => {
+                    let entry = entry.with_status(response.status().as_u16());
+                    match response.content_length() {
+                        Some(len) => entry.with_bytes(len),
+                        None => entry,
+                    }
+                }
+                // reqwest errors include the URL, which contains the bot token
+                Err(e) => entry.with_error(e.to_string().replace(&self.token, "<redacted>")),
+            });
+        }
+
+        let response = result?;
 
         if response.status().is_success() {
             debug!("Telegram message sent successfully");
@@ -137,16 +238,16 @@ impl TelegramNotifier {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{Confidence, ExtractionMethod, Package};
+    use crate::types::{Confidence, Evidence, ExtractionMethod, Package, Span};
 
     fn make_test_finding() -> Finding {
         Finding {
-            package: Package {
-                name: "@internal/test-pkg".to_string(),
-                extraction_method: ExtractionMethod::SourceMap,
-                source_url: "https://example.com/bundle.js".to_string(),
-                confidence: Confidence::High,
-            },
+            package: Package::new(
+                "@internal/test-pkg".to_string(),
+                ExtractionMethod::SourceMap,
+                "https://example.com/bundle.js".to_string(),
+                Confidence::High,
+            ),
             npm_result: NpmCheckResult::NotFound {
                 name: "@internal/test-pkg".to_string(),
             },
@@ -162,4 +263,85 @@ mod tests {
         let finding = make_test_finding();
         assert!(!finding.package.name.is_empty());
     }
+
+    #[test]
+    fn test_format_version_ahead() {
+        let notifier = TelegramNotifier::new("token", "chat").unwrap();
+        let mut finding = make_test_finding();
+        finding.package = finding.package.with_version(Some("4.1.0".to_string()));
+        finding.npm_result = NpmCheckResult::Exists {
+            name: "@internal/test-pkg".to_string(),
+            latest_version: Some("v4.0.3".to_string()),
+            published_versions: vec!["v4.0.3".to_string(), "4.0.4-beta.1".to_string()],
+        };
+        assert!(finding.internal_version_ahead());
+        assert!(finding.is_potential_vulnerability());
+
+        let message = notifier.format_finding_message(&finding, "https://example.com");
+        assert!(message.contains("public 4.0.4-beta.1 < bundled 4.1.0"));
+        assert!(message.contains("*Version:* `4.1.0`"));
+
+        finding.package = finding.package.with_version(Some("3.9.9".to_string()));
+        assert!(!finding.is_potential_vulnerability());
+        finding.package = finding.package.with_version(Some("not-a-version".to_string()));
+        assert!(!finding.internal_version_ahead());
+    }
+
+    #[test]
+    fn test_version_published_under_other_tag() {
+        let mut finding = make_test_finding();
+        finding.package = finding.package.with_version(Some("5.0.0-rc.1".to_string()));
+        finding.npm_result = NpmCheckResult::Exists {
+            name: "@internal/test-pkg".to_string(),
+            latest_version: Some("4.0.3".to_string()),
+            published_versions: vec!["4.0.3".to_string(), "5.0.0-rc.1".to_string()],
+        };
+        assert!(!finding.internal_version_ahead());
+        assert!(!finding.is_potential_vulnerability());
+
+        finding.package = finding.package.with_version(Some("4.2.0".to_string()));
+        assert!(!finding.internal_version_ahead());
+        finding.package = finding.package.with_version(Some("5.0.0".to_string()));
+        assert!(finding.internal_version_ahead());
+    }
+
+    #[test]
+    fn test_format_occurrences() {
+        let notifier = TelegramNotifier::new("token", "chat").unwrap();
+        let mut finding = make_test_finding();
+        let message = notifier.format_finding_message(&finding, "https://example.com");
+        assert!(!message.contains("*Seen:*"));
+
+        let mut import = finding.package.occurrence();
+        import.extraction_method = ExtractionMethod::Import;
+        finding.package.occurrences = vec![finding.package.occurrence(), import];
+        let message = notifier.format_finding_message(&finding, "https://example.com");
+        assert!(message.contains("*Seen:* 2 times via SourceMap, Import"));
+    }
+
+    #[test]
+    fn test_format_score() {
+        let notifier = TelegramNotifier::new("token", "chat").unwrap();
+        let mut finding = make_test_finding();
+        crate::scoring::score_package(&mut finding.package, "https://example.com");
+
+        let message = notifier.format_finding_message(&finding, "https://example.com");
+        assert!(message.contains("*Confidence:* High (90/100)"));
+        assert!(message.contains("*Score:* +70 SourceMap extraction (High), +15 name suggests internal use, +5 first-party file"));
+    }
+
+    #[test]
+    fn test_format_evidence() {
+        let notifier = TelegramNotifier::new("token", "chat").unwrap();
+        let mut finding = make_test_finding();
+        finding.package.evidence = Some(Evidence {
+            span: Span { start: 10, end: 38 },
+            line: 1,
+            column: 11,
+            snippet: "…n(){return require(`@internal/test-pkg`)}…".to_string(),
+        });
+
+        let message = notifier.format_finding_message(&finding, "https://example.com");
+        assert!(message.ends_with("*Evidence:* line 1, col 11\n`…n(){return require('@internal/test-pkg')}…`"));
+    }
 }
@@ -1,6 +1,7 @@
 //! AST-based JavaScript parser using oxc_parser.
 
-use crate::parser::{filters, normalize_package_name};
+use crate::parser::manifest::has_manifest_keys;
+use crate::parser::{explain, filters, normalize_package_name, PackageManifest};
 use crate::types::{Confidence, ExtractionMethod, Package, Result};
 use oxc_allocator::Allocator;
 use oxc_ast::ast::*;
@@ -8,6 +9,7 @@ use oxc_ast::visit::walk;
 use oxc_ast::Visit;
 use oxc_parser::Parser;
 use oxc_span::SourceType;
+use serde_json::{Map, Value};
 use std::collections::HashSet;
 use tracing::{debug, trace};
 
@@ -58,11 +60,13 @@ impl AstParser {
         let string_packages = self.extract_from_strings(content, source_url);
         visitor.packages.extend(string_packages);
 
-        let packages: Vec<Package> = visitor
+        let mut packages: Vec<Package> = visitor
             .packages
             .into_iter()
             .filter(|p| !filters::should_filter_package(&p.name, Some(content), Some(source_url)))
             .collect();
+        // Manifest packages are already filtered without the file as context
+        packages.extend(visitor.manifest_packages);
         debug!(
             "Extracted {} packages from AST: {}",
             packages.len(),
@@ -76,24 +80,18 @@ impl AstParser {
     fn extract_from_comments(&self, content: &str, source_url: &str) -> HashSet<Package> {
         let mut packages = HashSet::new();
 
-        // First, extract only comment text from the content
+        // Only look inside comments, with each comment's offset in the file
         // This prevents matching @scope/name patterns in actual import statements
-        let mut comment_text = String::new();
+        let mut comments = Vec::new();
 
         // Pattern for block comments: /* ... */
         if let Ok(block_comment_re) = regex::Regex::new(r"/\*[\s\S]*?\*/") {
-            for cap in block_comment_re.find_iter(content) {
-                comment_text.push_str(cap.as_str());
-                comment_text.push('\n');
-            }
+            comments.extend(block_comment_re.find_iter(content));
         }
 
         // Pattern for line comments: // ...
         if let Ok(line_comment_re) = regex::Regex::new(r"//[^\n]*") {
-            for cap in line_comment_re.find_iter(content) {
-                comment_text.push_str(cap.as_str());
-                comment_text.push('\n');
-            }
+            comments.extend(line_comment_re.find_iter(content));
         }
 
         // Only extract @scope/package patterns from within comment text
@@ -101,44 +99,51 @@ impl AstParser {
         // Pattern: // Built with @company/tool
         let package_pattern = regex::Regex::new(r"@([\w-]+)/([\w.-]+)").unwrap();
 
-        for cap in package_pattern.captures_iter(&comment_text) {
-            if let (Some(scope), Some(name)) = (cap.get(1), cap.get(2)) {
-                let match_start = cap.get(0).unwrap().start();
-                let match_end = cap.get(0).unwrap().end();
-
-                // Get the line containing this match for context checks
-                let line_start = comment_text[..match_start].rfind('\n').map(|p| p + 1).unwrap_or(0);
-                let line_end = comment_text[match_end..].find('\n').map(|p| match_end + p).unwrap_or(comment_text.len());
-                let same_line = &comment_text[line_start..line_end];
-
-                // FP: Skip @scope/package that appears on a line with a URL (CDN reference)
-                // Catches both:
-                //   - URLs before: https://cdn.com/npm/@scope/pkg/file.js
-                //   - URLs after:  "@scope/pkg":["https://cdn.com/file.js"]
-                //   - Extension maps: "@scope/pkg":"https://b.yzcdn.cn/..."
-                if same_line.contains("://") {
-                    continue;
-                }
+        for comment in comments {
+            let comment_text = comment.as_str();
+            for cap in package_pattern.captures_iter(comment_text) {
+                if let (Some(scope), Some(name)) = (cap.get(1), cap.get(2)) {
+                    let match_start = cap.get(0).unwrap().start();
+                    let match_end = cap.get(0).unwrap().end();
+
+                    // Get the line containing this match for context checks
+                    let line_start = comment_text[..match_start].rfind('\n').map(|p| p + 1).unwrap_or(0);
+                    let line_end = comment_text[match_end..].find('\n').map(|p| match_end + p).unwrap_or(comment_text.len());
+                    let same_line = &comment_text[line_start..line_end];
+
+                    // FP: Skip @scope/package that appears on a line with a URL (CDN reference)
+                    // Catches both:
+                    //   - URLs before: https://cdn.com/npm/@scope/pkg/file.js
+                    //   - URLs after:  "@scope/pkg":["https://cdn.com/file.js"]
+                    //   - Extension maps: "@scope/pkg":"https://b.yzcdn.cn/..."
+                    if same_line.contains("://") {
+                        continue;
+                    }
 
-                // FP: Skip lines that look like CDN/extension mappings
-                // Pattern: "@scope/pkg":[ or "@scope/pkg":" (JSON-like key mapped to a URL)
-                let after_match = &comment_text[match_end..line_end];
-                let after_trimmed = after_match.trim_start();
-                if after_trimmed.starts_with("\":[")
-                    || after_trimmed.starts_with("\":{")
-                    || after_trimmed.starts_with("\":\"http")
-                {
-                    continue;
-                }
+                    // FP: Skip lines that look like CDN/extension mappings
+                    // Pattern: "@scope/pkg":[ or "@scope/pkg":" (JSON-like key mapped to a URL)
+                    let after_match = &comment_text[match_end..line_end];
+                    let after_trimmed = after_match.trim_start();
+                    if after_trimmed.starts_with("\":[")
+                        || after_trimmed.starts_with("\":{")
+                        || after_trimmed.starts_with("\":\"http")
+                    {
+                        continue;
+                    }
 
-                let full_name = format!("@{}/{}", scope.as_str(), name.as_str());
-                if let Some(normalized) = normalize_package_name(&full_name) {
-                    packages.insert(Package {
-                        name: normalized,
-                        extraction_method: ExtractionMethod::Comment,
-                        source_url: source_url.to_string(),
-                        confidence: Confidence::Medium,
-                    });
+                    let full_name = format!("@{}/{}", scope.as_str(), name.as_str());
+                    if let Some(normalized) = normalize_package_name(&full_name) {
+                        let offset = comment.start();
+                        packages.insert(
+                            Package::new(
+                                normalized,
+                                ExtractionMethod::Comment,
+                                source_url.to_string(),
+                                Confidence::Medium,
+                            )
+                            .with_span(offset + match_start..offset + match_end),
+                        );
+                    }
                 }
             }
         }
@@ -167,12 +172,15 @@ impl AstParser {
                 for cap in re.captures_iter(content) {
                     if let Some(pkg) = cap.get(1) {
                         if let Some(normalized) = normalize_package_name(pkg.as_str()) {
-                            packages.insert(Package {
-                                name: normalized,
-                                extraction_method: ExtractionMethod::ErrorMessage,
-                                source_url: source_url.to_string(),
-                                confidence: Confidence::Low,
-                            });
+                            packages.insert(
+                                Package::new(
+                                    normalized,
+                                    ExtractionMethod::ErrorMessage,
+                                    source_url.to_string(),
+                                    Confidence::Low,
+                                )
+                                .with_span(pkg.range()),
+                            );
                         }
                     }
                 }
@@ -186,6 +194,8 @@ impl AstParser {
 /// Visitor for extracting package names from AST.
 struct PackageVisitor {
     packages: HashSet<Package>,
+    /// Packages from embedded package.json manifests.
+    manifest_packages: Vec<Package>,
     source_url: String,
     include_low_confidence: bool,
 }
@@ -194,47 +204,62 @@ impl PackageVisitor {
     fn new(source_url: String, include_low_confidence: bool) -> Self {
         Self {
             packages: HashSet::new(),
+            manifest_packages: Vec::new(),
             source_url,
             include_low_confidence,
         }
     }
 
-    fn add_package(&mut self, name: &str, method: ExtractionMethod, confidence: Confidence) {
-        if confidence == Confidence::Low && !self.include_low_confidence {
-            return;
+    /// Add a manifest's packages, located at the JSON text or object literal.
+    fn add_manifest(&mut self, manifest: Option<PackageManifest>, span: oxc_span::Span) {
+        if let Some(manifest) = manifest {
+            self.manifest_packages.extend(
+                manifest
+                    .packages(&self.source_url)
+                    .into_iter()
+                    .map(|pkg| pkg.with_span(byte_span(span))),
+            );
         }
+    }
 
+    fn add_package(
+        &mut self,
+        name: &str,
+        method: ExtractionMethod,
+        confidence: Confidence,
+        span: oxc_span::Span,
+    ) {
         if let Some(normalized) = normalize_package_name(name) {
-            self.packages.insert(Package {
-                name: normalized,
-                extraction_method: method,
-                source_url: self.source_url.clone(),
-                confidence,
-            });
+            if confidence == Confidence::Low && !self.include_low_confidence {
+                explain::record(&normalized, "skip: low confidence", Some(&self.source_url));
+                return;
+            }
+            self.packages.insert(
+                Package::new(normalized, method, self.source_url.clone(), confidence)
+                    .with_span(byte_span(span)),
+            );
         }
     }
 
-    fn extract_from_string_literal(&mut self, value: &str, method: ExtractionMethod) {
-        self.add_package(value, method, Confidence::High);
+    fn extract_from_string_literal(&mut self, lit: &StringLiteral<'_>, method: ExtractionMethod) {
+        self.add_package(lit.value.as_str(), method, Confidence::High, lit.span);
     }
 }
 
 impl<'a> Visit<'a> for PackageVisitor {
     fn visit_import_declaration(&mut self, decl: &ImportDeclaration<'a>) {
-        let source = decl.source.value.as_str();
-        self.extract_from_string_literal(source, ExtractionMethod::Import);
+        self.extract_from_string_literal(&decl.source, ExtractionMethod::Import);
         walk::walk_import_declaration(self, decl);
     }
 
     fn visit_export_all_declaration(&mut self, decl: &ExportAllDeclaration<'a>) {
-        let source = decl.source.value.as_str();
-        self.extract_from_string_literal(source, ExtractionMethod::Import);
+        self.extract_from_string_literal(&decl.source, ExtractionMethod::Import);
         walk::walk_export_all_declaration(self, decl);
     }
 
     fn visit_export_named_declaration(&mut self, decl: &ExportNamedDeclaration<'a>) {
         if let Some(ref source) = decl.source {
-            self.extract_from_string_literal(source.value.as_str(), ExtractionMethod::Import);
+            self.extract_from_string_literal(source, ExtractionMethod::Import);
         }
         walk::walk_export_named_declaration(self, decl);
     }
@@ -244,7 +269,18 @@ impl<'a> Visit<'a> for PackageVisitor {
         if let Expression::Identifier(id) = &expr.callee {
             if id.name == "require" {
                 if let Some(Argument::StringLiteral(lit)) = expr.arguments.first() {
-                    self.extract_from_string_literal(lit.value.as_str(), ExtractionMethod::Require);
+                    self.extract_from_string_literal(lit, ExtractionMethod::Require);
+                }
+            }
+        }
+
+        // webpack emits imported JSON as JSON.parse('{"name":...}')
+        if expr.callee.is_specific_member_access("JSON", "parse") {
+            if let Some(Argument::StringLiteral(lit)) = expr.arguments.first() {
+                if let Ok(Value::Object(object)) = serde_json::from_str(lit.value.as_str()) {
+                    if has_manifest_keys(object.keys().map(String::as_str)) {
+                        self.add_manifest(PackageManifest::from_object(&object), lit.span);
+                    }
                 }
             }
         }
@@ -252,15 +288,85 @@ impl<'a> Visit<'a> for PackageVisitor {
         walk::walk_call_expression(self, expr);
     }
 
+    fn visit_object_expression(&mut self, expr: &ObjectExpression<'a>) {
+        // Other bundlers inline imported JSON as an object literal
+        let keys = expr.properties.iter().filter_map(|prop| match prop {
+            ObjectPropertyKind::ObjectProperty(p) if !p.computed => p.key.static_name(),
+            _ => None,
+        });
+        if has_manifest_keys(keys.collect::<Vec<_>>().iter().map(|k| k.as_ref())) {
+            let manifest = object_to_json(expr, 0).and_then(|o| PackageManifest::from_object(&o));
+            self.add_manifest(manifest, expr.span);
+        }
+        walk::walk_object_expression(self, expr);
+    }
+
     fn visit_import_expression(&mut self, expr: &ImportExpression<'a>) {
         // Dynamic import('package')
         if let Expression::StringLiteral(lit) = &expr.source {
-            self.extract_from_string_literal(lit.value.as_str(), ExtractionMethod::DynamicImport);
+            self.extract_from_string_literal(lit, ExtractionMethod::DynamicImport);
         }
         walk::walk_import_expression(self, expr);
     }
 }
 
+/// Byte range of an AST node.
+fn byte_span(span: oxc_span::Span) -> crate::types::Span {
+    (span.start as usize..span.end as usize).into()
+}
+
+/// Deepest object literal nesting converted to JSON.
+const MAX_JSON_DEPTH: usize = 4;
+
+/// Convert a static object literal to JSON. Values that aren't literals become `null`.
+fn object_to_json(expr: &ObjectExpression<'_>, depth: usize) -> Option<Map<String, Value>> {
+    if depth > MAX_JSON_DEPTH {
+        return None;
+    }
+    let mut object = Map::new();
+    for prop in &expr.properties {
+        if let ObjectPropertyKind::ObjectProperty(p) = prop {
+            if let (false, Some(key)) = (p.computed, p.key.static_name()) {
+                object.insert(key.into_owned(), literal_to_json(&p.value, depth));
+            }
+        }
+    }
+    Some(object)
+}
+
+fn literal_to_json(expr: &Expression<'_>, depth: usize) -> Value {
+    match expr {
+        Expression::StringLiteral(lit) => Value::String(lit.value.to_string()),
+        Expression::TemplateLiteral(lit) if lit.expressions.is_empty() => lit
+            .quasis
+            .first()
+            .and_then(|q| q.value.cooked.as_ref())
+            .map_or(Value::Null, |s| Value::String(s.to_string())),
+        Expression::NumericLiteral(lit) => {
+            serde_json::Number::from_f64(lit.value).map_or(Value::Null, Value::Number)
+        }
+        Expression::BooleanLiteral(lit) => Value::Bool(lit.value),
+        // Minifiers write true and false as !0 and !1
+        Expression::UnaryExpression(unary) if unary.operator == UnaryOperator::LogicalNot => {
+            match &unary.argument {
+                Expression::NumericLiteral(lit) => Value::Bool(lit.value == 0.0),
+                _ => Value::Null,
+            }
+        }
+        Expression::ObjectExpression(obj) => {
+            object_to_json(obj, depth + 1).map_or(Value::Null, Value::Object)
+        }
+        Expression::ArrayExpression(arr) => Value::Array(
+            arr.elements
+                .iter()
+                .filter_map(|el| el.as_expression())
+                .map(|el| literal_to_json(el, depth + 1))
+                .collect(),
+        ),
+        _ => Value::Null,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -328,4 +434,29 @@ mod tests {
         let packages = parser.parse(js, "test.js").unwrap();
         assert!(packages.is_empty());
     }
+
+    #[test]
+    fn test_parse_embedded_manifests() {
+        let parser = AstParser::new(false);
+        let js = r#"
+            var e = JSON.parse('{"name":"acme-checkout","version":"3.1.0","dependencies":{"@acme/payments-sdk":"2.0.1"}}');
+            const pkg = {name:"@acme/admin",version:"0.9.0",private:!0,dependencies:{"acme-feature-flags":"^1.2.0"},publishConfig:{registry:"https://npm.acme.io/"}};
+            const meta = {name:"widget",version:"1.0.0"};
+        "#;
+
+        let packages = parser.parse(js, "https://example.com/app.js").unwrap();
+        let find = |name: &str| packages.iter().find(|p| p.name == name);
+
+        let payments = find("@acme/payments-sdk").unwrap();
+        assert_eq!(payments.extraction_method, ExtractionMethod::Manifest);
+        assert_eq!(payments.version.as_deref(), Some("2.0.1"));
+        assert!(find("acme-checkout").is_some());
+
+        let admin = find("@acme/admin").unwrap();
+        assert_eq!(admin.registry.as_deref(), Some("https://npm.acme.io/"));
+        assert!(find("acme-feature-flags").is_some());
+
+        // A bare name and version is not treated as a manifest
+        assert!(find("widget").is_none());
+    }
 }
@@ -0,0 +1,222 @@
+//! User-defined bundler extraction rules.
+//!
+//! A TOML rules file teaches [`BundlerParser`](super::BundlerParser) new bundler
+//! idioms without code changes. Each `[[rule]]` matches files by a detection
+//! predicate and reads package names with capture regexes:
+//!
+//! ```toml
+//! [[rule]]
+//! name = "acme-loader"
+//! detect = ["__acme_load__"]                 # any substring; or detect_regex
+//! patterns = ['__acme_load__\(\s*"([^"]+)"'] # group 1 is the name or path
+//! transform = "path"                         # name | path | esbuild | vite
+//! method = "WebpackChunk"                    # default
+//! confidence = "high"                        # default
+//! ```
+//!
+//! Transforms turn the capture into a package name: `name` takes it as is,
+//! `path` reads a `node_modules` path (`node_modules/@acme/ui/index.js`),
+//! `esbuild` undoes esbuild identifiers (`require__acme_ui`) and `vite` undoes
+//! Vite's pre-bundled names (`@acme_ui.js`). Several files can be loaded and are
+//! merged, so shared rule packs combine with local ones.
+
+use crate::types::{Confidence, DepfusedError, ExtractionMethod, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+
+/// How a rule's capture becomes a package name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transform {
+    /// The capture is a package specifier.
+    Name,
+    /// The capture is a path, possibly through `node_modules/`.
+    Path,
+    /// The capture is an esbuild identifier like `require__acme_ui`.
+    Esbuild,
+    /// The capture is a Vite pre-bundled name like `@acme_ui.js`.
+    Vite,
+}
+
+/// One `[[rule]]` table, as written.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    name: String,
+    #[serde(default)]
+    detect: Vec<String>,
+    detect_regex: Option<String>,
+    patterns: Vec<String>,
+    #[serde(default = "default_transform")]
+    transform: Transform,
+    #[serde(default = "default_method")]
+    method: ExtractionMethod,
+    confidence: Option<String>,
+}
+
+fn default_transform() -> Transform {
+    Transform::Name
+}
+
+fn default_method() -> ExtractionMethod {
+    ExtractionMethod::WebpackChunk
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RulesFile {
+    rule: Vec<RuleFile>,
+}
+
+/// A compiled bundler rule.
+#[derive(Debug, Clone)]
+pub struct BundlerRule {
+    pub name: String,
+    detect: Vec<String>,
+    detect_regex: Option<Regex>,
+    pub patterns: Vec<Regex>,
+    pub transform: Transform,
+    pub method: ExtractionMethod,
+    pub confidence: Confidence,
+}
+
+impl BundlerRule {
+    fn compile(file: RuleFile) -> Result<Self> {
+        let invalid = |msg: String| DepfusedError::ConfigError(format!("Bundler rule '{}': {}", file.name, msg));
+        let compile = |re: &str| Regex::new(re).map_err(|e| invalid(format!("invalid regex '{}': {}", re, e)));
+
+        if file.patterns.is_empty() {
+            return Err(invalid("no patterns".to_string()));
+        }
+        let patterns = file
+            .patterns
+            .iter()
+            .map(|re| {
+                let pattern = compile(re)?;
+                if pattern.captures_len() < 2 {
+                    return Err(invalid(format!("pattern '{}' has no capture group", re)));
+                }
+                Ok(pattern)
+            })
+            .collect::<Result<_>>()?;
+        let detect_regex = file.detect_regex.as_deref().map(compile).transpose()?;
+        let confidence = match file.confidence.as_deref() {
+            Some(level) => level.parse().map_err(invalid)?,
+            None => Confidence::High,
+        };
+
+        Ok(Self {
+            name: file.name,
+            detect: file.detect,
+            detect_regex,
+            patterns,
+            transform: file.transform,
+            method: file.method,
+            confidence,
+        })
+    }
+
+    /// Whether the rule applies to a file; rules without a predicate apply to all.
+    pub fn detects(&self, content: &str) -> bool {
+        if self.detect.is_empty() && self.detect_regex.is_none() {
+            return true;
+        }
+        self.detect.iter().any(|s| content.contains(s.as_str()))
+            || self.detect_regex.as_ref().is_some_and(|re| re.is_match(content))
+    }
+}
+
+/// Bundler rules loaded from one or more rules files.
+#[derive(Debug, Clone, Default)]
+pub struct BundlerRules {
+    rules: Vec<BundlerRule>,
+}
+
+impl BundlerRules {
+    /// Load a rules file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content).map_err(|e| match e {
+            DepfusedError::ConfigError(msg) => {
+                DepfusedError::ConfigError(format!("{}: {}", path.display(), msg))
+            }
+            e => e,
+        })
+    }
+
+    /// Parse rules from TOML text.
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: RulesFile = toml::from_str(content)
+            .map_err(|e| DepfusedError::ConfigError(format!("Invalid bundler rules: {}", e)))?;
+        let rules = file
+            .rule
+            .into_iter()
+            .map(BundlerRule::compile)
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Add the rules of another file.
+    pub fn merge(&mut self, other: BundlerRules) {
+        self.rules.extend(other.rules);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &BundlerRule> {
+        self.rules.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = BundlerRules::parse(
+            r#"
+            [[rule]]
+            name = "acme-loader"
+            detect = ["__acme_load__"]
+            patterns = ['__acme_load__\(\s*"([^"]+)"']
+            transform = "path"
+            method = "Require"
+            confidence = "medium"
+
+            [[rule]]
+            name = "any"
+            patterns = ['use\("([^"]+)"\)']
+            "#,
+        )
+        .unwrap();
+        let rules: Vec<_> = rules.iter().collect();
+
+        assert_eq!(rules[0].transform, Transform::Path);
+        assert_eq!(rules[0].method, ExtractionMethod::Require);
+        assert_eq!(rules[0].confidence, Confidence::Medium);
+        assert!(rules[0].detects("__acme_load__(\"x\")"));
+        assert!(!rules[0].detects("require(\"x\")"));
+
+        assert_eq!(rules[1].transform, Transform::Name);
+        assert_eq!(rules[1].method, ExtractionMethod::WebpackChunk);
+        assert_eq!(rules[1].confidence, Confidence::High);
+        assert!(rules[1].detects(""));
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let rule = |body: &str| BundlerRules::parse(&format!("[[rule]]\nname = \"r\"\n{}", body));
+
+        assert!(rule("patterns = ['(']").is_err());
+        assert!(rule("patterns = ['no-group']").is_err());
+        assert!(rule("patterns = []").is_err());
+        assert!(rule("patterns = ['(x)']\ntransform = \"rot13\"").is_err());
+        assert!(rule("patterns = ['(x)']\nconfidence = \"sure\"").is_err());
+        assert!(rule("patterns = ['(x)']\npattern = ['typo']").is_err());
+        assert!(rule("patterns = ['(x)']\ndetect_regex = '['").is_err());
+    }
+}
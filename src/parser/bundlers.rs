@@ -1,5 +1,6 @@
 //! Bundler-specific parsers for Vite, Parcel, Turbopack, esbuild, and others.
 
+use crate::parser::bundler_rules::{BundlerRules, Transform};
 use crate::parser::{filters, normalize_package_name};
 use crate::types::{Confidence, ExtractionMethod, Package};
 use regex::Regex;
@@ -21,6 +22,8 @@ pub struct BundlerParser {
     swc_patterns: Vec<Regex>,
     /// Generic minified patterns
     minified_patterns: Vec<Regex>,
+    /// Rules loaded from `--bundler-rules` files
+    rules: BundlerRules,
 }
 
 impl BundlerParser {
@@ -33,9 +36,16 @@ impl BundlerParser {
             esbuild_patterns: Self::build_esbuild_patterns(),
             swc_patterns: Self::build_swc_patterns(),
             minified_patterns: Self::build_minified_patterns(),
+            rules: BundlerRules::default(),
         }
     }
 
+    /// Also extract with user-defined rules.
+    pub fn with_rules(mut self, rules: BundlerRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
     /// Build Vite/Rollup specific patterns.
     fn build_vite_patterns() -> Vec<Regex> {
         vec![
@@ -205,6 +215,7 @@ impl BundlerParser {
         self.extract_esbuild_packages(content, source_url, &mut packages);
         self.extract_swc_packages(content, source_url, &mut packages);
         self.extract_minified_packages(content, source_url, &mut packages);
+        self.extract_rule_packages(content, source_url, &mut packages);
 
         let result: Vec<Package> = packages
             .into_iter()
@@ -231,12 +242,15 @@ impl BundlerParser {
                     let name = self.convert_vite_name(raw_name);
                     if let Some(normalized) = normalize_package_name(&name) {
                         trace!("Vite pattern matched: {} -> {}", raw_name, normalized);
-                        packages.insert(Package {
-                            name: normalized,
-                            extraction_method: ExtractionMethod::WebpackChunk, // Reuse for bundler
-                            source_url: source_url.to_string(),
-                            confidence: Confidence::High,
-                        });
+                        packages.insert(
+                            Package::new(
+                                normalized,
+                                ExtractionMethod::WebpackChunk, // Reuse for bundler
+                                source_url.to_string(),
+                                Confidence::High,
+                            )
+                            .with_span(pkg_match.range()),
+                        );
                     }
                 }
             }
@@ -244,40 +258,39 @@ impl BundlerParser {
     }
 
     /// Convert Vite naming conventions back to package names.
+    ///
+    /// Pre-bundled deps are named by Vite's `flattenId`, which replaces `/` with `_`,
+    /// `.` with `__`, the ` > ` between a nested dep and its importers with `___`
+    /// and `#` with `____`. Names that still contain a `/` weren't flattened.
     fn convert_vite_name(&self, name: &str) -> String {
-        // Vite converts @ to empty and / to _ in some cases
-        // e.g., @company/utils -> company_utils or @company_utils
-        let mut result = name.to_string();
-
-        // Handle .js extension
-        if result.ends_with(".js") {
-            result = result[..result.len() - 3].to_string();
+        let name = name.split('?').next().unwrap_or(name);
+        let name = name.strip_suffix(".js").unwrap_or(name);
+        if name.contains('/') {
+            return name.to_string();
         }
 
-        // Handle @scope_package -> @scope/package
-        if result.starts_with('@') && !result.contains('/') {
-            if let Some(idx) = result[1..].find('_') {
-                let scope = &result[0..=idx];
-                let pkg = &result[idx + 2..];
-                result = format!("{}/{}", scope, pkg);
+        let mut id = String::with_capacity(name.len());
+        let mut chars = name.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '_' {
+                id.push(c);
+                continue;
             }
-        }
-
-        // Handle scope_package -> @scope/package (without @)
-        if !result.starts_with('@') && result.contains('_') {
-            // Check if first part looks like a scope
-            if let Some(idx) = result.find('_') {
-                let potential_scope = &result[..idx];
-                // Common scope-like names
-                if ["company", "internal", "private", "org", "team"].iter()
-                    .any(|s| potential_scope.contains(s)) {
-                    let pkg = &result[idx + 1..];
-                    result = format!("@{}/{}", potential_scope, pkg);
-                }
+            let mut run = 1;
+            while chars.next_if_eq(&'_').is_some() {
+                run += 1;
             }
+            id.push_str(match run {
+                1 => "/",
+                2 => ".",
+                3 => " > ",
+                _ => "#",
+            });
         }
 
-        result
+        // A nested dep is listed after its importers; the last one is the package itself
+        let id = id.rsplit(" > ").next().unwrap_or(&id);
+        id.split('#').next().unwrap_or(id).to_string()
     }
 
     /// Extract packages using Parcel patterns.
@@ -287,12 +300,15 @@ impl BundlerParser {
                 if let Some(pkg_match) = cap.get(1) {
                     if let Some(normalized) = self.extract_package_from_path(pkg_match.as_str()) {
                         trace!("Parcel pattern matched: {}", normalized);
-                        packages.insert(Package {
-                            name: normalized,
-                            extraction_method: ExtractionMethod::WebpackChunk,
-                            source_url: source_url.to_string(),
-                            confidence: Confidence::High,
-                        });
+                        packages.insert(
+                            Package::new(
+                                normalized,
+                                ExtractionMethod::WebpackChunk,
+                                source_url.to_string(),
+                                Confidence::High,
+                            )
+                            .with_span(pkg_match.range()),
+                        );
                     }
                 }
             }
@@ -306,12 +322,15 @@ impl BundlerParser {
                 if let Some(pkg_match) = cap.get(1) {
                     if let Some(normalized) = self.extract_package_from_path(pkg_match.as_str()) {
                         trace!("Turbopack pattern matched: {}", normalized);
-                        packages.insert(Package {
-                            name: normalized,
-                            extraction_method: ExtractionMethod::WebpackChunk,
-                            source_url: source_url.to_string(),
-                            confidence: Confidence::High,
-                        });
+                        packages.insert(
+                            Package::new(
+                                normalized,
+                                ExtractionMethod::WebpackChunk,
+                                source_url.to_string(),
+                                Confidence::High,
+                            )
+                            .with_span(pkg_match.range()),
+                        );
                     }
                 }
             }
@@ -334,12 +353,15 @@ impl BundlerParser {
 
                     if let Some(normalized) = self.extract_package_from_path(&name) {
                         trace!("esbuild pattern matched: {} -> {}", raw, normalized);
-                        packages.insert(Package {
-                            name: normalized,
-                            extraction_method: ExtractionMethod::WebpackChunk,
-                            source_url: source_url.to_string(),
-                            confidence: Confidence::High,
-                        });
+                        packages.insert(
+                            Package::new(
+                                normalized,
+                                ExtractionMethod::WebpackChunk,
+                                source_url.to_string(),
+                                Confidence::High,
+                            )
+                            .with_span(pkg_match.range()),
+                        );
                     }
                 }
             }
@@ -375,12 +397,15 @@ impl BundlerParser {
                 if let Some(pkg_match) = cap.get(1) {
                     if let Some(normalized) = normalize_package_name(pkg_match.as_str()) {
                         trace!("SWC pattern matched: {}", normalized);
-                        packages.insert(Package {
-                            name: normalized,
-                            extraction_method: ExtractionMethod::WebpackChunk,
-                            source_url: source_url.to_string(),
-                            confidence: Confidence::High,
-                        });
+                        packages.insert(
+                            Package::new(
+                                normalized,
+                                ExtractionMethod::WebpackChunk,
+                                source_url.to_string(),
+                                Confidence::High,
+                            )
+                            .with_span(pkg_match.range()),
+                        );
                     }
                 }
             }
@@ -394,12 +419,57 @@ impl BundlerParser {
                 if let Some(pkg_match) = cap.get(1) {
                     if let Some(normalized) = normalize_package_name(pkg_match.as_str()) {
                         trace!("Minified pattern matched: {}", normalized);
-                        packages.insert(Package {
-                            name: normalized,
-                            extraction_method: ExtractionMethod::Require,
-                            source_url: source_url.to_string(),
-                            confidence: Confidence::Medium, // Lower confidence for minified
-                        });
+                        packages.insert(
+                            Package::new(
+                                normalized,
+                                ExtractionMethod::Require,
+                                source_url.to_string(),
+                                Confidence::Medium, // Lower confidence for minified,
+                            )
+                            .with_span(pkg_match.range()),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Extract packages using user-defined rules.
+    fn extract_rule_packages(&self, content: &str, source_url: &str, packages: &mut HashSet<Package>) {
+        for rule in self.rules.iter().filter(|rule| rule.detects(content)) {
+            trace!("Bundler rule {} applies to {}", rule.name, source_url);
+            for pattern in &rule.patterns {
+                for cap in pattern.captures_iter(content) {
+                    if let Some(pkg_match) = cap.get(1) {
+                        let raw = pkg_match.as_str();
+                        let name = match rule.transform {
+                            Transform::Name => normalize_package_name(raw),
+                            Transform::Path => {
+                                // Everything up to the innermost node_modules is the project's
+                                let path = raw.rsplit_once("node_modules/").map_or(raw, |(_, p)| p);
+                                self.extract_package_from_path(path)
+                            }
+                            Transform::Esbuild => {
+                                let raw = raw
+                                    .strip_prefix("require_")
+                                    .or_else(|| raw.strip_prefix("init_"))
+                                    .unwrap_or(raw);
+                                self.extract_package_from_path(&self.convert_esbuild_name(raw))
+                            }
+                            Transform::Vite => normalize_package_name(&self.convert_vite_name(raw)),
+                        };
+                        if let Some(normalized) = name {
+                            trace!("Bundler rule {} matched: {} -> {}", rule.name, raw, normalized);
+                            packages.insert(
+                                Package::new(
+                                    normalized,
+                                    rule.method.clone(),
+                                    source_url.to_string(),
+                                    rule.confidence,
+                                )
+                                .with_span(pkg_match.range()),
+                            );
+                        }
                     }
                 }
             }
@@ -534,11 +604,75 @@ mod tests {
         assert!(names.contains(&"express"));
     }
 
+    #[test]
+    fn test_extract_rule_packages() {
+        let rules = BundlerRules::parse(
+            r#"
+            [[rule]]
+            name = "acme-loader"
+            detect = ["__acme_load__"]
+            patterns = ['__acme_load__\(\s*"([^"]+)"']
+            transform = "path"
+
+            [[rule]]
+            name = "acme-esbuild"
+            patterns = ['__acme_init\((require_\w+)\)']
+            transform = "esbuild"
+            method = "Require"
+            confidence = "medium"
+            "#,
+        )
+        .unwrap();
+        let parser = BundlerParser::new().with_rules(rules);
+        let content = r#"
+            __acme_load__("/assets/node_modules/@company/widgets/dist/index.js");
+            __acme_init(require__company_auth_client);
+        "#;
+        let packages = parser.extract_packages(content, "test.js");
+
+        let widgets = packages.iter().find(|p| p.name == "@company/widgets").unwrap();
+        assert_eq!(widgets.extraction_method, ExtractionMethod::WebpackChunk);
+        assert_eq!(widgets.confidence, Confidence::High);
+        let auth = packages.iter().find(|p| p.name == "@company/auth-client").unwrap();
+        assert_eq!(auth.extraction_method, ExtractionMethod::Require);
+        assert_eq!(auth.confidence, Confidence::Medium);
+    }
+
     #[test]
     fn test_convert_vite_name() {
         let parser = BundlerParser::new();
 
         assert_eq!(parser.convert_vite_name("@company_utils.js"), "@company/utils");
         assert_eq!(parser.convert_vite_name("lodash.js"), "lodash");
+        assert_eq!(parser.convert_vite_name("lodash__debounce.js"), "lodash.debounce");
+        assert_eq!(parser.convert_vite_name("react-dom_client.js?v=3f2a"), "react-dom/client");
+        assert_eq!(parser.convert_vite_name("@acme_ui_button.js"), "@acme/ui/button");
+        assert_eq!(parser.convert_vite_name("@acme_app___@acme_ui__core.js"), "@acme/ui.core");
+        assert_eq!(parser.convert_vite_name("@acme/ui_kit"), "@acme/ui_kit");
+    }
+
+    #[test]
+    fn test_extract_vite_rule_packages() {
+        let rules = BundlerRules::parse(
+            r#"
+            [[rule]]
+            name = "acme-deps"
+            patterns = ['__acme_dep\("([^"]+)"\)']
+            transform = "vite"
+            "#,
+        )
+        .unwrap();
+        let parser = BundlerParser::new().with_rules(rules);
+        let content = r#"
+            __acme_dep("lodash__debounce.js");
+            __acme_dep("acme-sdk__client.js");
+            __acme_dep("@acme_ui_button.js");
+            __acme_dep("acme-payments_checkout.js?v=91ac");
+        "#;
+        let packages = parser.extract_packages(content, "test.js");
+        let mut names: Vec<_> = packages.iter().map(|p| p.name.as_str()).collect();
+        names.sort();
+
+        assert_eq!(names, ["@acme/ui", "acme-payments", "acme-sdk.client", "lodash.debounce"]);
     }
 }
@@ -6,14 +6,46 @@
 //! - Unicode escapes (\u006c\u006f\u0064\u0061\u0073\u0068)
 //! - String.fromCharCode(108,111,100,97,115,104)
 //! - Array.join patterns: ["l","o","d","a","s","h"].join("")
+//! - javascript-obfuscator string arrays: require(_0x5a1b(0x1f))
 
-use crate::parser::{filters, normalize_package_name};
+use crate::parser::{filters, normalize_package_name, string_array, AstParser};
 use crate::types::{Confidence, ExtractionMethod, Package};
 use base64::{engine::general_purpose::STANDARD, Engine};
 use regex::Regex;
 use std::collections::HashSet;
+use std::sync::OnceLock;
 use tracing::{debug, trace};
 
+/// Patterns of [`Deobfuscator::is_likely_obfuscated`].
+struct ObfuscationPatterns {
+    /// javascript-obfuscator string array calls: `_0x5a1b(0x1f)`
+    string_array_call: Regex,
+    indicators: Vec<Regex>,
+    short_var: Regex,
+}
+
+static OBFUSCATION_PATTERNS: OnceLock<ObfuscationPatterns> = OnceLock::new();
+
+fn obfuscation_patterns() -> &'static ObfuscationPatterns {
+    OBFUSCATION_PATTERNS.get_or_init(|| ObfuscationPatterns {
+        string_array_call: Regex::new(r"_0x[0-9a-f]{4,6}\(\s*'?0x[0-9a-f]+").unwrap(),
+        indicators: [
+            r#"\\x[0-9a-fA-F]{2}"#,
+            r#"\\u[0-9a-fA-F]{4}"#,
+            r#"String\.fromCharCode"#,
+            r#"\["fromCharCode"\]"#,
+            r#"atob\s*\("#,
+            r#"\.split\s*\(\s*["']["']\s*\)\.reverse"#,
+            r#"eval\s*\("#,
+            r#"Function\s*\("#,
+        ]
+        .iter()
+        .map(|pattern| Regex::new(pattern).unwrap())
+        .collect(),
+        short_var: Regex::new(r"\b[a-z]\s*=").unwrap(),
+    })
+}
+
 /// Deobfuscator for extracting package names from obfuscated strings.
 #[derive(Clone)]
 pub struct Deobfuscator {
@@ -22,6 +54,8 @@ pub struct Deobfuscator {
     unicode_patterns: Vec<Regex>,
     from_char_code_patterns: Vec<Regex>,
     array_join_patterns: Vec<Regex>,
+    /// Whether specifiers decoded from string arrays keep low-confidence matches.
+    include_low: bool,
 }
 
 impl Deobfuscator {
@@ -51,9 +85,16 @@ impl Deobfuscator {
                 Regex::new(r#"\[\s*((?:["'][^"']*["']\s*,?\s*)+)\]\s*\.join\s*\(\s*["']['"]?\s*\)"#).unwrap(),
                 Regex::new(r#"require\s*\(\s*\[\s*((?:["'][^"']*["']\s*,?\s*)+)\]\s*\.join"#).unwrap(),
             ],
+            include_low: false,
         }
     }
 
+    /// Keep low-confidence matches in code decoded from string arrays.
+    pub fn with_include_low(mut self, include_low: bool) -> Self {
+        self.include_low = include_low;
+        self
+    }
+
     /// Extract packages from potentially obfuscated content.
     pub fn extract_packages(&self, content: &str, source_url: &str) -> Vec<Package> {
         let mut packages = HashSet::new();
@@ -68,6 +109,9 @@ impl Deobfuscator {
         // String concatenation patterns
         self.extract_concat_packages(content, source_url, &mut packages);
 
+        // Specifiers hidden behind an obfuscator string array
+        self.extract_string_array_packages(content, source_url, &mut packages);
+
         let result: Vec<Package> = packages.into_iter().collect();
         if !result.is_empty() {
             debug!("Extracted {} packages from deobfuscation: {}", result.len(), source_url);
@@ -96,12 +140,15 @@ impl Deobfuscator {
                             }
 
                             trace!("Deobfuscated: {} -> {}", encoded.as_str(), normalized);
-                            packages.insert(Package {
-                                name: normalized,
-                                extraction_method: ExtractionMethod::Deobfuscate,
-                                source_url: source_url.to_string(),
-                                confidence: Confidence::Low,
-                            });
+                            packages.insert(
+                                Package::new(
+                                    normalized,
+                                    ExtractionMethod::Deobfuscate,
+                                    source_url.to_string(),
+                                    Confidence::Low,
+                                )
+                                .with_span(encoded.range()),
+                            );
                         }
                     }
                 }
@@ -172,38 +219,51 @@ impl Deobfuscator {
                 let full_name = format!("@{}/{}", scope.as_str(), name.as_str());
                 if let Some(normalized) = normalize_package_name(&full_name) {
                     trace!("Concat decoded: {}", normalized);
-                    packages.insert(Package {
-                        name: normalized,
-                        extraction_method: ExtractionMethod::Deobfuscate,
-                        source_url: source_url.to_string(),
-                        confidence: Confidence::Low,
-                    });
+                    packages.insert(
+                        Package::new(
+                            normalized,
+                            ExtractionMethod::Deobfuscate,
+                            source_url.to_string(),
+                            Confidence::Low,
+                        )
+                        .with_span(cap.get(0).unwrap().range()),
+                    );
                 }
             }
         }
     }
 
+    /// Extract packages from require/import specifiers that a string array decoder returns.
+    fn extract_string_array_packages(&self, content: &str, source_url: &str, packages: &mut HashSet<Package>) {
+        let Some(decoded) = string_array::decode(content) else {
+            return;
+        };
+        debug!("Decoded {} string array calls in {}", decoded.decoded_calls(), source_url);
+
+        let found = AstParser::new(self.include_low).parse(&decoded.text, source_url).unwrap_or_default();
+        for mut pkg in found {
+            // Only names that were hidden; the rest is found by the AST pass
+            let Some(span) = pkg.span.and_then(|s| decoded.original_span(s.start..s.end)) else {
+                continue;
+            };
+            trace!("String array decoded: {}", pkg.name);
+            pkg.extraction_method = ExtractionMethod::Deobfuscate;
+            pkg.confidence = Confidence::Medium;
+            packages.insert(pkg.with_span(span));
+        }
+    }
+
     /// Check if content appears to be obfuscated.
     pub fn is_likely_obfuscated(&self, content: &str) -> bool {
-        let indicators = [
-            r#"\\x[0-9a-fA-F]{2}"#,
-            r#"\\u[0-9a-fA-F]{4}"#,
-            r#"String\.fromCharCode"#,
-            r#"\["fromCharCode"\]"#,
-            r#"atob\s*\("#,
-            r#"\.split\s*\(\s*["']["']\s*\)\.reverse"#,
-            r#"eval\s*\("#,
-            r#"Function\s*\("#,
-        ];
+        let patterns = obfuscation_patterns();
+        if patterns.string_array_call.find_iter(content).take(3).count() >= 3 {
+            return true;
+        }
 
-        let score: usize = indicators.iter()
-            .filter(|&pattern| Regex::new(pattern).map(|re| re.is_match(content)).unwrap_or(false))
-            .count();
+        let score = patterns.indicators.iter().filter(|re| re.is_match(content)).count();
 
         // Also check for excessive single-letter variables (minified code)
-        let short_var_count = Regex::new(r"\b[a-z]\s*=")
-            .map(|re| re.find_iter(content).count())
-            .unwrap_or(0);
+        let short_var_count = patterns.short_var.find_iter(content).count();
 
         score >= 2 || (score >= 1 && short_var_count > 50)
     }
@@ -267,6 +327,20 @@ mod tests {
         assert!(packages.iter().any(|p| p.name == "lodash"));
     }
 
+    #[test]
+    fn test_extract_string_array_packages() {
+        let deob = Deobfuscator::new();
+        let content = "var _0x1234=['log','@acme/auth-client','lodash'];(function(_0x2d8f05,_0x4b81bb){var _0x4d74cb=function(_0x32719f){while(--_0x32719f){_0x2d8f05['push'](_0x2d8f05['shift']());}};_0x4d74cb(++_0x4b81bb);}(_0x1234,0x1));var _0x5678=function(_0x2d8f05,_0x4b81bb){_0x2d8f05=_0x2d8f05-0x0;var _0x4d74cb=_0x1234[_0x2d8f05];return _0x4d74cb;};var a=require(_0x5678('0x0')),b=require(_0x5678('0x1'));console[_0x5678('0x2')](a,b);";
+        assert!(deob.is_likely_obfuscated(content));
+
+        let packages = deob.extract_packages(content, "test.js");
+        let auth = packages.iter().find(|p| p.name == "@acme/auth-client").unwrap();
+        assert_eq!(auth.extraction_method, ExtractionMethod::Deobfuscate);
+        let span = auth.span.unwrap();
+        assert_eq!(&content[span.start..span.end], "_0x5678('0x0')");
+        assert!(packages.iter().any(|p| p.name == "lodash"));
+    }
+
     #[test]
     fn test_is_likely_obfuscated() {
         let deob = Deobfuscator::new();
@@ -0,0 +1,89 @@
+//! Filter explain mode.
+//!
+//! With `--explain-filters`, every candidate dropped by a filter is recorded
+//! with the rule that dropped it. Extraction runs in per-file tasks that know
+//! nothing about the target, so a target's log is made current for its tasks
+//! with [`scope`], and filters record into whichever log is current. Outside a
+//! scope, recording does nothing.
+
+use crate::types::FilteredPackage;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+tokio::task_local! {
+    static CURRENT: Arc<FilterLog>;
+}
+
+/// Candidates dropped while scanning one target.
+#[derive(Debug, Default)]
+pub struct FilterLog {
+    entries: Mutex<Vec<FilteredPackage>>,
+}
+
+impl FilterLog {
+    pub fn push(&self, name: &str, rule: &str, source_url: Option<&str>) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.push(FilteredPackage {
+                name: name.to_string(),
+                rule: rule.to_string(),
+                source_url: source_url.map(str::to_string),
+            });
+        }
+    }
+
+    /// Recorded candidates sorted by name, each drop listed once.
+    pub fn take(&self) -> Vec<FilteredPackage> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map(|mut entries| std::mem::take(&mut *entries))
+            .unwrap_or_default();
+        entries.sort();
+        entries.dedup();
+        entries
+    }
+}
+
+/// Run `f` with `log` as the current log, or as is without one.
+pub async fn scope<F: Future>(log: Option<Arc<FilterLog>>, f: F) -> F::Output {
+    match log {
+        Some(log) => CURRENT.scope(log, f).await,
+        None => f.await,
+    }
+}
+
+/// [`scope`] for synchronous code.
+pub fn sync_scope<R>(log: Option<Arc<FilterLog>>, f: impl FnOnce() -> R) -> R {
+    match log {
+        Some(log) => CURRENT.sync_scope(log, f),
+        None => f(),
+    }
+}
+
+/// Record a dropped candidate in the current log, if there is one.
+pub fn record(name: &str, rule: &str, source_url: Option<&str>) {
+    let _ = CURRENT.try_with(|log| log.push(name, rule, source_url));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_in_scope() {
+        record("outside", "filter: css class", None);
+
+        let log = Arc::new(FilterLog::default());
+        sync_scope(Some(log.clone()), || {
+            record("card-back", "filter: css class", Some("https://example.com/a.js"));
+            record("card-back", "filter: css class", Some("https://example.com/a.js"));
+            record("abc", "false positive: short identifier", None);
+        });
+
+        let entries = log.take();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "abc");
+        assert_eq!(entries[1].source_url.as_deref(), Some("https://example.com/a.js"));
+        assert!(log.take().is_empty());
+    }
+}
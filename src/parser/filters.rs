@@ -130,8 +130,8 @@ pub fn is_bundler_artifact(package_name: &str) -> bool {
 /// - icjsn, ipjsn → Incapsula/Imperva anti-bot (15+ findings)
 pub fn is_obfuscation_artifact(package_name: &str) -> bool {
     // Hex number identifiers (0x...)
-    if package_name.starts_with("0x") {
-        if package_name[2..].chars().all(|c| c.is_ascii_hexdigit()) {
+    if let Some(hex) = package_name.strip_prefix("0x") {
+        if hex.chars().all(|c| c.is_ascii_hexdigit()) {
             debug!("Filter: Hex identifier: {}", package_name);
             return true;
         }
@@ -363,9 +363,28 @@ pub fn is_odoo_module(package_name: &str, source_context: Option<&str>, source_u
     false
 }
 
+/// Why a name that passed every filter still looks like it could be an artifact.
+///
+/// Used to lower the confidence score rather than to filter.
+pub fn near_miss(package_name: &str) -> Option<&'static str> {
+    let bare = package_name.rsplit('/').next().unwrap_or(package_name);
+    if bare.len() <= 3 {
+        return Some("very short name");
+    }
+    let digits = bare.chars().filter(char::is_ascii_digit).count();
+    if digits * 3 >= bare.len() {
+        return Some("mostly digits");
+    }
+    if is_likely_css_class(bare) {
+        return Some("looks like a CSS class");
+    }
+    None
+}
+
 /// Master filter function - returns true if package should be FILTERED OUT (skipped).
 ///
-/// This applies all filter rules in sequence. Based on investigation of 13,840 findings,
+/// This applies the installed user rules (see [`super::rules`]), then all
+/// built-in filter rules in sequence. Based on investigation of 13,840 findings,
 /// this should filter out 90%+ of false positives while preserving real vulnerabilities.
 ///
 /// CRITICAL: This must NOT filter out the 2 confirmed real vulnerabilities:
@@ -376,62 +395,94 @@ pub fn should_filter_package(
     source_context: Option<&str>,
     source_url: Option<&str>,
 ) -> bool {
+    match filter_reason(package_name, source_context, source_url) {
+        Some(reason) => {
+            super::explain::record(package_name, &reason, source_url);
+            true
+        }
+        None => false,
+    }
+}
+
+/// The rule [`should_filter_package`] drops a package by, if any.
+pub fn filter_reason(
+    package_name: &str,
+    source_context: Option<&str>,
+    source_url: Option<&str>,
+) -> Option<String> {
+    // User rules come first: allow keeps a name the filters below would drop
+    if let Some(matched) = super::rules::check_current(package_name, source_url) {
+        debug!("Filter rule '{}': {}", matched, package_name);
+        return (matched.action == super::rules::RuleAction::Deny).then(|| format!("rule: {}", matched));
+    }
+
     // Filter 1: Parser artifacts (255 false positives)
     if package_name == "node_modules" ||
        package_name.starts_with("node_modules_") ||
        package_name.starts_with("node_modules/") {
         debug!("Filter: Parser artifact 'node_modules': {}", package_name);
-        return true;
+        return layer("parser artifact");
     }
 
     // Filter 2: CSS class names (160+ false positives)
     if is_likely_css_class(package_name) {
-        return true;
+        return layer("css class");
     }
 
     // Filter 3: Regex patterns (4 false positives)
     if is_regex_pattern(package_name) {
-        return true;
+        return layer("regex pattern");
     }
 
     // Filter 4: Bundler artifacts (10 false positives)
     if is_bundler_artifact(package_name) {
-        return true;
+        return layer("bundler artifact");
     }
 
     // Filter 5: Obfuscation (75+ false positives)
     if is_obfuscation_artifact(package_name) {
-        return true;
+        return layer("obfuscation artifact");
     }
 
     // Filter 6: URL paths (2 false positives)
     if is_url_path_component(package_name, source_context) {
-        return true;
+        return layer("url path");
     }
 
     // Filter 7: Service integrations (10+ false positives)
     if is_service_integration(package_name, source_url) {
-        return true;
+        return layer("service integration");
     }
 
     // Filter 8: i18n keys (6 false positives)
     if is_i18n_key(package_name, source_context) {
-        return true;
+        return layer("i18n key");
     }
 
     // Filter 9: Odoo framework modules (NEW - from cyshield.com findings)
     if is_odoo_module(package_name, source_context, source_url) {
-        return true;
+        return layer("odoo module");
     }
 
     // Package passed all filters - should be investigated
-    false
+    None
+}
+
+fn layer(name: &str) -> Option<String> {
+    Some(format!("filter: {}", name))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_filter_reason() {
+        assert_eq!(filter_reason("card--flipped", None, None).as_deref(), Some("filter: css class"));
+        assert_eq!(filter_reason("node_modules", None, None).as_deref(), Some("filter: parser artifact"));
+        assert_eq!(filter_reason("@getbento/website-components", None, None), None);
+    }
+
     #[test]
     fn test_css_class_filters() {
         // BEM patterns
@@ -0,0 +1,265 @@
+//! Embedded `package.json` manifests.
+//!
+//! Bundles often import their own `package.json` (usually for the version), and
+//! the whole manifest ends up in the bundle or in the source map's
+//! `sourcesContent`. Its `name` and declared dependencies are reliable package
+//! names, and a `publishConfig` registry that is not the public one shows the
+//! package is published privately.
+
+use crate::parser::{filters, normalize_package_name};
+use crate::types::{Confidence, ExtractionMethod, Package};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use tracing::debug;
+
+/// Dependency sections read from a manifest.
+const DEPENDENCY_FIELDS: &[&str] = &[
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// Keys that, next to a string `name`, mark an object as a package manifest.
+const MANIFEST_FIELDS: &[&str] = &[
+    "version",
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+    "publishConfig",
+];
+
+/// Public registries that say nothing about a package being internal.
+const PUBLIC_REGISTRIES: &[&str] = &["registry.npmjs.org", "registry.yarnpkg.com", "registry.npmjs.com"];
+
+/// The parts of a `package.json` relevant to dependency confusion.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageManifest {
+    pub name: String,
+    pub version: Option<String>,
+    /// Declared dependencies of every kind, name to version range.
+    pub dependencies: BTreeMap<String, String>,
+    /// `publishConfig.registry`, when it is not a public registry.
+    pub registry: Option<String>,
+    /// `publishConfig["@scope:registry"]` entries that are not public registries.
+    pub scope_registries: BTreeMap<String, String>,
+}
+
+/// Whether an object with these keys may be a manifest worth reading from
+/// bundled code: a `name` plus dependencies or a `publishConfig`. A bare name
+/// and version is too common in bundles to trust.
+pub fn has_manifest_keys<'k>(keys: impl IntoIterator<Item = &'k str>) -> bool {
+    let (mut name, mut declares) = (false, false);
+    for key in keys {
+        name |= key == "name";
+        declares |= key == "publishConfig" || DEPENDENCY_FIELDS.contains(&key);
+    }
+    name && declares
+}
+
+impl PackageManifest {
+    /// Parse a manifest from JSON text, or `None` if it is not one.
+    pub fn parse(json: &str) -> Option<Self> {
+        let trimmed = json.trim_start();
+        if !trimmed.starts_with('{') {
+            return None;
+        }
+        match serde_json::from_str::<Value>(trimmed).ok()? {
+            Value::Object(object) => Self::from_object(&object),
+            _ => None,
+        }
+    }
+
+    /// Read a manifest from a JSON object with a string `name` and at least one
+    /// other manifest field.
+    pub fn from_object(object: &Map<String, Value>) -> Option<Self> {
+        let name = object.get("name")?.as_str()?.trim();
+        if !MANIFEST_FIELDS.iter().any(|field| object.contains_key(*field)) {
+            return None;
+        }
+
+        let mut dependencies = BTreeMap::new();
+        for field in DEPENDENCY_FIELDS {
+            if let Some(Value::Object(deps)) = object.get(*field) {
+                for (dep, range) in deps {
+                    dependencies.insert(dep.clone(), range.as_str().unwrap_or_default().to_string());
+                }
+            }
+        }
+
+        let mut registry = None;
+        let mut scope_registries = BTreeMap::new();
+        if let Some(Value::Object(publish)) = object.get("publishConfig") {
+            for (key, value) in publish {
+                let Some(url) = value.as_str().filter(|url| is_private_registry(url)) else {
+                    continue;
+                };
+                if key == "registry" {
+                    registry = Some(url.to_string());
+                } else if let Some(scope) = key.strip_suffix(":registry").filter(|s| s.starts_with('@')) {
+                    scope_registries.insert(scope.to_string(), url.to_string());
+                }
+            }
+        }
+
+        Some(Self {
+            name: name.to_string(),
+            version: object.get("version").and_then(Value::as_str).map(str::to_string),
+            dependencies,
+            registry,
+            scope_registries,
+        })
+    }
+
+    /// Private registry the named package is published to, if the manifest leaks one.
+    fn registry_for(&self, package: &str) -> Option<&String> {
+        let scoped = package
+            .split_once('/')
+            .and_then(|(scope, _)| self.scope_registries.get(scope));
+        if package == self.name {
+            scoped.or(self.registry.as_ref())
+        } else {
+            scoped
+        }
+    }
+
+    /// The manifest's own package and its dependencies, as high-confidence packages.
+    ///
+    /// Dependencies pinned to an exact version carry it; ranges do not.
+    pub fn packages(&self, source_url: &str) -> Vec<Package> {
+        let own = (self.name.as_str(), self.version.as_deref().and_then(exact_version));
+        let deps = self
+            .dependencies
+            .iter()
+            .filter(|(_, range)| !is_non_registry_range(range))
+            .map(|(name, range)| (name.as_str(), exact_version(range)));
+
+        let packages: Vec<Package> = std::iter::once(own)
+            .chain(deps)
+            .filter_map(|(raw, version)| {
+                let name = normalize_package_name(raw)?;
+                // Only the name itself, not a deep import path
+                if name != raw || filters::should_filter_package(&name, None, Some(source_url)) {
+                    return None;
+                }
+                let mut pkg = Package::new(
+                    name,
+                    ExtractionMethod::Manifest,
+                    source_url.to_string(),
+                    Confidence::High,
+                )
+                .with_version(version);
+                pkg.registry = self.registry_for(&pkg.name).cloned();
+                Some(pkg)
+            })
+            .collect();
+
+        debug!(
+            "Manifest {} declares {} packages: {}",
+            self.name,
+            packages.len(),
+            source_url
+        );
+        packages
+    }
+}
+
+/// Whether a registry URL points somewhere other than the public npm registries.
+fn is_private_registry(url: &str) -> bool {
+    let host = url::Url::parse(url.trim())
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_ascii_lowercase));
+    match host {
+        Some(host) => !PUBLIC_REGISTRIES.contains(&host.as_str()),
+        None => false,
+    }
+}
+
+/// Ranges resolved outside the registry (`workspace:`, `file:`, git, URLs) can't
+/// be confused with a public package.
+fn is_non_registry_range(range: &str) -> bool {
+    const PREFIXES: &[&str] = &[
+        "workspace:", "file:", "link:", "portal:", "git", "github:", "http:", "https:",
+    ];
+    let range = range.trim();
+    PREFIXES.iter().any(|prefix| range.starts_with(prefix))
+}
+
+/// A range that pins one exact version (`1.2.3` or `=1.2.3`).
+fn exact_version(range: &str) -> Option<String> {
+    let version = range.trim().trim_start_matches('=').trim_start_matches('v');
+    semver::Version::parse(version).ok().map(|_| version.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"{
+        "name": "@acme/storefront",
+        "version": "2.4.0",
+        "private": true,
+        "dependencies": {
+            "@acme/design-tokens": "1.8.2",
+            "react": "^18.2.0",
+            "@acme/ui": "workspace:*"
+        },
+        "devDependencies": { "acme-build-tools": "~0.3.1" },
+        "publishConfig": {
+            "registry": "https://npm.acme-corp.internal/",
+            "@acme:registry": "https://npm.acme-corp.internal/"
+        }
+    }"#;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = PackageManifest::parse(MANIFEST).unwrap();
+        assert_eq!(manifest.name, "@acme/storefront");
+        assert_eq!(manifest.dependencies.len(), 4);
+        assert_eq!(manifest.registry.as_deref(), Some("https://npm.acme-corp.internal/"));
+        assert_eq!(manifest.scope_registries.get("@acme").map(String::as_str), Some("https://npm.acme-corp.internal/"));
+
+        // Objects that merely have a name are not manifests
+        assert!(PackageManifest::parse(r#"{"name": "Submit"}"#).is_none());
+        assert!(PackageManifest::parse(r#"["name"]"#).is_none());
+
+        let public = PackageManifest::parse(
+            r#"{"name": "x", "publishConfig": {"registry": "https://registry.npmjs.org/"}}"#,
+        )
+        .unwrap();
+        assert!(public.registry.is_none());
+    }
+
+    #[test]
+    fn test_has_manifest_keys() {
+        assert!(has_manifest_keys(["name", "version", "dependencies"]));
+        assert!(has_manifest_keys(["publishConfig", "name"]));
+        assert!(!has_manifest_keys(["name", "version"]));
+        assert!(!has_manifest_keys(["dependencies"]));
+    }
+
+    #[test]
+    fn test_manifest_packages() {
+        let manifest = PackageManifest::parse(MANIFEST).unwrap();
+        let packages = manifest.packages("https://example.com/app.js.map");
+        let find = |name: &str| packages.iter().find(|p| p.name == name);
+
+        assert!(find("@acme/ui").is_none(), "workspace ranges are skipped");
+
+        let own = find("@acme/storefront").unwrap();
+        assert_eq!(own.extraction_method, ExtractionMethod::Manifest);
+        assert_eq!(own.confidence, Confidence::High);
+        assert_eq!(own.version.as_deref(), Some("2.4.0"));
+        assert_eq!(own.registry.as_deref(), Some("https://npm.acme-corp.internal/"));
+
+        let tokens = find("@acme/design-tokens").unwrap();
+        assert_eq!(tokens.version.as_deref(), Some("1.8.2"));
+        assert_eq!(tokens.registry.as_deref(), Some("https://npm.acme-corp.internal/"));
+
+        let react = find("react").unwrap();
+        assert!(react.version.is_none());
+        assert!(react.registry.is_none());
+        assert!(find("acme-build-tools").is_some());
+    }
+}
@@ -4,24 +4,37 @@
 //! - JavaScript AST (require/import statements)
 //! - Source maps (sources array paths)
 //! - Webpack chunk manifests
-//! - Vite, Parcel, Turbopack, esbuild bundler patterns
-//! - Obfuscated/encoded strings (base64, hex, unicode, fromCharCode)
+//! - Vite, Parcel, Turbopack, esbuild bundler patterns, plus user rules files
+//! - Obfuscated/encoded strings (base64, hex, unicode, fromCharCode, obfuscator string arrays)
+//! - Embedded package.json manifests
+//!
+//! False positives are dropped by built-in filters and user rules files.
 
 pub mod ast_parser;
+pub mod bundler_rules;
 pub mod bundlers;
 pub mod deobfuscate;
+pub mod explain;
 pub mod filters;
+pub mod manifest;
+pub mod rules;
 pub mod sourcemap;
+pub mod string_array;
 pub mod webpack;
 
 pub use ast_parser::AstParser;
+pub use bundler_rules::BundlerRules;
 pub use bundlers::BundlerParser;
 pub use deobfuscate::Deobfuscator;
 pub use filters::should_filter_package;
-pub use sourcemap::SourceMapParser;
+pub use manifest::PackageManifest;
+pub use rules::FilterRules;
+pub use sourcemap::{attach_evidence, LineIndex, ParsedSourceMap, SourceMapLookup, SourceMapParser};
 pub use webpack::WebpackParser;
 
 /// Normalize a package name (handle scoped packages, strip versions, etc.).
+///
+/// Rejected names are recorded in the current [`explain`] log.
 pub fn normalize_package_name(name: &str) -> Option<String> {
     let trimmed = name.trim();
 
@@ -30,14 +43,21 @@ pub fn normalize_package_name(name: &str) -> Option<String> {
         return None;
     }
 
+    normalize_trimmed(trimmed)
+        .map_err(|reason| explain::record(trimmed, reason, None))
+        .ok()
+}
+
+/// The normalized form of a non-empty name, or why it can't be a package.
+fn normalize_trimmed(trimmed: &str) -> std::result::Result<String, &'static str> {
     // Skip relative imports
     if trimmed.starts_with('.') || trimmed.starts_with('/') {
-        return None;
+        return Err("normalize: relative path");
     }
 
     // Skip node built-ins
     if is_node_builtin(trimmed) {
-        return None;
+        return Err("normalize: node built-in");
     }
 
     // Handle scoped packages (@scope/package)
@@ -51,27 +71,27 @@ pub fn normalize_package_name(name: &str) -> Option<String> {
 
             // Validate scope name
             if !is_valid_scope(scope) {
-                return None;
+                return Err("normalize: invalid scope");
             }
 
             // Validate package name
             if !is_valid_package_name(package) {
-                return None;
+                return Err("normalize: invalid package name");
             }
 
-            return Some(format!("{}/{}", scope, package));
+            return Ok(format!("{}/{}", scope, package));
         }
-        return None;
+        return Err("normalize: scope without package");
     }
 
     // Regular package - extract just the package name (first segment)
-    let package_name = trimmed.split('/').next()?;
+    let package_name = trimmed.split('/').next().unwrap_or(trimmed);
 
     if !is_valid_package_name(package_name) {
-        return None;
+        return Err("normalize: invalid package name");
     }
 
-    Some(package_name.to_string())
+    Ok(package_name.to_string())
 }
 
 /// Check if a name is a Node.js built-in module.
@@ -159,9 +179,13 @@ fn is_valid_scope(scope: &str) -> bool {
         .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
 }
 
-/// Check if a package name looks like an internal/private package.
 /// Check if a package name is likely a false positive (not a real package).
 pub fn is_likely_false_positive(name: &str) -> bool {
+    false_positive_reason(name).is_some()
+}
+
+/// Why a name is likely a false positive, if it is.
+pub fn false_positive_reason(name: &str) -> Option<&'static str> {
     // Design system component patterns (responsive breakpoint sizes)
     // Pattern: @scope/component-{xs|sm|md|lg|xl}
     // Example: @allocation-list/asset-list-xs, @asset-list/holding-list-md
@@ -171,7 +195,7 @@ pub fn is_likely_false_positive(name: &str) -> bool {
         || name.ends_with("-md") || name.ends_with("-lg") || name.ends_with("-xl")) {
         // Strong indicator: scope name ends with -list and package name also contains -list
         if name.contains("-list/") && name.contains("-list-") {
-            return true;
+            return Some("breakpoint component");
         }
     }
 
@@ -181,14 +205,14 @@ pub fn is_likely_false_positive(name: &str) -> bool {
             // FP: Version number as package name (from CDN URLs like @scope/1.2.3/dist/...)
             // Pattern: @scope/X.Y.Z or @scope/X.Y where X,Y,Z are digits
             if pkg_part.contains('.') && pkg_part.chars().all(|c| c.is_ascii_digit() || c == '.') {
-                return true;
+                return Some("version number");
             }
 
             // FP: Blog post / article URL slugs (Medium, etc.)
             // Pattern: @celoorg/2025-year-in-review-while-crypto-talked-celo-delivered-1f2472952abf
             // Real npm package names are almost never longer than 50 chars
             if pkg_part.len() > 50 {
-                return true;
+                return Some("article slug");
             }
 
             // FP: Blog slugs starting with a year (e.g. "2025-year-in-review...")
@@ -199,7 +223,7 @@ pub fn is_likely_false_positive(name: &str) -> bool {
                 // Count hyphens — blog slugs have many, package names usually <5
                 let hyphen_count = pkg_part.chars().filter(|&c| c == '-').count();
                 if hyphen_count > 5 {
-                    return true;
+                    return Some("dated article slug");
                 }
             }
 
@@ -210,24 +234,24 @@ pub fn is_likely_false_positive(name: &str) -> bool {
                     if last_part.len() >= 10
                         && last_part.chars().all(|c| c.is_ascii_hexdigit())
                     {
-                        return true;
+                        return Some("article slug with hash id");
                     }
                 }
             }
         }
 
         // Skip remaining scoped packages (they're more reliable)
-        return false;
+        return None;
     }
 
     // Very short names (1-2 chars) are usually variables, not packages
     if name.len() <= 2 {
-        return true;
+        return Some("too short");
     }
 
     // Names ending with _id or _ID (webpack/bundler identifiers)
     if name.ends_with("_id") || name.ends_with("_ID") || name.ends_with("Id") {
-        return true;
+        return Some("identifier suffix");
     }
 
     // Common webpack/bundler artifacts
@@ -250,7 +274,7 @@ pub fn is_likely_false_positive(name: &str) -> bool {
 
     for artifact in bundler_artifacts {
         if name == artifact || name.starts_with(artifact) {
-            return true;
+            return Some("bundler artifact");
         }
     }
 
@@ -262,7 +286,7 @@ pub fn is_likely_false_positive(name: &str) -> bool {
             let last_part = parts[1];
             // If last part is a single letter or very short (1-2 chars), likely an artifact
             if last_part.len() <= 2 && last_part.chars().all(|c| c.is_ascii_alphabetic()) {
-                return true;
+                return Some("list identifier");
             }
         }
     }
@@ -274,7 +298,7 @@ pub fn is_likely_false_positive(name: &str) -> bool {
     ];
 
     if generic_names.contains(&name) {
-        return true;
+        return Some("generic name");
     }
 
     // Hex hashes from obfuscated code (e.g., cce448c, 806d289, 02cd8bbf69bb5ae8)
@@ -285,7 +309,7 @@ pub fn is_likely_false_positive(name: &str) -> bool {
 
         // If mixed hex (has both letters and numbers), likely a hash
         if has_letter && has_digit {
-            return true;
+            return Some("hex hash");
         }
     }
 
@@ -304,7 +328,7 @@ pub fn is_likely_false_positive(name: &str) -> bool {
     // Check lowercase version for case-insensitive matching
     let name_lower = name.to_lowercase();
     if js_builtins.contains(&name_lower.as_str()) {
-        return true;
+        return Some("JavaScript built-in");
     }
 
     // Very short names from deobfuscation (3-4 chars)
@@ -316,7 +340,7 @@ pub fn is_likely_false_positive(name: &str) -> bool {
         let all_alphanumeric = name.chars().all(|c| c.is_ascii_alphanumeric());
 
         if all_lowercase || (has_digit && all_alphanumeric) {
-            return true;
+            return Some("short identifier");
         }
     }
 
@@ -324,7 +348,7 @@ pub fn is_likely_false_positive(name: &str) -> bool {
     let webpack_suffixes = ["-handler", "-tgl", "-btn", "-grp", "-chkbox"];
     for suffix in webpack_suffixes {
         if name.ends_with(suffix) {
-            return true;
+            return Some("webpack module suffix");
         }
     }
 
@@ -338,14 +362,14 @@ pub fn is_likely_false_positive(name: &str) -> bool {
     ];
     for pattern in webpack_patterns {
         if name.starts_with(pattern) || name.contains(pattern) {
-            return true;
+            return Some("webpack module name");
         }
     }
 
     // Brand names and specific false positives
     let brand_names = ["rakbank"]; // Banking/company brands unlikely to be npm packages
     if brand_names.contains(&name_lower.as_str()) {
-        return true;
+        return Some("brand name");
     }
 
     // Webpack hashed module names (e.g., react-d494828cb1d95eaa, design-system-f4677b5ea6850f41)
@@ -355,7 +379,7 @@ pub fn is_likely_false_positive(name: &str) -> bool {
         if let Some(last_part) = parts.last() {
             // If last part is a long hex string (12+ chars), likely a webpack hash
             if last_part.len() >= 12 && last_part.chars().all(|c| c.is_ascii_hexdigit()) {
-                return true;
+                return Some("hashed module name");
             }
         }
     }
@@ -371,7 +395,7 @@ pub fn is_likely_false_positive(name: &str) -> bool {
     ];
     for tld in tlds {
         if name.contains(tld) {
-            return true;
+            return Some("domain name");
         }
     }
 
@@ -381,7 +405,7 @@ pub fn is_likely_false_positive(name: &str) -> bool {
         "visible", "hidden", "selected", "focused", "checked", "valid",
     ];
     if more_generic_names.contains(&name_lower.as_str()) {
-        return true;
+        return Some("state name");
     }
 
     // DOM event names — extracted by deobfuscator from event handlers, never real packages
@@ -394,7 +418,7 @@ pub fn is_likely_false_positive(name: &str) -> bool {
         "focusin", "focusout", "compositionstart", "compositionend",
     ];
     if dom_events.contains(&name_lower.as_str()) {
-        return true;
+        return Some("DOM event");
     }
 
     // Referrer policy values and other web API constants
@@ -403,12 +427,13 @@ pub fn is_likely_false_positive(name: &str) -> bool {
         "evenodd", "alphabetic", "experimental-webgl",
     ];
     if web_constants.contains(&name) {
-        return true;
+        return Some("web API constant");
     }
 
-    false
+    None
 }
 
+/// Check if a package name looks like an internal/private package.
 pub fn is_likely_internal(name: &str) -> bool {
     // Scoped packages with company-like scopes
     if name.starts_with('@') {
@@ -468,6 +493,25 @@ mod tests {
         assert_eq!(normalize_package_name("node:fs"), None);
     }
 
+    #[test]
+    fn test_normalize_records_rejections() {
+        let log = std::sync::Arc::new(explain::FilterLog::default());
+        explain::sync_scope(Some(log.clone()), || {
+            normalize_package_name("./local");
+            normalize_package_name("node:fs");
+            normalize_package_name("lodash");
+            normalize_package_name("  ");
+        });
+        let rules: Vec<(String, String)> = log.take().into_iter().map(|f| (f.name, f.rule)).collect();
+        assert_eq!(
+            rules,
+            vec![
+                ("./local".to_string(), "normalize: relative path".to_string()),
+                ("node:fs".to_string(), "normalize: node built-in".to_string()),
+            ]
+        );
+    }
+
     #[test]
     fn test_is_valid_package_name() {
         assert!(is_valid_package_name("lodash"));
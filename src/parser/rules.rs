@@ -0,0 +1,324 @@
+//! User-defined filter rules.
+//!
+//! A TOML rules file tunes false positive filtering without code changes. Both
+//! tables take the same keys:
+//!
+//! ```toml
+//! [allow]
+//! names = ["@getbento/website-components"]
+//! scopes = ["@acme"]
+//!
+//! [deny]
+//! names = ["rakbank"]
+//! globs = ["*-theme", "@odoo/*"]
+//! regexes = ['^web-[a-z]+-legacy$']
+//! scopes = ["@web"]
+//! sources = ["*.odoo.com/*"]     # URL of the file the name was found in
+//! ```
+//!
+//! Allow rules for names, scopes, globs and regexes win over deny rules and the
+//! built-in filters. An allow `sources` rule only exempts the names found in
+//! matching files from deny rules; the built-in filters still apply to them.
+//! Deny rules drop names the built-in filters keep. Several files can be loaded
+//! and are merged, so shared rule packs combine with local ones.
+//!
+//! Rules belong to a scanner, not the process: extraction tasks run with their
+//! scanner's rules made current by [`scope`], the same way the explain log is
+//! passed to them.
+
+use crate::scope::glob_match;
+use crate::types::{DepfusedError, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+
+tokio::task_local! {
+    static CURRENT: Arc<FilterRules>;
+}
+
+/// Run `f` with `rules` consulted by [`should_filter_package`](super::should_filter_package),
+/// or as is without rules.
+pub async fn scope<F: Future>(rules: Option<Arc<FilterRules>>, f: F) -> F::Output {
+    match rules {
+        Some(rules) => CURRENT.scope(rules, f).await,
+        None => f.await,
+    }
+}
+
+/// [`scope`] for synchronous code.
+pub fn sync_scope<R>(rules: Option<Arc<FilterRules>>, f: impl FnOnce() -> R) -> R {
+    match rules {
+        Some(rules) => CURRENT.sync_scope(rules, f),
+        None => f(),
+    }
+}
+
+/// The rule deciding `name` under the current rules, if there are any and one matches.
+pub fn check_current(name: &str, source_url: Option<&str>) -> Option<RuleMatch> {
+    CURRENT
+        .try_with(|rules| rules.check(name, source_url))
+        .ok()
+        .flatten()
+}
+
+/// What a matching rule does with a name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleAction {
+    Allow,
+    Deny,
+}
+
+/// The rule that decided a name, e.g. `deny glob *-theme`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleMatch {
+    pub action: RuleAction,
+    pub rule: String,
+}
+
+impl fmt::Display for RuleMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self.action {
+            RuleAction::Allow => "allow",
+            RuleAction::Deny => "deny",
+        };
+        write!(f, "{} {}", action, self.rule)
+    }
+}
+
+/// One table of a rules file, as written.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RuleSetFile {
+    names: Vec<String>,
+    globs: Vec<String>,
+    regexes: Vec<String>,
+    scopes: Vec<String>,
+    sources: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RulesFile {
+    allow: RuleSetFile,
+    deny: RuleSetFile,
+}
+
+/// Compiled rules of one table.
+#[derive(Debug, Default)]
+struct RuleSet {
+    names: HashSet<String>,
+    globs: Vec<String>,
+    regexes: Vec<Regex>,
+    /// Scopes with their `@`.
+    scopes: HashSet<String>,
+    /// Lowercased URL globs.
+    sources: Vec<String>,
+}
+
+impl RuleSet {
+    fn compile(file: RuleSetFile) -> Result<Self> {
+        let regexes = file
+            .regexes
+            .iter()
+            .map(|re| {
+                Regex::new(re).map_err(|e| {
+                    DepfusedError::ConfigError(format!("Invalid regex in filter rules '{}': {}", re, e))
+                })
+            })
+            .collect::<Result<_>>()?;
+        let scopes = file
+            .scopes
+            .iter()
+            .map(|scope| format!("@{}", scope.trim().trim_start_matches('@')))
+            .collect();
+
+        Ok(Self {
+            names: file.names.into_iter().collect(),
+            globs: file.globs,
+            regexes,
+            scopes,
+            sources: file.sources.iter().map(|s| s.to_ascii_lowercase()).collect(),
+        })
+    }
+
+    fn extend(&mut self, other: RuleSet) {
+        self.names.extend(other.names);
+        self.globs.extend(other.globs);
+        self.regexes.extend(other.regexes);
+        self.scopes.extend(other.scopes);
+        self.sources.extend(other.sources);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.names.is_empty()
+            && self.globs.is_empty()
+            && self.regexes.is_empty()
+            && self.scopes.is_empty()
+            && self.sources.is_empty()
+    }
+
+    /// Describe the first rule matching the name or its source URL.
+    fn matching(&self, name: &str, source_url: Option<&str>) -> Option<String> {
+        self.matching_name(name)
+            .or_else(|| self.matching_source(source_url?))
+    }
+
+    /// Describe the first name, scope, glob or regex rule matching the name.
+    fn matching_name(&self, name: &str) -> Option<String> {
+        if self.names.contains(name) {
+            return Some(format!("name {}", name));
+        }
+        if let Some(scope) = name.split_once('/').map(|(scope, _)| scope) {
+            if self.scopes.contains(scope) {
+                return Some(format!("scope {}", scope));
+            }
+        }
+        if let Some(glob) = self.globs.iter().find(|glob| glob_match(glob, name)) {
+            return Some(format!("glob {}", glob));
+        }
+        self.regexes
+            .iter()
+            .find(|re| re.is_match(name))
+            .map(|re| format!("regex {}", re.as_str()))
+    }
+
+    /// Describe the first source rule matching the URL.
+    fn matching_source(&self, source_url: &str) -> Option<String> {
+        let url = source_url.to_ascii_lowercase();
+        self.sources
+            .iter()
+            .find(|glob| glob_match(glob, &url))
+            .map(|glob| format!("source {}", glob))
+    }
+}
+
+/// Allow and deny rules loaded from one or more rules files.
+#[derive(Debug, Default)]
+pub struct FilterRules {
+    allow: RuleSet,
+    deny: RuleSet,
+}
+
+impl FilterRules {
+    /// Load a rules file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content).map_err(|e| match e {
+            DepfusedError::ConfigError(msg) => {
+                DepfusedError::ConfigError(format!("{}: {}", path.display(), msg))
+            }
+            e => e,
+        })
+    }
+
+    /// Parse rules from TOML text.
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: RulesFile = toml::from_str(content)
+            .map_err(|e| DepfusedError::ConfigError(format!("Invalid filter rules: {}", e)))?;
+        Ok(Self {
+            allow: RuleSet::compile(file.allow)?,
+            deny: RuleSet::compile(file.deny)?,
+        })
+    }
+
+    /// Add the rules of another file.
+    pub fn merge(&mut self, other: FilterRules) {
+        self.allow.extend(other.allow);
+        self.deny.extend(other.deny);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// The rule deciding whether a name found in `source_url` is kept, if any
+    /// matches. Allow rules are checked first.
+    ///
+    /// A name in a file matched by an allow `sources` rule is never denied, but
+    /// isn't allowed either: no rule decides it and the built-in filters apply.
+    pub fn check(&self, name: &str, source_url: Option<&str>) -> Option<RuleMatch> {
+        if let Some(rule) = self.allow.matching_name(name) {
+            return Some(RuleMatch { action: RuleAction::Allow, rule });
+        }
+        if source_url.is_some_and(|url| self.allow.matching_source(url).is_some()) {
+            return None;
+        }
+        self.deny
+            .matching(name, source_url)
+            .map(|rule| RuleMatch { action: RuleAction::Deny, rule })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = r#"
+        [allow]
+        names = ["@getbento/website-components"]
+        scopes = ["acme"]
+        sources = ["https://apps.odoo.com/*"]
+
+        [deny]
+        names = ["rakbank"]
+        globs = ["*-theme", "@getbento/*"]
+        regexes = ['^web-[a-z]+-legacy$']
+        scopes = ["@web"]
+        sources = ["*.odoo.com/*"]
+    "#;
+
+    fn action(rules: &FilterRules, name: &str, source: &str) -> Option<RuleAction> {
+        rules.check(name, Some(source)).map(|m| m.action)
+    }
+
+    #[test]
+    fn test_check_rules() {
+        let rules = FilterRules::parse(RULES).unwrap();
+        let site = "https://example.com/main.js";
+
+        assert_eq!(action(&rules, "rakbank", site), Some(RuleAction::Deny));
+        assert_eq!(action(&rules, "shop-theme", site), Some(RuleAction::Deny));
+        assert_eq!(action(&rules, "web-forms-legacy", site), Some(RuleAction::Deny));
+        assert_eq!(action(&rules, "@web/core", site), Some(RuleAction::Deny));
+        assert_eq!(action(&rules, "lodash", "https://WWW.odoo.com/web/assets.js"), Some(RuleAction::Deny));
+        assert_eq!(action(&rules, "lodash", site), None);
+
+        // Allow wins over a matching deny rule
+        assert_eq!(action(&rules, "@getbento/website-components", site), Some(RuleAction::Allow));
+        assert_eq!(action(&rules, "@getbento/other", site), Some(RuleAction::Deny));
+        assert_eq!(action(&rules, "@acme/shop-theme", site), Some(RuleAction::Allow));
+
+        // An allowed source shields its names from deny rules, but decides nothing itself
+        assert_eq!(action(&rules, "lodash", "https://apps.odoo.com/main.js"), None);
+        assert_eq!(action(&rules, "shop-theme", "https://apps.odoo.com/main.js"), None);
+
+        let matched = rules.check("shop-theme", None).unwrap();
+        assert_eq!(matched.to_string(), "deny glob *-theme");
+    }
+
+    #[test]
+    fn test_merge_and_errors() {
+        let mut rules = FilterRules::parse("[deny]\nnames = [\"a-b\"]").unwrap();
+        rules.merge(FilterRules::parse("[allow]\nnames = [\"a-b\"]").unwrap());
+        assert_eq!(rules.check("a-b", None).map(|m| m.action), Some(RuleAction::Allow));
+        assert!(!rules.is_empty());
+        assert!(FilterRules::default().is_empty());
+
+        assert!(FilterRules::parse("[deny]\nregexes = ['(']").is_err());
+        assert!(FilterRules::parse("[deny]\nname = ['typo']").is_err());
+        assert!(FilterRules::parse("[block]").is_err());
+    }
+
+    #[test]
+    fn test_scoped_rules() {
+        let rules = Arc::new(FilterRules::parse("[deny]\nnames = [\"a-b\"]").unwrap());
+        assert!(check_current("a-b", None).is_none());
+        let action = sync_scope(Some(rules), || check_current("a-b", None).map(|m| m.action));
+        assert_eq!(action, Some(RuleAction::Deny));
+        assert!(check_current("a-b", None).is_none());
+    }
+}
@@ -1,10 +1,387 @@
 //! Source map parser for extracting package names from sources array.
 
-use crate::parser::{filters, normalize_package_name};
-use crate::types::{Confidence, ExtractionMethod, Package, Result};
+use crate::parser::{explain, filters, normalize_package_name, PackageManifest};
+use crate::types::{Confidence, Evidence, ExtractionMethod, OriginalLocation, Package, Result, Span};
 use std::collections::HashSet;
+use std::ops::Range;
 use tracing::debug;
 
+/// Packages extracted from a source map, plus index sections still to be fetched.
+#[derive(Debug, Default)]
+pub struct ParsedSourceMap {
+    pub packages: Vec<Package>,
+    pub workspace_only: HashSet<String>,
+    /// Names seen under `node_modules/`, to reconcile `workspace_only` with sections.
+    pub node_modules_names: HashSet<String>,
+    /// Absolute URLs of index-map sections that reference a remote map.
+    pub remote_sections: Vec<String>,
+    /// The decoded map, for locating extractions from the generated file.
+    pub lookup: Option<SourceMapLookup>,
+}
+
+impl ParsedSourceMap {
+    /// Merge a remote section of this index map.
+    ///
+    /// A name is workspace-only when no part of the map, parent or section, has
+    /// it under `node_modules/`, so the sets are reconciled after merging.
+    pub fn merge_section(&mut self, section: ParsedSourceMap) {
+        self.packages.extend(section.packages);
+        self.node_modules_names.extend(section.node_modules_names);
+        self.workspace_only.extend(section.workspace_only);
+        let node_modules_names = &self.node_modules_names;
+        self.workspace_only.retain(|name| !node_modules_names.contains(name));
+    }
+}
+
+/// A decoded source map kept to translate generated positions to original ones.
+#[derive(Debug)]
+pub struct SourceMapLookup {
+    map: sourcemap::DecodedMap,
+}
+
+impl SourceMapLookup {
+    /// Original file, line and column of a byte range in the generated file.
+    pub fn locate(&self, generated: &LineIndex, span: Span) -> Option<OriginalLocation> {
+        let (line, column) = generated.position(span.start);
+        let token = self.map.lookup_token(line, column)?;
+        Some(OriginalLocation {
+            file: display_source_path(token.get_source()?),
+            line: token.get_src_line() + 1,
+            column: token.get_src_col() + 1,
+        })
+    }
+}
+
+/// Line starts of a text, to turn byte offsets into source map positions.
+#[derive(Debug)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { text, starts }
+    }
+
+    /// 0-based line and UTF-16 column of a byte offset, as source maps count them.
+    pub fn position(&self, offset: usize) -> (u32, u32) {
+        let offset = offset.min(self.text.len());
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let column = self.text[self.starts[line]..]
+            .char_indices()
+            .take_while(|(i, _)| self.starts[line] + i < offset)
+            .map(|(_, c)| c.len_utf16() as u32)
+            .sum();
+        (line as u32, column)
+    }
+
+    /// Position and snippet of a byte range, for showing where a package was found.
+    ///
+    /// The snippet keeps some code on each side of the match, without crossing
+    /// line ends, and marks cuts with `…`.
+    pub fn evidence(&self, span: Span) -> Evidence {
+        let text = self.text;
+        let start = floor_char_boundary(text, span.start);
+        let end = floor_char_boundary(text, span.end.max(start));
+        let (line, column) = self.position(start);
+
+        let line_start = self.starts[line as usize];
+        let line_end = text[end..].find('\n').map_or(text.len(), |i| end + i);
+        let shown_end = floor_char_boundary(text, end.min(start + SNIPPET_MAX_MATCH));
+        let from = floor_char_boundary(text, start.saturating_sub(SNIPPET_CONTEXT).max(line_start));
+        let to = if shown_end < end {
+            shown_end
+        } else {
+            floor_char_boundary(text, (end + SNIPPET_CONTEXT).min(line_end))
+        };
+
+        let mut snippet = String::new();
+        if from > line_start {
+            snippet.push('…');
+        }
+        snippet.push_str(&text[from..to].split_whitespace().collect::<Vec<_>>().join(" "));
+        if to < line_end {
+            snippet.push('…');
+        }
+
+        Evidence {
+            span: Span { start, end },
+            line: line + 1,
+            column: column + 1,
+            snippet,
+        }
+    }
+}
+
+/// Bytes of code kept on each side of a match in an evidence snippet.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Longest match shown in full in an evidence snippet.
+const SNIPPET_MAX_MATCH: usize = 160;
+
+/// Largest char boundary at or before `index`.
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Packages of a `package.json` embedded as `content`, located in that file
+/// like the other packages read from `sourcesContent`.
+fn manifest_packages(
+    manifest: &PackageManifest,
+    content: &str,
+    source_path: &str,
+    map_url: &str,
+) -> Vec<Package> {
+    let lines = LineIndex::new(content);
+    let mut packages = manifest.packages(map_url);
+    for pkg in &mut packages {
+        // The name's first appearance as a JSON string: the `name` field or a dependency key
+        let quoted = format!("\"{}\"", pkg.name);
+        let Some(start) = content.find(&quoted) else {
+            continue;
+        };
+        let (line, column) = lines.position(start);
+        pkg.original = Some(OriginalLocation {
+            file: display_source_path(source_path),
+            line: line + 1,
+            column: column + 1,
+        });
+        pkg.evidence = Some(lines.evidence((start..start + quoted.len()).into()));
+    }
+    packages
+}
+
+/// Record evidence for every package with a span into `text`.
+pub fn attach_evidence(packages: &mut [Package], text: &str) {
+    if packages.iter().all(|p| p.span.is_none()) {
+        return;
+    }
+    let lines = LineIndex::new(text);
+    for pkg in packages {
+        if let Some(span) = pkg.span {
+            pkg.evidence = Some(lines.evidence(span));
+        }
+    }
+}
+
+/// A `sources` entry without bundler scheme, query and `./` segments:
+/// `webpack://acme/./src/login/index.tsx?7f3a` becomes `acme/src/login/index.tsx`.
+pub fn display_source_path(source: &str) -> String {
+    let source = source.split(['?', '#']).next().unwrap_or_default();
+    let source = source.split_once("://").map_or(source, |(_, rest)| rest);
+    source
+        .split('/')
+        .filter(|part| !matches!(*part, "" | "."))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Nesting depth at which [`source_ranges`] gives up on a map.
+const MAX_JSON_DEPTH: usize = 64;
+
+/// Byte ranges, quotes included, of the `sources` strings of every regular map
+/// in a source map's raw JSON, in document order (the order of
+/// [`flatten_sections`]).
+///
+/// Offsets are recorded while scanning, so escapes such as `\/` don't matter.
+/// Returns `None` for JSON the scanner can't follow.
+fn source_ranges(json: &str) -> Option<Vec<Vec<Option<Range<usize>>>>> {
+    let mut scanner = RawJson { bytes: json.as_bytes(), pos: json.find('{')?, maps: Vec::new() };
+    scanner.value(0)?;
+    Some(scanner.maps)
+}
+
+/// Minimal JSON scanner behind [`source_ranges`].
+struct RawJson<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    maps: Vec<Vec<Option<Range<usize>>>>,
+}
+
+impl RawJson<'_> {
+    fn peek(&mut self) -> Option<u8> {
+        while self.bytes.get(self.pos)?.is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        (self.peek()? == byte).then(|| self.pos += 1)
+    }
+
+    fn string(&mut self) -> Option<Range<usize>> {
+        let start = self.pos;
+        self.expect(b'"')?;
+        loop {
+            match *self.bytes.get(self.pos)? {
+                b'"' => break,
+                b'\\' => self.pos += 2,
+                _ => self.pos += 1,
+            }
+        }
+        self.pos += 1;
+        Some(start..self.pos)
+    }
+
+    fn value(&mut self, depth: usize) -> Option<()> {
+        if depth > MAX_JSON_DEPTH {
+            return None;
+        }
+        match self.peek()? {
+            b'{' => self.object(depth),
+            b'[' => self.array(depth, |_, _| {}),
+            b'"' => self.string().map(|_| ()),
+            _ => {
+                let end = self.bytes[self.pos..]
+                    .iter()
+                    .position(|b| matches!(b, b',' | b'}' | b']') || b.is_ascii_whitespace())?;
+                self.pos += end;
+                Some(())
+            }
+        }
+    }
+
+    /// Scan an array, passing each element's string range (if it is a string) to `item`.
+    fn array(&mut self, depth: usize, mut item: impl FnMut(&mut Self, Option<Range<usize>>)) -> Option<()> {
+        self.expect(b'[')?;
+        while self.peek()? != b']' {
+            if self.peek()? == b'"' {
+                let range = self.string()?;
+                item(self, Some(range));
+            } else {
+                self.value(depth + 1)?;
+                item(self, None);
+            }
+            if self.peek()? == b',' {
+                self.pos += 1;
+            }
+        }
+        self.pos += 1;
+        Some(())
+    }
+
+    fn object(&mut self, depth: usize) -> Option<()> {
+        self.expect(b'{')?;
+        let mut sources = None;
+        while self.peek()? != b'}' {
+            let key = self.string()?;
+            self.expect(b':')?;
+            if &self.bytes[key] == b"\"sources\"" && self.peek()? == b'[' {
+                let mut ranges = Vec::new();
+                self.array(depth, |_, range| ranges.push(range))?;
+                sources = Some(ranges);
+            } else {
+                self.value(depth + 1)?;
+            }
+            if self.peek()? == b',' {
+                self.pos += 1;
+            }
+        }
+        self.pos += 1;
+        // Nested section maps close first, so maps are listed in document order
+        if let Some(sources) = sources {
+            self.maps.push(sources);
+        }
+        Some(())
+    }
+}
+
+/// Collect every regular map in `decoded` with the URL its packages are attributed to.
+fn flatten_sections<'a>(
+    decoded: &'a sourcemap::DecodedMap,
+    map_url: &str,
+    maps: &mut Vec<(String, &'a sourcemap::SourceMap)>,
+    remote_sections: &mut Vec<String>,
+) {
+    match decoded {
+        sourcemap::DecodedMap::Regular(map) => maps.push((map_url.to_string(), map)),
+        sourcemap::DecodedMap::Hermes(map) => maps.push((map_url.to_string(), map)),
+        sourcemap::DecodedMap::Index(index) => {
+            for (i, section) in index.sections().enumerate() {
+                if let Some(map) = section.get_sourcemap() {
+                    flatten_sections(map, &format!("{}#section={}", map_url, i), maps, remote_sections);
+                } else if let Some(url) = section.get_url() {
+                    let resolved = url::Url::parse(map_url)
+                        .and_then(|base| base.join(url))
+                        .map(|u| u.to_string());
+                    match resolved {
+                        Ok(url) if !remote_sections.contains(&url) => remote_sections.push(url),
+                        Ok(_) => {}
+                        Err(e) => debug!("Bad section URL {} in {}: {}", url, map_url, e),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// One `sourcesContent` entry of a source map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedSource {
+    /// Map (or `#section=<n>` of an index map) the source came from.
+    pub map_url: String,
+    /// Source path as written in `sources`.
+    pub path: String,
+    pub content: String,
+}
+
+/// Every embedded source in a regular or index map. Remote index sections are skipped.
+pub fn embedded_sources(content: &str, map_url: &str) -> Result<Vec<EmbeddedSource>> {
+    let decoded = sourcemap::decode_slice(content.as_bytes())
+        .map_err(|e| crate::types::DepfusedError::SourceMapError(e.to_string()))?;
+
+    let mut maps = Vec::new();
+    flatten_sections(&decoded, map_url, &mut maps, &mut Vec::new());
+
+    let mut sources = Vec::new();
+    for (map_url, map) in maps {
+        for i in 0..map.get_source_count() {
+            if let (Some(path), Some(content)) = (map.get_source(i), map.get_source_contents(i)) {
+                sources.push(EmbeddedSource {
+                    map_url: map_url.clone(),
+                    path: path.to_string(),
+                    content: content.to_string(),
+                });
+            }
+        }
+    }
+    Ok(sources)
+}
+
+/// Reads a version from a package store directory name.
+type StoreVersion = fn(&str) -> Option<String>;
+
+/// Version from a pnpm store directory such as `@acme+ui@3.2.1_react@18.2.0`
+/// or `@acme+ui@3.2.1(react@18.2.0)`.
+fn pnpm_store_version(dir: &str) -> Option<String> {
+    // The first '@' after a leading scope separates name and version
+    let at = dir.get(1..)?.find('@')? + 1;
+    let rest = &dir[at + 1..];
+    let end = rest.find(['_', '(']).unwrap_or(rest.len());
+    valid_version(&rest[..end])
+}
+
+/// Version from a Yarn cache entry such as `@acme-ui-npm-3.2.1-0a1b2c3d4e.zip`.
+fn yarn_store_version(dir: &str) -> Option<String> {
+    let dir = dir.strip_suffix(".zip").unwrap_or(dir);
+    let (_, rest) = dir.rsplit_once("-npm-")?;
+    let (version, _checksum) = rest.rsplit_once('-')?;
+    valid_version(version)
+}
+
+fn valid_version(version: &str) -> Option<String> {
+    semver::Version::parse(version).ok().map(|_| version.to_string())
+}
+
 /// Parser for extracting package information from source maps.
 #[derive(Clone)]
 pub struct SourceMapParser;
@@ -20,23 +397,39 @@ impl SourceMapParser {
     /// Returns `(packages, workspace_only_names)` where `workspace_only_names` contains
     /// package names found in `packages/` paths but NOT in `node_modules/` paths.
     /// These are monorepo workspace packages that should be suppressed.
+    ///
+    /// Sections of an index map that reference remote maps are ignored; use
+    /// [`Self::parse_map`] to get their URLs.
     pub fn parse(&self, content: &str, source_url: &str) -> Result<(Vec<Package>, HashSet<String>)> {
-        let map: sourcemap::SourceMap = sourcemap::SourceMap::from_slice(content.as_bytes())
+        let parsed = self.parse_map(content, source_url)?;
+        Ok((parsed.packages, parsed.workspace_only))
+    }
+
+    /// Parse a regular or index (sectioned) source map.
+    ///
+    /// Packages from an embedded section are attributed to `<source_url>#section=<n>`.
+    /// Sections that only carry a `url` are returned, resolved against `source_url`,
+    /// for the caller to fetch and parse.
+    pub fn parse_map(&self, content: &str, source_url: &str) -> Result<ParsedSourceMap> {
+        let decoded = sourcemap::decode_slice(content.as_bytes())
             .map_err(|e| crate::types::DepfusedError::SourceMapError(e.to_string()))?;
 
-        // Classification pass: identify workspace-only packages
+        let mut maps: Vec<(String, &sourcemap::SourceMap)> = Vec::new();
+        let mut remote_sections = Vec::new();
+        flatten_sections(&decoded, source_url, &mut maps, &mut remote_sections);
+
+        // Classification pass: identify workspace-only packages across all sections
         let mut node_modules_names: HashSet<String> = HashSet::new();
         let mut workspace_names: HashSet<String> = HashSet::new();
 
-        for source in map.sources() {
+        for source in maps.iter().flat_map(|(_, map)| map.sources()) {
             let path = source
                 .strip_prefix("webpack:///")
                 .or_else(|| source.strip_prefix("webpack://"))
                 .unwrap_or(source);
 
-            if let Some(idx) = path.find("node_modules/") {
-                let after = &path[idx + "node_modules/".len()..];
-                if let Some(name) = self.extract_package_from_path_segment(after) {
+            if path.contains("node_modules/") {
+                if let Some((name, _)) = self.extract_node_modules_package(path) {
                     node_modules_names.insert(name);
                 }
             } else if let Some(idx) = path.find("packages/") {
@@ -53,36 +446,70 @@ impl SourceMapParser {
             .collect();
 
         let mut packages = HashSet::new();
+        // Raw offsets of each map's sources; only trusted when they line up with the decoded maps
+        let ranges = source_ranges(content).filter(|ranges| {
+            ranges.len() == maps.len()
+                && ranges.iter().zip(&maps).all(|(r, (_, map))| r.len() == map.get_source_count() as usize)
+        });
 
-        // Extract from sources array
-        for source in map.sources() {
-            if let Some(pkgs) = self.extract_packages_from_path(source, source_url) {
-                packages.extend(pkgs);
+        for (index, (map_url, map)) in maps.iter().enumerate() {
+            // Extract from sources array
+            for (i, source) in map.sources().enumerate() {
+                if let Some(pkgs) = self.extract_packages_from_path(source, map_url) {
+                    let span = ranges.as_ref().and_then(|ranges| ranges[index][i].clone());
+                    packages.extend(pkgs.into_iter().map(|pkg| match &span {
+                        Some(span) => pkg.with_span(span.clone()),
+                        None => pkg,
+                    }));
+                }
             }
-        }
 
-        // Extract from sourcesContent — find require/import of packages in embedded source code
-        let source_count = map.get_source_count();
-        for i in 0..source_count {
-            if let Some(content) = map.get_source_contents(i) {
-                self.extract_packages_from_source_content(content, source_url, &mut packages);
+            // Extract from sourcesContent — find require/import of packages in embedded source code
+            let source_count = map.get_source_count();
+            for i in 0..source_count {
+                if let Some(content) = map.get_source_contents(i) {
+                    let source_path = map.get_source(i).unwrap_or_default();
+                    // An imported package.json is embedded verbatim
+                    if let Some(manifest) = PackageManifest::parse(content) {
+                        debug!("Embedded manifest {} in {}", source_path, map_url);
+                        packages.extend(manifest_packages(&manifest, content, source_path, map_url));
+                        continue;
+                    }
+                    self.extract_packages_from_source_content(content, source_path, map_url, &mut packages);
+                }
             }
         }
 
         // Filter workspace-only packages
-        let result: Vec<Package> = packages
+        let mut result: Vec<Package> = packages
             .into_iter()
-            .filter(|p| !workspace_only.contains(&p.name))
+            .filter(|p| {
+                let keep = !workspace_only.contains(&p.name);
+                if !keep {
+                    explain::record(&p.name, "workspace-only package", Some(&p.source_url));
+                }
+                keep
+            })
             .collect();
+        attach_evidence(&mut result, content);
 
         debug!(
-            "Extracted {} packages from source map (filtered {} workspace-only): {}",
+            "Extracted {} packages from source map (filtered {} workspace-only, {} sections, {} remote): {}",
             result.len(),
             workspace_only.len(),
+            maps.len(),
+            remote_sections.len(),
             source_url
         );
 
-        Ok((result, workspace_only))
+        drop(maps);
+        Ok(ParsedSourceMap {
+            packages: result,
+            workspace_only,
+            node_modules_names,
+            remote_sections,
+            lookup: Some(SourceMapLookup { map: decoded }),
+        })
     }
 
     /// Extract package names from a source path.
@@ -99,18 +526,23 @@ impl SourceMapParser {
             .or_else(|| path.strip_prefix("webpack://"))
             .unwrap_or(path);
 
-        // Look for node_modules pattern
-        if let Some(idx) = path.find("node_modules/") {
-            let after_nm = &path[idx + "node_modules/".len()..];
-            if let Some(pkg_name) = self.extract_package_from_path_segment(after_nm) {
+        // Look for node_modules pattern (plain, pnpm store or Yarn cache)
+        if path.contains("node_modules/") {
+            if let Some((pkg_name, version)) = self.extract_node_modules_package(path) {
+                // Store directory names (`react@18.2.0`) would trip the context
+                // filters, so only the innermost node_modules path is checked
+                let context = path.rfind("node_modules/").map_or(path, |idx| &path[idx..]);
                 // Apply filters to reduce false positives
-                if !filters::should_filter_package(&pkg_name, Some(path), Some(source_url)) {
-                    packages.insert(Package {
-                        name: pkg_name,
-                        extraction_method: ExtractionMethod::SourceMap,
-                        source_url: source_url.to_string(),
-                        confidence: Confidence::High,
-                    });
+                if !filters::should_filter_package(&pkg_name, Some(context), Some(source_url)) {
+                    packages.insert(
+                        Package::new(
+                            pkg_name,
+                            ExtractionMethod::SourceMap,
+                            source_url.to_string(),
+                            Confidence::High,
+                        )
+                        .with_version(version),
+                    );
                 }
             }
         }
@@ -122,12 +554,12 @@ impl SourceMapParser {
             let after_packages = &path[idx + "packages/".len()..];
             if let Some(pkg_name) = self.extract_package_from_path_segment(after_packages) {
                 if !filters::should_filter_package(&pkg_name, Some(path), Some(source_url)) {
-                    packages.insert(Package {
-                        name: pkg_name,
-                        extraction_method: ExtractionMethod::SourceMap,
-                        source_url: source_url.to_string(),
-                        confidence: Confidence::Low,
-                    });
+                    packages.insert(Package::new(
+                        pkg_name,
+                        ExtractionMethod::SourceMap,
+                        source_url.to_string(),
+                        Confidence::Low,
+                    ));
                 }
             }
         }
@@ -137,12 +569,12 @@ impl SourceMapParser {
             if let Some(pkg_name) = self.extract_package_from_path_segment(clean_path) {
                 // Apply filters to reduce false positives
                 if !filters::should_filter_package(&pkg_name, Some(path), Some(source_url)) {
-                    packages.insert(Package {
-                        name: pkg_name,
-                        extraction_method: ExtractionMethod::SourceMap,
-                        source_url: source_url.to_string(),
-                        confidence: Confidence::Medium,
-                    });
+                    packages.insert(Package::new(
+                        pkg_name,
+                        ExtractionMethod::SourceMap,
+                        source_url.to_string(),
+                        Confidence::Medium,
+                    ));
                 }
             }
         }
@@ -158,9 +590,12 @@ impl SourceMapParser {
     fn extract_packages_from_source_content(
         &self,
         content: &str,
+        source_path: &str,
         source_url: &str,
         packages: &mut HashSet<Package>,
     ) {
+        let lines = LineIndex::new(content);
+
         // Only process content that likely has require/import of packages
         // Match: require("pkg"), require('pkg'), import "pkg", import 'pkg', from "pkg", from 'pkg'
         let patterns = [
@@ -187,12 +622,21 @@ impl SourceMapParser {
                         let raw_name = name_match.as_str();
                         if let Some(pkg_name) = normalize_package_name(raw_name) {
                             if !filters::should_filter_package(&pkg_name, None, Some(source_url)) {
-                                packages.insert(Package {
-                                    name: pkg_name,
-                                    extraction_method: ExtractionMethod::SourceMap,
-                                    source_url: source_url.to_string(),
-                                    confidence: Confidence::Low,
+                                // The embedded file is the original source
+                                let (line, column) = lines.position(name_match.start());
+                                let mut pkg = Package::new(
+                                    pkg_name,
+                                    ExtractionMethod::SourceMap,
+                                    source_url.to_string(),
+                                    Confidence::Low,
+                                );
+                                pkg.original = Some(OriginalLocation {
+                                    file: display_source_path(source_path),
+                                    line: line + 1,
+                                    column: column + 1,
                                 });
+                                pkg.evidence = Some(lines.evidence(caps.get(0).unwrap().range().into()));
+                                packages.insert(pkg);
                             }
                         }
                     }
@@ -201,6 +645,37 @@ impl SourceMapParser {
         }
     }
 
+    /// Extract a package and, where the layout encodes it, its version from a
+    /// path containing `node_modules/`:
+    ///
+    /// - `node_modules/.pnpm/@acme+ui@3.2.1_react@18.2.0/node_modules/@acme/ui/...`
+    /// - `node_modules/.pnpm/node_modules/@acme/ui/...` (pnpm's hoisted links, no version)
+    /// - `.yarn/cache/@acme-ui-npm-3.2.1-0a1b2c3d4e.zip/node_modules/@acme/ui/...`
+    /// - `.yarn/unplugged/@acme-ui-npm-3.2.1-0a1b2c3d4e/node_modules/@acme/ui/...`
+    /// - `node_modules/@acme/ui/...` (no version)
+    fn extract_node_modules_package(&self, path: &str) -> Option<(String, Option<String>)> {
+        let stores: [(&str, StoreVersion); 3] = [
+            ("node_modules/.pnpm/", pnpm_store_version),
+            (".yarn/cache/", yarn_store_version),
+            (".yarn/unplugged/", yarn_store_version),
+        ];
+        for (marker, version_of) in stores {
+            if let Some(idx) = path.find(marker) {
+                let rest = &path[idx + marker.len()..];
+                if let Some(inner) = rest.strip_prefix("node_modules/") {
+                    return Some((self.extract_package_from_path_segment(inner)?, None));
+                }
+                let (store_dir, inner) = rest.split_once("/node_modules/")?;
+                let name = self.extract_package_from_path_segment(inner)?;
+                return Some((name, version_of(store_dir)));
+            }
+        }
+
+        let idx = path.find("node_modules/")?;
+        let name = self.extract_package_from_path_segment(&path[idx + "node_modules/".len()..])?;
+        Some((name, None))
+    }
+
     /// Extract package name from path segment after node_modules.
     fn extract_package_from_path_segment(&self, segment: &str) -> Option<String> {
         let segment = segment.trim_start_matches('/');
@@ -292,6 +767,224 @@ mod tests {
         assert!(workspace.is_empty());
     }
 
+    #[test]
+    fn test_embedded_manifest() {
+        let parser = SourceMapParser::new();
+        let sourcemap_json = r#"{
+            "version": 3,
+            "sources": ["webpack:///./package.json", "webpack:///./src/index.js"],
+            "sourcesContent": [
+                "{\"name\":\"acme-portal\",\"version\":\"1.0.0\",\"dependencies\":{\"@acme/auth-client\":\"^4.0.0\"},\"publishConfig\":{\"registry\":\"https://npm.acme.dev\"}}",
+                "import pkg from '../package.json';"
+            ],
+            "mappings": "AAAA",
+            "names": []
+        }"#;
+
+        let (packages, _) = parser.parse(sourcemap_json, "https://example.com/app.js.map").unwrap();
+        let portal = packages.iter().find(|p| p.name == "acme-portal").unwrap();
+        assert_eq!(portal.extraction_method, ExtractionMethod::Manifest);
+        assert_eq!(portal.registry.as_deref(), Some("https://npm.acme.dev"));
+        let auth = packages.iter().find(|p| p.name == "@acme/auth-client").unwrap();
+        assert_eq!(auth.confidence, Confidence::High);
+        assert!(auth.registry.is_none());
+
+        // Located in the embedded package.json, like other sourcesContent packages
+        let evidence = auth.evidence.as_ref().unwrap();
+        assert!(evidence.snippet.contains(r#""@acme/auth-client":"^4.0.0""#));
+        assert_eq!(auth.original.as_ref().unwrap().file, "package.json");
+        assert_eq!(portal.original.as_ref().unwrap().column, 9);
+    }
+
+    #[test]
+    fn test_locate_original() {
+        let parser = SourceMapParser::new();
+        // Generated line 2 maps to line 14 of the original file
+        let map = r#"{
+            "version": 3,
+            "sources": ["webpack://acme-web/./src/login/index.tsx?3f9a"],
+            "sourcesContent": ["// login\nimport { signIn } from '@acme/auth';"],
+            "mappings": ";AAaA",
+            "names": []
+        }"#;
+        let generated = "var a;\nimport{signIn}from'@acme/auth';";
+
+        let parsed = parser.parse_map(map, "https://example.com/app.js.map").unwrap();
+        let lookup = parsed.lookup.unwrap();
+        let lines = LineIndex::new(generated);
+        let offset = generated.find("'@acme").unwrap();
+        assert_eq!(lines.position(offset), (1, offset as u32 - 7));
+
+        let location = lookup.locate(&lines, Span::from(offset..offset + 12)).unwrap();
+        assert_eq!(location.to_string(), "acme-web/src/login/index.tsx:14");
+        assert_eq!(location.column, 1);
+
+        // Imports found in sourcesContent are located in the embedded file
+        let auth = parsed.packages.iter().find(|p| p.name == "@acme/auth").unwrap();
+        let original = auth.original.as_ref().unwrap();
+        assert_eq!((original.line, original.column), (2, 25));
+    }
+
+    #[test]
+    fn test_line_index_utf16_columns() {
+        let lines = LineIndex::new("a\n\u{1F600}é'x'");
+        // The emoji is two UTF-16 units, é is one
+        assert_eq!(lines.position(2 + 4 + 2), (1, 3));
+        assert_eq!(lines.position(0), (0, 0));
+    }
+
+    #[test]
+    fn test_evidence_snippet() {
+        let bundle = format!(
+            "{}\n{}n(){{return   require(\"@acme/auth-client\")}}{}\nnext",
+            "header",
+            "x".repeat(60),
+            "y".repeat(60)
+        );
+        let start = bundle.find("require").unwrap();
+        let end = bundle.find(")}").unwrap() + 1;
+        let evidence = LineIndex::new(&bundle).evidence((start..end).into());
+
+        assert_eq!(evidence.line, 2);
+        assert_eq!(evidence.column, 60 + 14);
+        assert_eq!(evidence.span, Span { start, end });
+        assert!(evidence.snippet.starts_with('…'));
+        assert!(evidence.snippet.ends_with('…'));
+        assert!(evidence.snippet.contains("n(){return require(\"@acme/auth-client\")}"));
+
+        // Short lines are shown whole, and cuts never split a character
+        let short = LineIndex::new("import 'é-pkg'").evidence((7..15).into());
+        assert_eq!(short.snippet, "import 'é-pkg'");
+        assert_eq!(short.column, 8);
+        let cut = LineIndex::new("éééééééééééééééééééééééééé require('x')").evidence((53..65).into());
+        assert!(cut.snippet.starts_with('…'));
+    }
+
+    #[test]
+    fn test_sources_evidence() {
+        let parser = SourceMapParser::new();
+        let map = r#"{"version":3,"sources":["webpack:///src/app.js","webpack:///node_modules/@company/utils/index.js"],"mappings":"AAAA","names":[]}"#;
+        let (packages, _) = parser.parse(map, "bundle.js.map").unwrap();
+
+        let evidence = packages[0].evidence.as_ref().unwrap();
+        assert_eq!(&map[evidence.span.start..evidence.span.end], r#""webpack:///node_modules/@company/utils/index.js""#);
+        assert_eq!(evidence.line, 1);
+
+        // Escaped slashes and a repeated source don't throw the spans off
+        let map = r#"{"version":3,"sources":["webpack:\/\/\/src\/app.js","node_modules\/a-lib\/x.js",
+            "node_modules\/@company\/utils\/index.js"],"sourcesContent":["x","node_modules/@company/utils",null],
+            "mappings":"AAAA","names":[]}"#;
+        let (packages, _) = parser.parse(map, "bundle.js.map").unwrap();
+        let utils = packages.iter().find(|p| p.name == "@company/utils").unwrap();
+        let span = utils.evidence.as_ref().unwrap().span;
+        assert_eq!(&map[span.start..span.end], r#""node_modules\/@company\/utils\/index.js""#);
+        assert_eq!(utils.evidence.as_ref().unwrap().line, 2);
+    }
+
+    #[test]
+    fn test_versioned_store_paths() {
+        let parser = SourceMapParser::new();
+        let cases = [
+            (
+                "webpack:///node_modules/.pnpm/@acme+ui@3.2.1_react@18.2.0/node_modules/@acme/ui/dist/index.js",
+                "@acme/ui",
+                Some("3.2.1"),
+            ),
+            (
+                "../node_modules/.pnpm/acme-logger@1.0.0-beta.2(react@18.2.0)/node_modules/acme-logger/index.js",
+                "acme-logger",
+                Some("1.0.0-beta.2"),
+            ),
+            (
+                ".yarn/cache/@acme-ui-npm-3.2.1-0a1b2c3d4e.zip/node_modules/@acme/ui/index.js",
+                "@acme/ui",
+                Some("3.2.1"),
+            ),
+            (
+                ".yarn/unplugged/acme-native-npm-0.4.0-rc.1-9f8e7d6c5b/node_modules/acme-native/build.js",
+                "acme-native",
+                Some("0.4.0-rc.1"),
+            ),
+            ("node_modules/acme-plain/lib/index.js", "acme-plain", None),
+            (
+                "webpack:///node_modules/.pnpm/node_modules/@acme/hoisted/index.js",
+                "@acme/hoisted",
+                None,
+            ),
+        ];
+
+        for (path, name, version) in cases {
+            let packages = parser
+                .extract_packages_from_path(path, "https://example.com/main.js.map")
+                .unwrap_or_else(|| panic!("no package from {}", path));
+            let pkg = packages.iter().next().unwrap();
+            assert_eq!(pkg.name, name, "{}", path);
+            assert_eq!(pkg.version.as_deref(), version, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_parse_index_map() {
+        let parser = SourceMapParser::new();
+        let index = r#"{
+            "version": 3,
+            "file": "bundle.js",
+            "sections": [
+                {"offset": {"line": 0, "column": 0}, "map": {
+                    "version": 3, "sources": ["node_modules/@acme/auth/index.js"], "names": [], "mappings": "AAAA"
+                }},
+                {"offset": {"line": 100, "column": 0}, "map": {
+                    "version": 3, "sources": ["node_modules/internal-logger/lib/log.js"], "names": [], "mappings": "AAAA"
+                }},
+                {"offset": {"line": 200, "column": 0}, "url": "vendor.js.map"}
+            ]
+        }"#;
+
+        let parsed = parser
+            .parse_map(index, "https://cdn.example.com/js/bundle.js.map")
+            .unwrap();
+
+        let auth = parsed.packages.iter().find(|p| p.name == "@acme/auth").unwrap();
+        assert_eq!(auth.source_url, "https://cdn.example.com/js/bundle.js.map#section=0");
+        let logger = parsed.packages.iter().find(|p| p.name == "internal-logger").unwrap();
+        assert_eq!(logger.source_url, "https://cdn.example.com/js/bundle.js.map#section=1");
+        let span = logger.span.unwrap();
+        assert_eq!(&index[span.start..span.end], r#""node_modules/internal-logger/lib/log.js""#);
+        assert_eq!(
+            parsed.remote_sections,
+            vec!["https://cdn.example.com/js/vendor.js.map".to_string()]
+        );
+
+        // The tuple API still works on index maps
+        let (packages, _) = parser.parse(index, "https://cdn.example.com/js/bundle.js.map").unwrap();
+        assert_eq!(packages.len(), 2);
+    }
+
+    #[test]
+    fn test_remote_section_reconciles_workspace_only() {
+        let parser = SourceMapParser::new();
+        let index = r#"{
+            "version": 3,
+            "sections": [
+                {"offset": {"line": 0, "column": 0}, "map": {
+                    "version": 3, "sources": ["packages/shared-utils/src/index.js"], "names": [], "mappings": "AAAA"
+                }},
+                {"offset": {"line": 100, "column": 0}, "url": "vendor.js.map"}
+            ]
+        }"#;
+        let section = r#"{
+            "version": 3, "sources": ["node_modules/shared-utils/index.js"], "names": [], "mappings": "AAAA"
+        }"#;
+
+        let mut parsed = parser.parse_map(index, "https://cdn.example.com/bundle.js.map").unwrap();
+        assert!(parsed.workspace_only.contains("shared-utils"));
+
+        parsed.merge_section(parser.parse_map(section, "https://cdn.example.com/vendor.js.map").unwrap());
+        // Installed from node_modules in the section, so not workspace-only after all
+        assert!(!parsed.workspace_only.contains("shared-utils"));
+        assert!(parsed.packages.iter().any(|p| p.name == "shared-utils"));
+    }
+
     #[test]
     fn test_workspace_only_suppressed() {
         let parser = SourceMapParser::new();
@@ -0,0 +1,982 @@
+//! javascript-obfuscator string array decoding.
+//!
+//! javascript-obfuscator moves string literals into one array, rotates the
+//! array at startup and replaces each literal with a call to a decoder function,
+//! so `require("lodash")` becomes `require(_0x5a1b(0x1f))`. The array, its
+//! rotation and the decoder (plain, base64 or RC4) are recovered from the AST
+//! and evaluated statically - nothing is executed - and each call site is
+//! replaced with the string it returns, so the result reads as ordinary code.
+//!
+//! Names are resolved through the function scopes declaring them, so a local
+//! shadowing a decoder or an array is not mistaken for it. Block scopes are not
+//! told apart from the function around them.
+
+use oxc_allocator::Allocator;
+use oxc_ast::ast::*;
+use oxc_ast::visit::walk;
+use oxc_ast::Visit;
+use oxc_parser::Parser;
+use oxc_span::SourceType;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use tracing::{debug, trace};
+
+/// Alphabet of the obfuscator's base64, lowercase first.
+const BASE64_ALPHABET: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789+/=";
+
+/// Decoders are small; larger functions are not inspected.
+const MAX_DECODER_LEN: usize = 16 * 1024;
+
+/// Deepest nesting of wrapper calls, aliases and expressions evaluated.
+const MAX_DEPTH: usize = 64;
+
+/// Scope of names declared at the top level.
+const PROGRAM: u32 = 0;
+
+/// A declared name: the start of the function body whose scope declares it
+/// ([`PROGRAM`] at the top level, and for undeclared globals), and the name.
+type Binding = (u32, String);
+
+/// The names each function body declares, keyed by its start.
+type Scopes = HashMap<u32, HashSet<String>>;
+
+/// The function bodies enclosing the node a visitor is at.
+#[derive(Clone)]
+struct ScopeStack<'s> {
+    scopes: &'s Scopes,
+    stack: Vec<u32>,
+}
+
+impl<'s> ScopeStack<'s> {
+    fn new(scopes: &'s Scopes) -> Self {
+        Self {
+            scopes,
+            stack: vec![PROGRAM],
+        }
+    }
+
+    fn enter(&mut self, body: &FunctionBody<'_>) {
+        self.stack.push(body.span.start);
+    }
+
+    fn leave(&mut self) {
+        self.stack.pop();
+    }
+
+    /// The stack inside `body`, to read a function before walking into it.
+    fn inside(&self, body: &FunctionBody<'_>) -> Self {
+        let mut scope = self.clone();
+        scope.enter(body);
+        scope
+    }
+
+    /// The binding `name` refers to here.
+    fn resolve(&self, name: &str) -> Binding {
+        let scope = self
+            .stack
+            .iter()
+            .rev()
+            .copied()
+            .find(|scope| self.scopes.get(scope).is_some_and(|names| names.contains(name)))
+            .unwrap_or(PROGRAM);
+        (scope, name.to_string())
+    }
+
+    /// The binding a declaration of `name` here creates.
+    fn declare(&self, name: &str) -> Binding {
+        (self.stack.last().copied().unwrap_or(PROGRAM), name.to_string())
+    }
+}
+
+/// The name of a function's first parameter, if it is a plain identifier.
+fn first_param(params: &FormalParameters<'_>) -> Option<String> {
+    params.items.first()?.pattern.get_identifier().map(|name| name.to_string())
+}
+
+/// Source with string array calls replaced by the strings they return.
+#[derive(Debug)]
+pub struct DecodedSource {
+    pub text: String,
+    replacements: Vec<Replacement>,
+}
+
+#[derive(Debug)]
+struct Replacement {
+    /// The call in the original source.
+    original: Range<usize>,
+    /// The string literal in the decoded text.
+    decoded: Range<usize>,
+}
+
+impl DecodedSource {
+    /// The call a range of the decoded text was decoded from, if the range lies
+    /// within a decoded literal.
+    pub fn original_span(&self, span: Range<usize>) -> Option<Range<usize>> {
+        let index = self
+            .replacements
+            .partition_point(|r| r.decoded.start <= span.start)
+            .checked_sub(1)?;
+        let replacement = &self.replacements[index];
+        (span.end <= replacement.decoded.end).then(|| replacement.original.clone())
+    }
+
+    /// Number of call sites decoded.
+    pub fn decoded_calls(&self) -> usize {
+        self.replacements.len()
+    }
+}
+
+/// Decode the string array calls in `content`, if it has any.
+pub fn decode(content: &str) -> Option<DecodedSource> {
+    let allocator = Allocator::default();
+    let source_type = SourceType::default().with_module(true).with_jsx(true);
+    let program = Parser::new(&allocator, content, source_type).parse().program;
+
+    let mut declarations = DeclarationCollector::default();
+    declarations.visit_program(&program);
+    let DeclarationCollector { scopes, arrays, .. } = declarations;
+    if arrays.is_empty() {
+        return None;
+    }
+
+    let mut analyzer = Analyzer {
+        content,
+        scope: ScopeStack::new(&scopes),
+        eval: Evaluator {
+            arrays,
+            ..Default::default()
+        },
+        rotations: Vec::new(),
+        sites: Vec::new(),
+    };
+    analyzer.visit_program(&program);
+    let Analyzer {
+        mut eval,
+        rotations,
+        mut sites,
+        ..
+    } = analyzer;
+    if eval.decoders.is_empty() {
+        return None;
+    }
+
+    for rotation in &rotations {
+        eval.rotate(rotation);
+    }
+
+    // Outer calls first, so calls nested in a replaced call are skipped
+    sites.sort_by_key(|(span, _)| (span.start, std::cmp::Reverse(span.end)));
+    let mut text = String::with_capacity(content.len());
+    let mut replacements = Vec::new();
+    let mut copied = 0;
+    for (span, call) in sites {
+        if span.start < copied {
+            continue;
+        }
+        let Expr::Call(name, _) = &call else { continue };
+        if !eval.is_callable(name) {
+            continue;
+        }
+        let Some(Value::Str(decoded)) = eval.eval(&call, &HashMap::new(), 0) else {
+            continue;
+        };
+        let Ok(literal) = serde_json::to_string(&decoded) else {
+            continue;
+        };
+        trace!("Decoded string array call at {}: {}", span.start, literal);
+        text.push_str(&content[copied..span.start]);
+        let start = text.len();
+        text.push_str(&literal);
+        replacements.push(Replacement {
+            original: span.clone(),
+            decoded: start..text.len(),
+        });
+        copied = span.end;
+    }
+    if replacements.is_empty() {
+        return None;
+    }
+    text.push_str(&content[copied..]);
+
+    debug!(
+        "Decoded {} string array calls through {} decoders",
+        replacements.len(),
+        eval.decoders.len()
+    );
+    Some(DecodedSource { text, replacements })
+}
+
+/// An expression the evaluator understands.
+#[derive(Debug, Clone)]
+enum Expr {
+    Num(f64),
+    Str(String),
+    Ident(String),
+    Neg(Box<Expr>),
+    Binary(BinaryOperator, Box<Expr>, Box<Expr>),
+    Call(Binding, Vec<Expr>),
+}
+
+impl Expr {
+    /// `expr`, with the functions it calls resolved in `scope`.
+    fn from_ast(expr: &Expression<'_>, scope: &ScopeStack<'_>, depth: usize) -> Option<Self> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        Some(match expr.without_parentheses() {
+            Expression::NumericLiteral(lit) => Expr::Num(lit.value),
+            Expression::StringLiteral(lit) => Expr::Str(lit.value.to_string()),
+            Expression::Identifier(id) => Expr::Ident(id.name.to_string()),
+            Expression::UnaryExpression(unary) => {
+                let argument = Box::new(Self::from_ast(&unary.argument, scope, depth + 1)?);
+                match unary.operator {
+                    UnaryOperator::UnaryNegation => Expr::Neg(argument),
+                    // -(-x) converts to a number like +x
+                    UnaryOperator::UnaryPlus => Expr::Neg(Box::new(Expr::Neg(argument))),
+                    _ => return None,
+                }
+            }
+            Expression::BinaryExpression(binary)
+                if matches!(
+                    binary.operator,
+                    BinaryOperator::Addition
+                        | BinaryOperator::Subtraction
+                        | BinaryOperator::Multiplication
+                        | BinaryOperator::Division
+                        | BinaryOperator::Remainder
+                ) =>
+            {
+                Expr::Binary(
+                    binary.operator,
+                    Box::new(Self::from_ast(&binary.left, scope, depth + 1)?),
+                    Box::new(Self::from_ast(&binary.right, scope, depth + 1)?),
+                )
+            }
+            Expression::CallExpression(call) => Self::from_call(call, scope, depth)?,
+            _ => return None,
+        })
+    }
+
+    /// A call of a function by name.
+    fn from_call(call: &CallExpression<'_>, scope: &ScopeStack<'_>, depth: usize) -> Option<Self> {
+        let Expression::Identifier(callee) = call.callee.without_parentheses() else {
+            return None;
+        };
+        let args = call
+            .arguments
+            .iter()
+            .map(|arg| arg.as_expression().and_then(|arg| Self::from_ast(arg, scope, depth + 1)))
+            .collect::<Option<_>>()?;
+        Some(Expr::Call(scope.resolve(&callee.name), args))
+    }
+
+    fn calls(&self, name: &str) -> bool {
+        match self {
+            Expr::Call((_, callee), args) => callee == name || args.iter().any(|arg| arg.calls(name)),
+            Expr::Neg(argument) => argument.calls(name),
+            Expr::Binary(_, left, right) => left.calls(name) || right.calls(name),
+            Expr::Num(_) | Expr::Str(_) | Expr::Ident(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Num(f64),
+    Str(String),
+}
+
+impl Value {
+    fn to_number(&self) -> f64 {
+        match self {
+            Value::Num(n) => *n,
+            Value::Str(s) => {
+                let s = s.trim();
+                if s.is_empty() {
+                    0.0
+                } else if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                    u64::from_str_radix(hex, 16).map_or(f64::NAN, |n| n as f64)
+                } else {
+                    s.parse().unwrap_or(f64::NAN)
+                }
+            }
+        }
+    }
+
+    fn into_string(self) -> String {
+        match self {
+            Value::Str(s) => s,
+            Value::Num(n) if n.fract() == 0.0 && n.abs() < 1e15 => format!("{}", n as i64),
+            Value::Num(n) => n.to_string(),
+        }
+    }
+}
+
+/// JavaScript's `parseInt` without a radix.
+fn parse_int(s: &str) -> f64 {
+    let s = s.trim_start();
+    let (sign, s) = match s.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (radix, s) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(rest) => (16, rest),
+        None => (10, s),
+    };
+    let digits: String = s.chars().take_while(|c| c.is_digit(radix)).collect();
+    if digits.is_empty() {
+        return f64::NAN;
+    }
+    let value = digits
+        .chars()
+        .filter_map(|c| c.to_digit(radix))
+        .fold(0.0, |n, d| n * radix as f64 + d as f64);
+    sign * value
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Plain,
+    Base64,
+    Rc4,
+}
+
+/// A function returning strings of an array by index.
+#[derive(Debug)]
+struct Decoder {
+    array: Binding,
+    offset: f64,
+    encoding: Encoding,
+}
+
+impl Decoder {
+    fn decode(&self, strings: &[String], args: &[Value]) -> Option<String> {
+        let index = args.first()?.to_number() - self.offset;
+        if index < 0.0 || index.fract() != 0.0 {
+            return None;
+        }
+        let string = strings.get(index as usize)?;
+        match self.encoding {
+            Encoding::Plain => Some(string.clone()),
+            Encoding::Base64 => base64_decode(string),
+            Encoding::Rc4 => {
+                let key = args.get(1)?.clone().into_string();
+                rc4(&base64_decode(string)?, &key)
+            }
+        }
+    }
+}
+
+/// A function passing its adjusted arguments on to a decoder, e.g.
+/// `function w(a, b) { return d(a - -0x1ea, b); }`.
+#[derive(Debug)]
+struct Wrapper {
+    params: Vec<String>,
+    call: Expr,
+}
+
+/// A rotation applied to an array at startup.
+#[derive(Debug)]
+struct Rotation {
+    array: Binding,
+    target: f64,
+    /// Rotation stops when this equals the target; without one the target is
+    /// the number of rotations.
+    checksum: Option<Expr>,
+}
+
+#[derive(Debug, Default)]
+struct Evaluator {
+    arrays: HashMap<Binding, Vec<String>>,
+    decoders: HashMap<Binding, Decoder>,
+    wrappers: HashMap<Binding, Wrapper>,
+    aliases: HashMap<Binding, Binding>,
+}
+
+impl Evaluator {
+    fn resolve<'s>(&'s self, mut name: &'s Binding) -> &'s Binding {
+        for _ in 0..MAX_DEPTH {
+            match self.aliases.get(name) {
+                Some(target) => name = target,
+                None => break,
+            }
+        }
+        name
+    }
+
+    fn is_callable(&self, name: &Binding) -> bool {
+        let name = self.resolve(name);
+        self.decoders.contains_key(name) || self.wrappers.contains_key(name)
+    }
+
+    fn eval(&self, expr: &Expr, env: &HashMap<&str, Value>, depth: usize) -> Option<Value> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        Some(match expr {
+            Expr::Num(n) => Value::Num(*n),
+            Expr::Str(s) => Value::Str(s.clone()),
+            Expr::Ident(name) => env.get(name.as_str())?.clone(),
+            Expr::Neg(argument) => Value::Num(-self.eval(argument, env, depth + 1)?.to_number()),
+            Expr::Binary(operator, left, right) => {
+                let left = self.eval(left, env, depth + 1)?;
+                let right = self.eval(right, env, depth + 1)?;
+                match operator {
+                    BinaryOperator::Addition => match (left, right) {
+                        (Value::Num(l), Value::Num(r)) => Value::Num(l + r),
+                        (l, r) => Value::Str(l.into_string() + &r.into_string()),
+                    },
+                    BinaryOperator::Subtraction => Value::Num(left.to_number() - right.to_number()),
+                    BinaryOperator::Multiplication => Value::Num(left.to_number() * right.to_number()),
+                    BinaryOperator::Division => Value::Num(left.to_number() / right.to_number()),
+                    BinaryOperator::Remainder => Value::Num(left.to_number() % right.to_number()),
+                    _ => return None,
+                }
+            }
+            Expr::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg, env, depth + 1))
+                    .collect::<Option<Vec<_>>>()?;
+                self.call(name, args, depth + 1)?
+            }
+        })
+    }
+
+    fn call(&self, name: &Binding, args: Vec<Value>, depth: usize) -> Option<Value> {
+        if name.0 == PROGRAM && name.1 == "parseInt" {
+            return Some(Value::Num(parse_int(&args.into_iter().next()?.into_string())));
+        }
+        let name = self.resolve(name);
+        if let Some(decoder) = self.decoders.get(name) {
+            return decoder.decode(self.arrays.get(&decoder.array)?, &args).map(Value::Str);
+        }
+        let wrapper = self.wrappers.get(name)?;
+        let env = wrapper.params.iter().map(String::as_str).zip(args).collect();
+        self.eval(&wrapper.call, &env, depth)
+    }
+
+    /// Apply a startup rotation to its array.
+    fn rotate(&mut self, rotation: &Rotation) {
+        let Some(len) = self.arrays.get(&rotation.array).map(Vec::len) else {
+            return;
+        };
+        let Some(checksum) = &rotation.checksum else {
+            if rotation.target >= 0.0 && rotation.target.fract() == 0.0 {
+                let shift = rotation.target as usize % len;
+                if let Some(array) = self.arrays.get_mut(&rotation.array) {
+                    array.rotate_left(shift);
+                }
+            }
+            return;
+        };
+
+        // Each failed check moves the first string to the end; after `len`
+        // failures the array is back where it started
+        for shift in 0..len {
+            let sum = self.eval(checksum, &HashMap::new(), 0).map(|v| v.to_number());
+            if sum == Some(rotation.target) {
+                trace!("String array {} rotated by {}", rotation.array.1, shift);
+                return;
+            }
+            if let Some(array) = self.arrays.get_mut(&rotation.array) {
+                array.rotate_left(1);
+            }
+        }
+        debug!("No rotation of string array {} matches its checksum", rotation.array.1);
+    }
+}
+
+/// The obfuscator's base64 (its own alphabet), decoded as UTF-8.
+fn base64_decode(encoded: &str) -> Option<String> {
+    let (mut bits, mut len) = (0u32, 0);
+    let mut bytes = Vec::with_capacity(encoded.len() * 3 / 4);
+    // Characters outside the alphabet, and padding, are skipped
+    for value in encoded.chars().filter_map(|c| BASE64_ALPHABET[..64].find(c)) {
+        bits = (bits << 6) | value as u32;
+        len += 6;
+        if len >= 8 {
+            len -= 8;
+            bytes.push((bits >> len) as u8);
+            bits &= (1 << len) - 1;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// RC4 over UTF-16 code units, as the obfuscator applies it with `charCodeAt`.
+fn rc4(data: &str, key: &str) -> Option<String> {
+    let key: Vec<u16> = key.encode_utf16().collect();
+    if key.is_empty() {
+        return None;
+    }
+    let mut state: Vec<usize> = (0..256).collect();
+    let mut j = 0;
+    for i in 0..256 {
+        j = (j + state[i] + key[i % key.len()] as usize) % 256;
+        state.swap(i, j);
+    }
+    let (mut i, mut j) = (0, 0);
+    let decoded: Vec<u16> = data
+        .encode_utf16()
+        .map(|unit| {
+            i = (i + 1) % 256;
+            j = (j + state[i]) % 256;
+            state.swap(i, j);
+            unit ^ state[(state[i] + state[j]) % 256] as u16
+        })
+        .collect();
+    String::from_utf16(&decoded).ok()
+}
+
+/// Array literal of strings only.
+fn string_array(expr: &Expression<'_>) -> Option<Vec<String>> {
+    let Expression::ArrayExpression(array) = expr.without_parentheses() else {
+        return None;
+    };
+    if array.elements.is_empty() {
+        return None;
+    }
+    array
+        .elements
+        .iter()
+        .map(|el| match el.as_expression() {
+            Some(Expression::StringLiteral(lit)) => Some(lit.value.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Finds the names each function body declares, and the string arrays among
+/// them: `var a = [...]`, and functions without parameters returning one
+/// (`function a() { var b = [...]; ... }`).
+#[derive(Default)]
+struct DeclarationCollector {
+    scopes: Scopes,
+    stack: Vec<u32>,
+    /// Parameters of the function whose body is entered next.
+    params: Vec<String>,
+    arrays: HashMap<Binding, Vec<String>>,
+}
+
+impl DeclarationCollector {
+    fn declare(&mut self, name: &str) -> Binding {
+        let scope = self.stack.last().copied().unwrap_or(PROGRAM);
+        self.scopes.entry(scope).or_default().insert(name.to_string());
+        (scope, name.to_string())
+    }
+}
+
+impl<'a> Visit<'a> for DeclarationCollector {
+    fn visit_formal_parameters(&mut self, params: &FormalParameters<'a>) {
+        walk::walk_formal_parameters(self, params);
+        self.params = params
+            .items
+            .iter()
+            .filter_map(|p| p.pattern.get_identifier().map(|name| name.to_string()))
+            .collect();
+    }
+
+    fn visit_function_body(&mut self, body: &FunctionBody<'a>) {
+        self.stack.push(body.span.start);
+        for param in std::mem::take(&mut self.params) {
+            self.declare(&param);
+        }
+        walk::walk_function_body(self, body);
+        self.stack.pop();
+    }
+
+    fn visit_statement(&mut self, stmt: &Statement<'a>) {
+        if let Statement::FunctionDeclaration(func) = stmt {
+            if let Some(id) = &func.id {
+                let binding = self.declare(&id.name);
+                let array = func.body.as_ref().filter(|_| func.params.items.is_empty()).and_then(|body| {
+                    body.statements.iter().find_map(|stmt| match stmt {
+                        Statement::VariableDeclaration(decl) => decl
+                            .declarations
+                            .iter()
+                            .find_map(|d| d.init.as_ref().and_then(string_array)),
+                        _ => None,
+                    })
+                });
+                if let Some(array) = array {
+                    self.arrays.insert(binding, array);
+                }
+            }
+        }
+        walk::walk_statement(self, stmt);
+    }
+
+    fn visit_variable_declarator(&mut self, decl: &VariableDeclarator<'a>) {
+        if let Some(name) = decl.id.get_identifier() {
+            let binding = self.declare(&name);
+            if let Some(array) = decl.init.as_ref().and_then(string_array) {
+                self.arrays.insert(binding, array);
+            }
+        }
+        walk::walk_variable_declarator(self, decl);
+    }
+}
+
+/// Finds the decoders, wrappers, aliases and rotations of known arrays, and
+/// the calls that may be decoded.
+struct Analyzer<'c> {
+    content: &'c str,
+    scope: ScopeStack<'c>,
+    eval: Evaluator,
+    rotations: Vec<Rotation>,
+    sites: Vec<(Range<usize>, Expr)>,
+}
+
+impl Analyzer<'_> {
+    fn function(&mut self, name: &str, func: &Function<'_>) {
+        let params: Vec<String> = func
+            .params
+            .items
+            .iter()
+            .filter_map(|p| p.pattern.get_identifier().map(|id| id.to_string()))
+            .collect();
+        let Some(body) = &func.body else { return };
+        if params.is_empty() {
+            return;
+        }
+        let binding = self.scope.declare(name);
+
+        if let [Statement::ReturnStatement(ret)] = body.statements.as_slice() {
+            if let Some(Expression::CallExpression(call)) = ret.argument.as_ref().map(|e| e.without_parentheses()) {
+                if let Some(call) = Expr::from_call(call, &self.scope.inside(body), 0) {
+                    self.eval.wrappers.insert(binding, Wrapper { params, call });
+                }
+            }
+            return;
+        }
+
+        let text = &self.content[func.span.start as usize..func.span.end as usize];
+        if text.len() > MAX_DECODER_LEN {
+            return;
+        }
+        let mut finder = DecoderFinder {
+            scope: self.scope.clone(),
+            arrays: &self.eval.arrays,
+            param: first_param(&func.params),
+            params: Vec::new(),
+            locals: HashMap::new(),
+            offsets: HashMap::new(),
+            lookups: Vec::new(),
+        };
+        finder.visit_function_body(body);
+        let Some((array, offset)) = finder.decoder() else {
+            return;
+        };
+        let encoding = if !text.contains(BASE64_ALPHABET) {
+            Encoding::Plain
+        } else if params.len() >= 2 && text.contains("0x100") {
+            Encoding::Rc4
+        } else {
+            Encoding::Base64
+        };
+        trace!("String array decoder {} for {} ({:?}, offset {})", name, array.1, encoding, offset);
+        self.eval.decoders.insert(
+            binding,
+            Decoder {
+                array,
+                offset,
+                encoding,
+            },
+        );
+    }
+
+    /// `(function(array, target) { ... })(_0x1a2b, 0x7bcc)`
+    fn rotation(&mut self, call: &CallExpression<'_>) {
+        let Expression::FunctionExpression(func) = call.callee.without_parentheses() else {
+            return;
+        };
+        let [Argument::Identifier(array), Argument::NumericLiteral(target)] = call.arguments.as_slice() else {
+            return;
+        };
+        let array = self.scope.resolve(&array.name);
+        if !self.eval.arrays.contains_key(&array) {
+            return;
+        }
+        let Some(body) = &func.body else { return };
+
+        let mut finder = ChecksumFinder {
+            scope: self.scope.clone(),
+            checksum: None,
+        };
+        finder.visit_function_body(body);
+        let text = &self.content[func.span.start as usize..func.span.end as usize];
+        if finder.checksum.is_none() && text.contains("parseInt") {
+            debug!("Unsupported checksum in rotation of string array {}", array.1);
+            return;
+        }
+        self.rotations.push(Rotation {
+            array,
+            target: target.value,
+            checksum: finder.checksum,
+        });
+    }
+}
+
+impl<'a> Visit<'a> for Analyzer<'_> {
+    fn visit_function_body(&mut self, body: &FunctionBody<'a>) {
+        self.scope.enter(body);
+        walk::walk_function_body(self, body);
+        self.scope.leave();
+    }
+
+    fn visit_statement(&mut self, stmt: &Statement<'a>) {
+        if let Statement::FunctionDeclaration(func) = stmt {
+            if let Some(id) = &func.id {
+                self.function(&id.name, func);
+            }
+        }
+        walk::walk_statement(self, stmt);
+    }
+
+    fn visit_variable_declarator(&mut self, decl: &VariableDeclarator<'a>) {
+        if let Some(name) = decl.id.get_identifier() {
+            match decl.init.as_ref().map(|e| e.without_parentheses()) {
+                Some(Expression::Identifier(target)) => {
+                    let alias = self.scope.declare(&name);
+                    let target = self.scope.resolve(&target.name);
+                    self.eval.aliases.insert(alias, target);
+                }
+                Some(Expression::FunctionExpression(func)) => self.function(&name, func),
+                _ => {}
+            }
+        }
+        walk::walk_variable_declarator(self, decl);
+    }
+
+    fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+        self.rotation(call);
+        if let Some(site) = Expr::from_call(call, &self.scope, 0) {
+            self.sites.push((call.span.start as usize..call.span.end as usize, site));
+        }
+        walk::walk_call_expression(self, call);
+    }
+}
+
+/// Finds the array a decoder indexes with its first parameter less a constant,
+/// `i = i - 0x1f0; return a[i]` or `return a[i - 0x1f0]`. The array may be read
+/// through a local (`var a = _0x1a2b()`), and the lookup may sit in a function
+/// the decoder replaces itself with, indexing with that function's first parameter.
+struct DecoderFinder<'s> {
+    scope: ScopeStack<'s>,
+    arrays: &'s HashMap<Binding, Vec<String>>,
+    /// First parameter of the function whose body is entered next.
+    param: Option<String>,
+    /// First parameter of each function entered, innermost last.
+    params: Vec<Option<Binding>>,
+    /// Locals holding an array.
+    locals: HashMap<Binding, Binding>,
+    /// Constants subtracted from first parameters.
+    offsets: HashMap<Binding, f64>,
+    /// Arrays indexed by a first parameter, with a constant subtracted inline.
+    lookups: Vec<(Binding, Binding, Option<f64>)>,
+}
+
+impl DecoderFinder<'_> {
+    /// The array and offset of the first lookup with a constant subtracted.
+    fn decoder(&self) -> Option<(Binding, f64)> {
+        self.lookups.iter().find_map(|(array, param, inline)| {
+            let offset = inline.or_else(|| self.offsets.get(param).copied())?;
+            Some((array.clone(), offset))
+        })
+    }
+
+    fn first_param(&self) -> Option<&Binding> {
+        self.params.last()?.as_ref()
+    }
+
+    fn is_first_param(&self, expr: &Expression<'_>) -> bool {
+        match expr.without_parentheses() {
+            Expression::Identifier(id) => self.first_param() == Some(&self.scope.resolve(&id.name)),
+            _ => false,
+        }
+    }
+
+    /// The array `expr` names, directly or through a local.
+    fn array(&self, expr: &Expression<'_>) -> Option<Binding> {
+        let Expression::Identifier(id) = expr.without_parentheses() else {
+            return None;
+        };
+        let binding = self.scope.resolve(&id.name);
+        if self.arrays.contains_key(&binding) {
+            return Some(binding);
+        }
+        self.locals.get(&binding).cloned()
+    }
+
+    fn constant(&self, expr: &Expression<'_>) -> Option<f64> {
+        let expr = Expr::from_ast(expr, &self.scope, 0)?;
+        match Evaluator::default().eval(&expr, &HashMap::new(), 0)? {
+            Value::Num(n) => Some(n),
+            Value::Str(_) => None,
+        }
+    }
+}
+
+impl<'a> Visit<'a> for DecoderFinder<'_> {
+    fn visit_formal_parameters(&mut self, params: &FormalParameters<'a>) {
+        walk::walk_formal_parameters(self, params);
+        self.param = first_param(params);
+    }
+
+    fn visit_function_body(&mut self, body: &FunctionBody<'a>) {
+        self.scope.enter(body);
+        let param = self.param.take().map(|name| self.scope.declare(&name));
+        self.params.push(param);
+        walk::walk_function_body(self, body);
+        self.params.pop();
+        self.scope.leave();
+    }
+
+    fn visit_variable_declarator(&mut self, decl: &VariableDeclarator<'a>) {
+        if let (Some(name), Some(init)) = (decl.id.get_identifier(), &decl.init) {
+            let array = match init.without_parentheses() {
+                Expression::CallExpression(call) if call.arguments.is_empty() => self.array(&call.callee),
+                init => self.array(init),
+            };
+            if let Some(array) = array {
+                self.locals.insert(self.scope.declare(&name), array);
+            }
+        }
+        walk::walk_variable_declarator(self, decl);
+    }
+
+    fn visit_assignment_expression(&mut self, expr: &AssignmentExpression<'a>) {
+        if let AssignmentTarget::AssignmentTargetIdentifier(target) = &expr.left {
+            let binding = self.scope.resolve(&target.name);
+            if self.first_param() == Some(&binding) {
+                let subtrahend = match (expr.operator, expr.right.without_parentheses()) {
+                    (AssignmentOperator::Subtraction, right) => Some(right),
+                    (AssignmentOperator::Assign, Expression::BinaryExpression(binary))
+                        if binary.operator == BinaryOperator::Subtraction
+                            && matches!(&binary.left, Expression::Identifier(id) if id.name == target.name) =>
+                    {
+                        Some(&binary.right)
+                    }
+                    _ => None,
+                };
+                if let Some(offset) = subtrahend.and_then(|right| self.constant(right)) {
+                    self.offsets.insert(binding, offset);
+                }
+            }
+        }
+        walk::walk_assignment_expression(self, expr);
+    }
+
+    fn visit_computed_member_expression(&mut self, member: &ComputedMemberExpression<'a>) {
+        if let (Some(array), Some(param)) = (self.array(&member.object), self.first_param()) {
+            let inline = match member.expression.without_parentheses() {
+                index if self.is_first_param(index) => Some(None),
+                Expression::BinaryExpression(binary)
+                    if binary.operator == BinaryOperator::Subtraction && self.is_first_param(&binary.left) =>
+                {
+                    self.constant(&binary.right).map(Some)
+                }
+                _ => None,
+            };
+            if let Some(inline) = inline {
+                self.lookups.push((array, param.clone(), inline));
+            }
+        }
+        walk::walk_computed_member_expression(self, member);
+    }
+}
+
+/// Finds the `parseInt` checksum a rotation loop compares with its target.
+struct ChecksumFinder<'s> {
+    scope: ScopeStack<'s>,
+    checksum: Option<Expr>,
+}
+
+impl<'a> Visit<'a> for ChecksumFinder<'_> {
+    fn visit_function_body(&mut self, body: &FunctionBody<'a>) {
+        self.scope.enter(body);
+        walk::walk_function_body(self, body);
+        self.scope.leave();
+    }
+
+    fn visit_variable_declarator(&mut self, decl: &VariableDeclarator<'a>) {
+        if self.checksum.is_none() {
+            self.checksum = decl
+                .init
+                .as_ref()
+                .and_then(|init| Expr::from_ast(init, &self.scope, 0))
+                .filter(|expr| expr.calls("parseInt"));
+        }
+        walk::walk_variable_declarator(self, decl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// javascript-obfuscator output with a checksum-rotated array, RC4
+    /// encoding, a function wrapper and a variable alias.
+    const RC4_FIXTURE: &str = r#"function _0x1a2b(){var _0x3c4d=['W4/cPrtdSwfo','W4ldR8kA','WPNdPJNcS8kxWPhdLKS','WQFcGxzeqSoSjSkSDCoPWP8cW4rzuqlcVbi','WP/cKX/cVSoFWP7dJG','WPxdMmokqhGz'];_0x1a2b=function(){return _0x3c4d;};return _0x1a2b();}
+function _0x5e6f(_0x7a8b,_0x9c0d){var _0x2e1f=_0x1a2b();return _0x5e6f=function(_0x4f3a,_0x1b2c){_0x4f3a=_0x4f3a-0x1f0;var _0x5d6e=_0x2e1f[_0x4f3a];if(_0x5e6f['tGvQmW']===undefined){var _0x3a4b=function(_0x6c7d){var _0x1e2f='abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789+/=';var _0x3b4c='',_0x5d6f='';for(var _0x7e8f=0x0,_0x9a0b,_0x1c2d,_0x3e4f=0x0;_0x1c2d=_0x6c7d['charAt'](_0x3e4f++);~_0x1c2d&&(_0x9a0b=_0x7e8f%0x4?_0x9a0b*0x40+_0x1c2d:_0x1c2d,_0x7e8f++%0x4)?_0x3b4c+=String['fromCharCode'](0xff&_0x9a0b>>(-0x2*_0x7e8f&0x6)):0x0){_0x1c2d=_0x1e2f['indexOf'](_0x1c2d);}for(var _0x5a6b=0x0,_0x7c8d=_0x3b4c['length'];_0x5a6b<_0x7c8d;_0x5a6b++){_0x5d6f+='%'+('00'+_0x3b4c['charCodeAt'](_0x5a6b)['toString'](0x10))['slice'](-0x2);}return decodeURIComponent(_0x5d6f);};var _0x8e9f=function(_0x1f2a,_0x3b4d){var _0x5c6e=[],_0x7d8f=0x0,_0x9e0a,_0x2b3c='';_0x1f2a=_0x3a4b(_0x1f2a);var _0x4d5e;for(_0x4d5e=0x0;_0x4d5e<0x100;_0x4d5e++){_0x5c6e[_0x4d5e]=_0x4d5e;}for(_0x4d5e=0x0;_0x4d5e<0x100;_0x4d5e++){_0x7d8f=(_0x7d8f+_0x5c6e[_0x4d5e]+_0x3b4d['charCodeAt'](_0x4d5e%_0x3b4d['length']))%0x100;_0x9e0a=_0x5c6e[_0x4d5e];_0x5c6e[_0x4d5e]=_0x5c6e[_0x7d8f];_0x5c6e[_0x7d8f]=_0x9e0a;}_0x4d5e=0x0;_0x7d8f=0x0;for(var _0x6f7a=0x0;_0x6f7a<_0x1f2a['length'];_0x6f7a++){_0x4d5e=(_0x4d5e+0x1)%0x100;_0x7d8f=(_0x7d8f+_0x5c6e[_0x4d5e])%0x100;_0x9e0a=_0x5c6e[_0x4d5e];_0x5c6e[_0x4d5e]=_0x5c6e[_0x7d8f];_0x5c6e[_0x7d8f]=_0x9e0a;_0x2b3c+=String['fromCharCode'](_0x1f2a['charCodeAt'](_0x6f7a)^_0x5c6e[(_0x5c6e[_0x4d5e]+_0x5c6e[_0x7d8f])%0x100]);}return _0x2b3c;};_0x5e6f['xKpLmN']=_0x8e9f;_0x5e6f['tGvQmW']=!![];}_0x5d6e=_0x5e6f['xKpLmN'](_0x5d6e,_0x1b2c);return _0x5d6e;},_0x5e6f(_0x7a8b,_0x9c0d);}
+(function(_0x4e5f,_0x6a7b){var _0x8c9d=_0x5e6f,_0x1d2e=_0x4e5f();while(!![]){try{var _0x3f4a=parseInt(_0x8c9d(0x1f0,'k1'))/0x1+-parseInt(_0x8c9d(0x1f2,'k2'))/0x2+parseInt(_0x8c9d(0x1f4,'k3'))/0x3;if(_0x3f4a===_0x6a7b)break;else _0x1d2e['push'](_0x1d2e['shift']());}catch(_0x5b6c){_0x1d2e['push'](_0x1d2e['shift']());}}}(_0x1a2b,0x7bcc));
+function _0x2c3d(_0x4a5b,_0x6c7d){return _0x5e6f(_0x4a5b- -0x1ea,_0x6c7d);}
+var _0x7e8f=_0x5e6f;var pay=require(_0x2c3d(0x7,'Ab1x')),_=require(_0x7e8f(0x1f3,'zz9'));console[_0x7e8f(0x1f5,'q')](pay,_);"#;
+
+    #[test]
+    fn test_decode_rotated_array() {
+        // Older output: a plain array rotated a fixed number of times
+        let content = "var _0x1234=['log','@acme/auth-client','lodash'];(function(_0x2d8f05,_0x4b81bb){var _0x4d74cb=function(_0x32719f){while(--_0x32719f){_0x2d8f05['push'](_0x2d8f05['shift']());}};_0x4d74cb(++_0x4b81bb);}(_0x1234,0x1));var _0x5678=function(_0x2d8f05,_0x4b81bb){_0x2d8f05=_0x2d8f05-0x0;var _0x4d74cb=_0x1234[_0x2d8f05];return _0x4d74cb;};var a=require(_0x5678('0x0'));console[_0x5678('0x2')]('hi');";
+        let decoded = decode(content).unwrap();
+        assert!(decoded.text.contains(r#"require("@acme/auth-client")"#));
+        assert!(decoded.text.contains(r#"console["log"]"#));
+
+        let start = decoded.text.find(r#""@acme/auth-client""#).unwrap();
+        let original = decoded.original_span(start..start + 19).unwrap();
+        assert_eq!(&content[original], "_0x5678('0x0')");
+        assert_eq!(decoded.original_span(0..3), None);
+    }
+
+    #[test]
+    fn test_decode_rc4_checksum() {
+        let decoded = decode(RC4_FIXTURE).unwrap();
+        assert!(decoded.text.contains(r#"require("@acme/payments-sdk")"#));
+        assert!(decoded.text.contains(r#"require("lodash")"#));
+        assert!(decoded.text.contains(r#"console["log"]"#));
+    }
+
+    #[test]
+    fn test_plain_code_is_left_alone() {
+        assert!(decode("const names = ['a', 'b']; function pick(i) { return names[i]; } pick(0);").is_none());
+        assert!(decode("import x from 'lodash';").is_none());
+    }
+
+    #[test]
+    fn test_decoder_shape_is_required() {
+        // `a` is a string array, but neither function indexes it with its first parameter less a constant
+        let content = "var a=['lodash','react'];function f(b){b=b-1;return c[b]+a.length;}function g(e){var t=e-0x1;return a[t]+a.length;}require(f(1));require(g(1));";
+        assert!(decode(content).is_none());
+    }
+
+    #[test]
+    fn test_shadowed_names_are_not_decoded() {
+        let content = "var _0x1234=['lodash','@acme/ui'];var _0x5678=function(a,b){a=a-0x0;var c=_0x1234[a];return c;};var d=_0x5678;\
+            function h(_0x5678){return require(_0x5678('0x0'));}\
+            function k(){var d=function(x){return x;};return require(d('0x1'));}\
+            function m(){var _0x1234=['react','vue'];return require(d('0x1'));}";
+        let decoded = decode(content).unwrap();
+        assert_eq!(decoded.decoded_calls(), 1);
+        assert!(decoded.text.contains(r#"require(_0x5678('0x0'))"#));
+        assert!(decoded.text.contains(r#"require(d('0x1'));}function m"#));
+        assert!(decoded.text.ends_with(r#"require("@acme/ui");}"#));
+    }
+
+    #[test]
+    fn test_parse_int() {
+        assert_eq!(parse_int("29340Xyz"), 29340.0);
+        assert_eq!(parse_int(" -12ab"), -12.0);
+        assert_eq!(parse_int("0x1f"), 31.0);
+        assert!(parse_int("log").is_nan());
+    }
+}
@@ -50,12 +50,15 @@ impl WebpackParser {
                             continue;
                         }
 
-                        packages.insert(Package {
-                            name: pkg,
-                            extraction_method: ExtractionMethod::WebpackChunk,
-                            source_url: source_url.to_string(),
-                            confidence: Confidence::High,
-                        });
+                        packages.insert(
+                            Package::new(
+                                pkg,
+                                ExtractionMethod::WebpackChunk,
+                                source_url.to_string(),
+                                Confidence::High,
+                            )
+                            .with_span(path.range()),
+                        );
                     }
                 }
             }
@@ -72,12 +75,15 @@ impl WebpackParser {
                             continue;
                         }
 
-                        packages.insert(Package {
-                            name: pkg,
-                            extraction_method: ExtractionMethod::WebpackChunk,
-                            source_url: source_url.to_string(),
-                            confidence: Confidence::High,
-                        });
+                        packages.insert(
+                            Package::new(
+                                pkg,
+                                ExtractionMethod::WebpackChunk,
+                                source_url.to_string(),
+                                Confidence::High,
+                            )
+                            .with_span(pkg_path.range()),
+                        );
                     }
                 }
             }
@@ -95,12 +101,15 @@ impl WebpackParser {
                             continue;
                         }
 
-                        packages.insert(Package {
-                            name: pkg,
-                            extraction_method: ExtractionMethod::WebpackChunk,
-                            source_url: source_url.to_string(),
-                            confidence: Confidence::High,
-                        });
+                        packages.insert(
+                            Package::new(
+                                pkg,
+                                ExtractionMethod::WebpackChunk,
+                                source_url.to_string(),
+                                Confidence::High,
+                            )
+                            .with_span(path.range()),
+                        );
                     }
                 }
             }
@@ -118,12 +127,15 @@ impl WebpackParser {
                             continue;
                         }
 
-                        packages.insert(Package {
-                            name: normalized,
-                            extraction_method: ExtractionMethod::WebpackChunk,
-                            source_url: source_url.to_string(),
-                            confidence: Confidence::Medium,
-                        });
+                        packages.insert(
+                            Package::new(
+                                normalized,
+                                ExtractionMethod::WebpackChunk,
+                                source_url.to_string(),
+                                Confidence::Medium,
+                            )
+                            .with_span(pkg.range()),
+                        );
                     }
                 }
             }
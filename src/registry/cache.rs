@@ -61,6 +61,7 @@ mod tests {
         let result = NpmCheckResult::Exists {
             name: "lodash".to_string(),
             latest_version: Some("4.17.21".to_string()),
+            published_versions: vec!["4.17.21".to_string()],
         };
 
         cache.set("lodash", result);
@@ -1,10 +1,15 @@
 //! npm registry checker for verifying package existence.
 
+use crate::audit::{AuditComponent, AuditEntry, AuditLog};
+use crate::discovery::apply_resolve_overrides;
 use crate::registry::cache::RegistryCache;
-use crate::types::{NpmCheckResult, Package, Result};
+use crate::types::{NpmCheckResult, Package, ResolveOverride, Result};
 use governor::{Quota, RateLimiter};
+use reqwest::header::HeaderMap;
 use reqwest::Client;
+use serde::de::IgnoredAny;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::num::NonZeroU32;
 use std::sync::Arc;
 use std::time::Duration;
@@ -17,6 +22,8 @@ struct NpmPackageInfo {
     name: String,
     #[serde(rename = "dist-tags")]
     dist_tags: Option<DistTags>,
+    #[serde(default)]
+    versions: HashMap<String, IgnoredAny>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -46,35 +53,69 @@ struct NpmSearchPackage {
 /// Checker for verifying packages against npm registry.
 pub struct NpmChecker {
     client: Client,
+    timeout_secs: u64,
     cache: RegistryCache,
     rate_limiter: Arc<RateLimiter<governor::state::NotKeyed, governor::state::InMemoryState, governor::clock::DefaultClock>>,
     registry_url: String,
+    audit_log: Option<Arc<AuditLog>>,
+    headers: HeaderMap,
 }
 
 impl NpmChecker {
     /// Create a new npm checker.
     pub fn new(timeout_secs: u64, rate_limit: u32, cache_ttl_secs: u64) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(timeout_secs))
-            .user_agent("depfused/0.1")
-            .http1_only() // Force HTTP/1.1 to avoid HTTP/2 stream limit issues
-            .pool_max_idle_per_host(10)
-            .pool_idle_timeout(Duration::from_secs(30))
-            .build()?;
+        let client = Self::build_client(timeout_secs, &[])?;
 
         let quota = Quota::per_second(NonZeroU32::new(rate_limit).unwrap_or(NonZeroU32::new(5).unwrap()));
         let rate_limiter = Arc::new(RateLimiter::direct(quota));
 
         Ok(Self {
             client,
+            timeout_secs,
             cache: RegistryCache::new(cache_ttl_secs),
             rate_limiter,
             registry_url: "https://registry.npmjs.org".to_string(),
+            audit_log: None,
+            headers: HeaderMap::new(),
         })
     }
 
+    /// Pin hostnames to specific addresses (`--resolve`).
+    pub fn with_resolve_overrides(mut self, overrides: &[ResolveOverride]) -> Result<Self> {
+        if !overrides.is_empty() {
+            self.client = Self::build_client(self.timeout_secs, overrides)?;
+        }
+        Ok(self)
+    }
+
+    fn build_client(timeout_secs: u64, overrides: &[ResolveOverride]) -> Result<Client> {
+        let client = apply_resolve_overrides(Client::builder(), overrides)
+            .timeout(Duration::from_secs(timeout_secs))
+            .user_agent("depfused/0.1")
+            .http1_only() // Force HTTP/1.1 to avoid HTTP/2 stream limit issues
+            .pool_max_idle_per_host(10)
+            .pool_idle_timeout(Duration::from_secs(30))
+            .build()?;
+
+        Ok(client)
+    }
+
+    /// Send extra static headers with every registry request.
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Record every registry request in the given audit log.
+    pub fn with_audit_log(mut self, audit_log: Option<Arc<AuditLog>>) -> Self {
+        self.audit_log = audit_log;
+        self
+    }
+
     /// Check if a package exists on npm.
-    pub async fn check_package(&self, package: &Package) -> NpmCheckResult {
+    ///
+    /// `target` is the scan target the package was found on (used for auditing).
+    pub async fn check_package(&self, package: &Package, target: &str) -> NpmCheckResult {
         // Check cache first
         if let Some(cached) = self.cache.get(&package.name) {
             trace!("Cache hit for {}", package.name);
@@ -84,7 +125,7 @@ impl NpmChecker {
         // Rate limit
         self.rate_limiter.until_ready().await;
 
-        let result = self.do_check(&package.name).await;
+        let result = self.do_check(&package.name, target).await;
 
         // Cache the result
         self.cache.set(&package.name, result.clone());
@@ -93,30 +134,61 @@ impl NpmChecker {
     }
 
     /// Perform the actual npm registry check.
-    async fn do_check(&self, package_name: &str) -> NpmCheckResult {
+    async fn do_check(&self, package_name: &str, target: &str) -> NpmCheckResult {
         // Handle scoped packages differently
         if package_name.starts_with('@') {
-            return self.check_scoped_package(package_name).await;
+            return self.check_scoped_package(package_name, target).await;
         }
 
-        self.check_regular_package(package_name).await
+        self.check_regular_package(package_name, target).await
+    }
+
+    /// GET a registry URL and read the whole body, recording it in the audit log.
+    async fn get(&self, url: &str, target: &str) -> reqwest::Result<(reqwest::StatusCode, String)> {
+        let entry = || AuditEntry::new(AuditComponent::NpmChecker, "GET", url).with_target(target);
+
+        let result = match self.client.get(url).headers(self.headers.clone()).send().await {
+            Ok(response) => {
+                let status = response.status();
+                response.text().await.map(|body| (status, body))
+            }
+            Err(e) => Err(e),
+        };
+
+        if let Some(ref audit_log) = self.audit_log {
+            audit_log.record(match &result {
+                Ok((status, body)) => entry()
+                    .with_status(status.as_u16())
+                    .with_bytes(body.len() as u64),
+                Err(e) => {
+                    let entry = entry().with_error(e);
+                    match e.status() {
+                        Some(status) => entry.with_status(status.as_u16()),
+                        None => entry,
+                    }
+                }
+            });
+        }
+
+        result
     }
 
     /// Check a regular (non-scoped) package.
-    async fn check_regular_package(&self, package_name: &str) -> NpmCheckResult {
+    async fn check_regular_package(&self, package_name: &str, target: &str) -> NpmCheckResult {
         let url = format!("{}/{}", self.registry_url, urlencoding::encode(package_name));
         trace!("Checking npm: {}", url);
 
-        match self.client.get(&url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
+        match self.get(&url, target).await {
+            Ok((status, body)) => {
+                if status.is_success() {
                     // Package exists
-                    match response.json::<NpmPackageInfo>().await {
+                    match serde_json::from_str::<NpmPackageInfo>(&body) {
                         Ok(info) => {
                             debug!("Package exists: {}", package_name);
                             NpmCheckResult::Exists {
                                 name: package_name.to_string(),
                                 latest_version: info.dist_tags.and_then(|dt| dt.latest),
+                                published_versions: info.versions.into_keys().collect(),
                             }
                         }
                         Err(e) => {
@@ -124,10 +196,11 @@ impl NpmChecker {
                             NpmCheckResult::Exists {
                                 name: package_name.to_string(),
                                 latest_version: None,
+                                published_versions: Vec::new(),
                             }
                         }
                     }
-                } else if response.status().as_u16() == 404 {
+                } else if status.as_u16() == 404 {
                     // Package doesn't exist - potential vulnerability
                     debug!("Package NOT FOUND: {}", package_name);
                     NpmCheckResult::NotFound {
@@ -136,7 +209,7 @@ impl NpmChecker {
                 } else {
                     NpmCheckResult::Error {
                         name: package_name.to_string(),
-                        error: format!("HTTP {}", response.status()),
+                        error: format!("HTTP {}", status),
                     }
                 }
             }
@@ -148,7 +221,7 @@ impl NpmChecker {
     }
 
     /// Check a scoped package (@scope/name).
-    async fn check_scoped_package(&self, package_name: &str) -> NpmCheckResult {
+    async fn check_scoped_package(&self, package_name: &str, target: &str) -> NpmCheckResult {
         // First check if the package itself exists
         let url = format!(
             "{}/{}",
@@ -157,30 +230,32 @@ impl NpmChecker {
         );
         trace!("Checking scoped npm package: {}", url);
 
-        match self.client.get(&url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
+        match self.get(&url, target).await {
+            Ok((status, body)) => {
+                if status.is_success() {
                     // Package exists
-                    match response.json::<NpmPackageInfo>().await {
+                    match serde_json::from_str::<NpmPackageInfo>(&body) {
                         Ok(info) => {
                             debug!("Scoped package exists: {}", package_name);
                             NpmCheckResult::Exists {
                                 name: package_name.to_string(),
                                 latest_version: info.dist_tags.and_then(|dt| dt.latest),
+                                published_versions: info.versions.into_keys().collect(),
                             }
                         }
                         Err(_) => NpmCheckResult::Exists {
                             name: package_name.to_string(),
                             latest_version: None,
+                            published_versions: Vec::new(),
                         },
                     }
-                } else if response.status().as_u16() == 404 {
+                } else if status.as_u16() == 404 {
                     // Package doesn't exist - check if scope is claimed
-                    self.check_scope_ownership(package_name).await
+                    self.check_scope_ownership(package_name, target).await
                 } else {
                     NpmCheckResult::Error {
                         name: package_name.to_string(),
-                        error: format!("HTTP {}", response.status()),
+                        error: format!("HTTP {}", status),
                     }
                 }
             }
@@ -202,7 +277,7 @@ impl NpmChecker {
     /// 3. Packages with that scope exist - searches /-/v1/search
     ///
     /// Only if ALL checks fail is the scope unclaimed and exploitable.
-    async fn check_scope_ownership(&self, package_name: &str) -> NpmCheckResult {
+    async fn check_scope_ownership(&self, package_name: &str, target: &str) -> NpmCheckResult {
         // Extract scope from package name
         let scope = package_name.split('/').next().unwrap_or("");
 
@@ -221,23 +296,21 @@ impl NpmChecker {
         );
         trace!("Checking if user exists: {}", user_url);
 
-        match self.client.get(&user_url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
+        match self.get(&user_url, target).await {
+            Ok((status, text)) => {
+                if status.is_success() {
                     // Try to parse the response
-                    if let Ok(text) = response.text().await {
-                        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) {
-                            // Check if user exists: ok=true, name field, or _id field
-                            let has_ok = json.get("ok").and_then(|v| v.as_bool()).unwrap_or(false);
-                            let has_name = json.get("name").is_some();
-                            let has_id = json.get("_id").is_some();
-
-                            if has_ok || has_name || has_id {
-                                debug!("Scope claimed by USER '{}': {}", scope_name, package_name);
-                                return NpmCheckResult::NotFound {
-                                    name: package_name.to_string(),
-                                };
-                            }
+                    if let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) {
+                        // Check if user exists: ok=true, name field, or _id field
+                        let has_ok = json.get("ok").and_then(|v| v.as_bool()).unwrap_or(false);
+                        let has_name = json.get("name").is_some();
+                        let has_id = json.get("_id").is_some();
+
+                        if has_ok || has_name || has_id {
+                            debug!("Scope claimed by USER '{}': {}", scope_name, package_name);
+                            return NpmCheckResult::NotFound {
+                                name: package_name.to_string(),
+                            };
                         }
                     }
                 }
@@ -252,23 +325,21 @@ impl NpmChecker {
         );
         trace!("Checking if org exists: {}", org_url);
 
-        match self.client.get(&org_url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    if let Ok(text) = response.text().await {
-                        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) {
-                            // Check if response contains an error field
-                            if let Some(error) = json.get("error") {
-                                // Response like {"error": "Scope not found"} means unclaimed
-                                trace!("Org API returned error: {}", error);
-                            } else {
-                                // No error field means org exists (even if empty object {})
-                                // Orgs with 0 packages return {} (empty object)
-                                debug!("Scope claimed by ORG '{}': {}", scope_name, package_name);
-                                return NpmCheckResult::NotFound {
-                                    name: package_name.to_string(),
-                                };
-                            }
+        match self.get(&org_url, target).await {
+            Ok((status, text)) => {
+                if status.is_success() {
+                    if let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) {
+                        // Check if response contains an error field
+                        if let Some(error) = json.get("error") {
+                            // Response like {"error": "Scope not found"} means unclaimed
+                            trace!("Org API returned error: {}", error);
+                        } else {
+                            // No error field means org exists (even if empty object {})
+                            // Orgs with 0 packages return {} (empty object)
+                            debug!("Scope claimed by ORG '{}': {}", scope_name, package_name);
+                            return NpmCheckResult::NotFound {
+                                name: package_name.to_string(),
+                            };
                         }
                     }
                 }
@@ -287,10 +358,10 @@ impl NpmChecker {
         // Build the scope prefix to verify results actually belong to this scope
         let scope_prefix = format!("{}/", scope); // e.g. "@myscope/"
 
-        match self.client.get(&search_url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    match response.json::<NpmSearchResponse>().await {
+        match self.get(&search_url, target).await {
+            Ok((status, body)) => {
+                if status.is_success() {
+                    match serde_json::from_str::<NpmSearchResponse>(&body) {
                         Ok(search_result) => {
                             // Verify at least one result actually belongs to this scope
                             // npm search is text-based, so "@internal" can match unrelated packages
@@ -328,12 +399,22 @@ mod tests {
     use crate::types::{Confidence, ExtractionMethod};
 
     fn make_test_package(name: &str) -> Package {
-        Package {
-            name: name.to_string(),
-            extraction_method: ExtractionMethod::Import,
-            source_url: "test.js".to_string(),
-            confidence: Confidence::High,
-        }
+        Package::new(
+            name.to_string(),
+            ExtractionMethod::Import,
+            "test.js".to_string(),
+            Confidence::High,
+        )
+    }
+
+    #[test]
+    fn test_parse_published_versions() {
+        let body = r#"{"name":"pkg","dist-tags":{"latest":"1.0.0","next":"2.0.0-rc.1"},"versions":{"1.0.0":{"name":"pkg"},"2.0.0-rc.1":{}}}"#;
+        let info: NpmPackageInfo = serde_json::from_str(body).unwrap();
+        let mut versions: Vec<_> = info.versions.into_keys().collect();
+        versions.sort();
+        assert_eq!(versions, ["1.0.0", "2.0.0-rc.1"]);
+        assert_eq!(info.dist_tags.and_then(|dt| dt.latest).as_deref(), Some("1.0.0"));
     }
 
     #[tokio::test]
@@ -341,7 +422,7 @@ mod tests {
         let checker = NpmChecker::new(10, 5, 60).unwrap();
         let package = make_test_package("lodash");
 
-        let result = checker.check_package(&package).await;
+        let result = checker.check_package(&package, "https://example.com").await;
 
         match result {
             NpmCheckResult::Exists { name, .. } => {
@@ -357,7 +438,7 @@ mod tests {
         // Use a very unlikely package name
         let package = make_test_package("this-package-definitely-does-not-exist-12345xyz");
 
-        let result = checker.check_package(&package).await;
+        let result = checker.check_package(&package, "https://example.com").await;
 
         match result {
             NpmCheckResult::NotFound { .. } | NpmCheckResult::Error { .. } => {
@@ -1,17 +1,25 @@
 //! Main scanner orchestrating all components.
 
+use crate::audit::AuditLog;
 use crate::config::ScanConfig;
-use crate::discovery::{BrowserCapture, JsFetcher, SourceMapProber};
-use crate::notify::{ConsoleOutput, TelegramNotifier};
-use crate::parser::{
-    is_likely_false_positive, is_likely_internal, AstParser, BundlerParser, Deobfuscator,
-    SourceMapParser, WebpackParser,
+use crate::export::SourceExporter;
+use crate::extract::{builtin_extractors, ExtractContext, Extractors};
+use crate::discovery::{
+    header_map, BrowserCapture, FetchOutcome, HostLimiter, JsFetcher, PoolSize, ResourceBlocker,
+    SourceMapProber,
 };
+use crate::notify::{ConsoleOutput, TelegramNotifier};
+use crate::parser::explain::{self, FilterLog};
+use crate::parser::rules::{self, FilterRules, RuleAction};
+use crate::parser::{false_positive_reason, is_likely_internal, BundlerParser, LineIndex};
 use crate::registry::NpmChecker;
+use crate::scope::ScopeGuard;
+use crate::scoring;
 use crate::types::{
-    Confidence, ExtractionMethod, Finding, JsFile, JsSource, NpmCheckResult, Package, Result,
-    ScanResult, Severity,
+    version_is_higher, Confidence, ExtractionMethod, Finding, JsFile, JsSource, NpmCheckResult,
+    Occurrence, Package, Result, ScanResult, Severity,
 };
+use dashmap::DashMap;
 use futures::stream::{self, StreamExt};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -23,36 +31,64 @@ pub struct Scanner {
     config: ScanConfig,
     fetcher: Arc<JsFetcher>,
     npm_checker: Arc<NpmChecker>,
-    ast_parser: AstParser,
-    sourcemap_parser: SourceMapParser,
-    webpack_parser: WebpackParser,
-    bundler_parser: BundlerParser,
-    deobfuscator: Deobfuscator,
-    sourcemap_prober: SourceMapProber,
+    extractors: Extractors,
     browser_capture: BrowserCapture,
     console: ConsoleOutput,
     telegram: Option<TelegramNotifier>,
+    scope: Option<Arc<ScopeGuard>>,
+    audit_log: Option<Arc<AuditLog>>,
+    resource_blocker: Option<Arc<ResourceBlocker>>,
+    source_exporter: Option<Arc<SourceExporter>>,
+    /// Per-target logs of dropped candidates, with `--explain-filters`.
+    filter_logs: DashMap<String, Arc<FilterLog>>,
+    /// User filter rules, current while this scanner extracts and deduplicates.
+    filter_rules: Option<Arc<FilterRules>>,
 }
 
 impl Scanner {
     /// Create a new scanner with the given configuration.
     pub async fn new(config: ScanConfig) -> Result<Self> {
-        let http_config = config.http_config();
-
-        let fetcher = Arc::new(JsFetcher::new(http_config.clone(), config.rate_limit)?);
-
-        let npm_checker = Arc::new(NpmChecker::new(
-            config.timeout,
+        let scope = config.load_scope()?;
+        let mut http_config = config.http_config();
+        http_config.scope = scope.clone();
+        let audit_log = config.open_audit_log()?;
+        let source_exporter = config.source_exporter()?;
+        let filter_rules = config.load_filter_rules()?.map(Arc::new);
+        http_config.audit_log = audit_log.clone();
+
+        // One limiter for all outbound JS/map traffic, keyed by host
+        let host_limiter = Arc::new(HostLimiter::new(
             config.rate_limit,
-            3600, // 1 hour cache TTL
-        )?);
+            http_config.max_in_flight_per_host,
+        ));
 
-        let sourcemap_prober =
-            SourceMapProber::new(config.timeout, &http_config.user_agent)?;
+        let fetcher = Arc::new(JsFetcher::new(http_config.clone(), host_limiter.clone())?);
 
-        // Resolve Chrome executable: explicit flag > managed install > auto-download
+        let registry_headers = if config.no_registry_headers {
+            Default::default()
+        } else {
+            header_map(&http_config.headers)?
+        };
+        let npm_checker = Arc::new(
+            NpmChecker::new(
+                config.timeout,
+                config.rate_limit,
+                3600, // 1 hour cache TTL
+            )?
+            .with_resolve_overrides(&http_config.resolve)?
+            .with_headers(registry_headers)
+            .with_audit_log(audit_log.clone()),
+        );
+
+        let sourcemap_prober = SourceMapProber::new(&http_config, host_limiter)?
+            .with_templates(config.map_template.clone());
+
+        // Resolve Chrome executable: explicit flag > managed install > auto-download.
+        // A JS-URL-only scan or a remote browser never launches Chrome, so skip the download entirely.
         let chrome_exe = match config.chrome_path.clone().or_else(crate::browser::resolve_chrome_executable) {
             Some(path) => Some(path),
+            None if !config.has_page_targets() && config.js_urls.is_some() => None,
+            None if config.cdp_url.is_some() => None,
             None => {
                 // No Chrome found anywhere — download now so parallel host groups don't race
                 tracing::warn!(
@@ -62,38 +98,106 @@ impl Scanner {
             }
         };
 
+        let resource_blocker = config.resource_blocker()?;
         let browser_capture = BrowserCapture::new(config.timeout, true)
             .with_fast_mode(config.fast)
-            .with_chrome_executable(chrome_exe);
+            .with_chrome_executable(chrome_exe)
+            .with_scope(scope.clone())
+            .with_audit_log(audit_log.clone())
+            .with_extra_headers(http_config.headers.clone())
+            .with_resolve(http_config.resolve.clone())
+            .with_cdp_url(config.cdp_url.clone())
+            .with_resource_blocker(resource_blocker.clone())
+            .with_profiles(config.profile.clone());
+        for (i, entry) in config.resolve.iter().enumerate() {
+            let conflict = config.resolve[..i]
+                .iter()
+                .find(|earlier| earlier.host == entry.host && earlier.ip != entry.ip);
+            if let Some(earlier) = conflict {
+                tracing::warn!(
+                    "--resolve maps {} on every port; {}:{} and {}:{} conflict, the browser uses {}",
+                    entry.host, earlier.port, earlier.ip, entry.port, entry.ip, earlier.ip
+                );
+            }
+        }
+        if config.cdp_url.is_some() && !config.resolve.is_empty() {
+            tracing::warn!(
+                "--resolve cannot change host resolution of a remote browser (--cdp-url); it only applies to depfused's own requests"
+            );
+        }
 
         let console = ConsoleOutput::new(config.verbose, config.json, config.quiet);
 
-        let include_low = config.min_confidence == "low";
+        let bundler_parser = BundlerParser::new().with_rules(config.load_bundler_rules()?);
 
         Ok(Self {
             config,
             fetcher,
             npm_checker,
-            ast_parser: AstParser::new(include_low),
-            sourcemap_parser: SourceMapParser::new(),
-            webpack_parser: WebpackParser::new(),
-            bundler_parser: BundlerParser::new(),
-            deobfuscator: Deobfuscator::new(),
-            sourcemap_prober,
+            // Low-rated matches are always extracted: corroboration, registry and
+            // same-site signals can lift them over --min-confidence, which filters by score
+            extractors: builtin_extractors(
+                true,
+                sourcemap_prober,
+                source_exporter.clone(),
+                bundler_parser,
+            ),
             browser_capture,
             console,
             telegram: None,
+            scope,
+            audit_log,
+            resource_blocker,
+            source_exporter,
+            filter_logs: DashMap::new(),
+            filter_rules,
         })
     }
 
+    /// The extractors run on every JS file, in order.
+    pub fn extractors(&self) -> &Extractors {
+        &self.extractors
+    }
+
+    /// Add, remove or reorder the extractors run on every JS file.
+    pub fn extractors_mut(&mut self) -> &mut Extractors {
+        &mut self.extractors
+    }
+
+    /// Use `rules` instead of the ones loaded from `--filter-rules`.
+    pub fn with_filter_rules(mut self, rules: FilterRules) -> Self {
+        self.filter_rules = Some(Arc::new(rules));
+        self
+    }
+
     /// Configure Telegram notifications.
     pub fn with_telegram(mut self, token: &str, chat_id: &str) -> Result<Self> {
-        self.telegram = Some(TelegramNotifier::new(token, chat_id)?);
+        self.telegram = Some(
+            TelegramNotifier::new(token, chat_id)?
+                .with_resolve_overrides(&self.config.resolve)?
+                .with_audit_log(self.audit_log.clone()),
+        );
         Ok(self)
     }
 
+    /// Result for a target outside the configured scope, or `None` if it is in scope.
+    fn out_of_scope_result(&self, target: &str) -> Option<ScanResult> {
+        let scope = self.scope.as_ref()?;
+        if scope.check(target, target) {
+            return None;
+        }
+
+        let mut result = ScanResult::failed(target, "Target is out of scope");
+        result.skipped_requests = scope.take_skipped(target);
+        Some(result)
+    }
+
     /// Scan a single target URL.
     pub async fn scan(&self, target: &str) -> Result<ScanResult> {
+        if let Some(result) = self.out_of_scope_result(target) {
+            return Ok(result);
+        }
+
         let start_time = Instant::now();
         self.console.print_scan_start(target);
 
@@ -124,10 +228,24 @@ impl Scanner {
     async fn process_captured_js(
         &self,
         target: &str,
-        mut all_js_files: Vec<JsFile>,
+        all_js_files: Vec<JsFile>,
         errors: Vec<String>,
         start_time: Instant,
     ) -> Result<ScanResult> {
+        let _filter_log = self.filter_log_guard(target);
+        let (js_files_count, all_packages) = self.extract_from_js(target, all_js_files).await;
+
+        self.finish_scan(target, js_files_count, all_packages, errors, start_time)
+            .await
+    }
+
+    /// Discover lazy chunks and extract packages from JS files.
+    /// Returns the number of JS files processed and the raw extracted packages.
+    async fn extract_from_js(
+        &self,
+        target: &str,
+        mut all_js_files: Vec<JsFile>,
+    ) -> (usize, HashSet<Package>) {
         let mut all_packages: HashSet<Package> = HashSet::new();
 
         // Discover lazy-loaded chunks referenced in captured JS (iterate to find nested chunks)
@@ -146,7 +264,7 @@ impl Scanner {
             scan_from = all_js_files.len();
             for chunk_url in new_urls {
                 seen_urls.insert(chunk_url.clone());
-                if let Some(js_file) = self.fetcher.fetch_one(&chunk_url, JsSource::Probe).await {
+                if let Some(js_file) = self.fetcher.fetch_one(&chunk_url, JsSource::Probe, target).await {
                     all_js_files.push(js_file);
                 }
             }
@@ -163,18 +281,13 @@ impl Scanner {
         let tasks: Vec<_> = all_js_files
             .into_iter()
             .map(|js_file| {
-                let fetcher = self.fetcher.clone();
-                let sourcemap_prober = self.sourcemap_prober.clone();
-                let sourcemap_parser = self.sourcemap_parser.clone();
-                let ast_parser = self.ast_parser.clone();
-                let webpack_parser = self.webpack_parser.clone();
-                let bundler_parser = self.bundler_parser.clone();
-                let deobfuscator = self.deobfuscator.clone();
-                let target = target.to_string();
-
-                tokio::spawn(async move {
+                let extractors = self.extractors.clone();
+                let ctx = ExtractContext::new(target, self.fetcher.clone());
+                let filter_log = self.filter_log(target);
+                let filter_rules = self.filter_rules.clone();
+
+                tokio::spawn(rules::scope(filter_rules, explain::scope(filter_log, async move {
                     let mut packages = HashSet::new();
-                    let mut workspace_names: HashSet<String> = HashSet::new();
 
                     // Skip very large files (>5MB) - they're rarely useful and slow to parse
                     const MAX_FILE_SIZE: usize = 5 * 1024 * 1024; // 5MB
@@ -183,104 +296,59 @@ impl Scanner {
                         return packages;
                     }
 
-                    // 1. Fetch and parse source map (if exists)
-                    if let Some(ref map_url) = js_file.source_map_url {
-                        if map_url.starts_with("data:") {
-                            // Inline source map
-                            if let Some(content) = SourceMapProber::decode_inline_sourcemap(map_url) {
-                                if let Ok((pkgs, ws)) = sourcemap_parser.parse(&content, map_url) {
-                                    packages.extend(pkgs);
-                                    workspace_names.extend(ws);
-                                }
-                            }
-                        } else {
-                            // Fetch external source map
-                            if let Some(map_js) = fetcher.fetch_one(map_url, JsSource::Probe).await {
-                                if let Ok((pkgs, ws)) = sourcemap_parser.parse(&map_js.content, map_url) {
-                                    packages.extend(pkgs);
-                                    workspace_names.extend(ws);
-                                }
-                            }
-                        }
-                    }
-
-                    // Also probe for .map even if not referenced (but only for bundled files)
-                    // Check if file looks like it's bundled (has webpack/vite/bundler patterns)
-                    let is_likely_bundled = js_file.content.contains("webpackChunk")
-                        || js_file.content.contains("__vite__")
-                        || js_file.content.contains("parcelRequire")
-                        || js_file.content.contains("__commonJS")
-                        || js_file.content.contains("__toESM")
-                        || js_file.content.contains("__require")
-                        || js_file.content.contains("/*#__PURE__*/")
-                        || js_file.url.contains(".bundle.js")
-                        || js_file.url.ends_with("/bundle.js")
-                        || js_file.url.contains("chunk")
-                        || js_file.url.contains("vendor")
-                        || js_file.url.contains("/main-")
-                        || js_file.url.contains("/main.")
-                        || js_file.content.len() > 50_000;
-
-                    if is_likely_bundled {
-                        if let Some((map_url, content)) = sourcemap_prober.probe(&js_file.url).await {
-                            if let Ok((pkgs, ws)) = sourcemap_parser.parse(&content, &map_url) {
-                                packages.extend(pkgs);
-                                workspace_names.extend(ws);
-                            }
-                        }
-                    }
-
-                    // 2. Parse JS with AST
-                    if let Ok(pkgs) = ast_parser.parse(&js_file.content, &js_file.url) {
-                        packages.extend(pkgs);
-                    }
-
-                    // 3. Webpack-specific extraction
-                    if webpack_parser.is_webpack_bundle(&js_file.content) {
-                        let webpack_packages = webpack_parser
-                            .extract_packages(&js_file.content, &js_file.url);
-                        packages.extend(webpack_packages);
-
-                        // Check for Next.js and extract build manifests
-                        if let Some(build_id) = webpack_parser.extract_nextjs_build_id(&js_file.content)
-                        {
-                            let manifest_urls = webpack_parser
-                                .get_nextjs_manifest_urls(&target, &build_id);
-                            for url in manifest_urls {
-                                if let Some(manifest) = fetcher.fetch_one(&url, JsSource::Probe).await {
-                                    if let Ok(pkgs) = ast_parser.parse(&manifest.content, &manifest.url) {
-                                        packages.extend(pkgs);
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    // 4. Bundler-specific extraction
-                    let bundler_packages = bundler_parser
-                        .extract_packages(&js_file.content, &js_file.url);
-                    packages.extend(bundler_packages);
-
-                    // 5. Deobfuscation extraction
-                    if deobfuscator.is_likely_obfuscated(&js_file.content) {
-                        debug!("Detected obfuscated content in {}, running deobfuscation", js_file.url);
-                        let deob_packages = deobfuscator
-                            .extract_packages(&js_file.content, &js_file.url);
-                        packages.extend(deob_packages);
+                    for extractor in extractors.iter() {
+                        let found = extractor.extract(&js_file, &ctx).await;
+                        trace!("Extractor {} found {} packages in {}", extractor.name(), found.len(), js_file.url);
+                        packages.extend(found);
                     }
+                    // The file's own map, to locate extractions in the original sources
+                    let (workspace_names, lookup) = ctx.into_parts();
 
                     // Filter workspace-only packages detected by source map analysis
                     if !workspace_names.is_empty() {
                         let before = packages.len();
-                        packages.retain(|p| !workspace_names.contains(&p.name));
+                        packages.retain(|p| {
+                            let keep = !workspace_names.contains(&p.name);
+                            if !keep {
+                                explain::record(&p.name, "workspace-only package", Some(&p.source_url));
+                            }
+                            keep
+                        });
                         let filtered = before - packages.len();
                         if filtered > 0 {
                             debug!("Filtered {} workspace-only packages", filtered);
                         }
                     }
 
+                    // Show the code each extraction from this file matched, and translate
+                    // it through the file's map to the original source
+                    let lines = LineIndex::new(&js_file.content);
+                    packages = packages
+                        .into_iter()
+                        .map(|mut p| {
+                            if let (Some(span), true) = (p.span, p.source_url == js_file.url) {
+                                p.evidence = Some(lines.evidence(span));
+                                if let (Some(lookup), None) = (&lookup, &p.original) {
+                                    p.original = lookup.locate(&lines, span);
+                                }
+                            }
+                            p
+                        })
+                        .collect();
+
+                    // Attribute everything found in this file to the profiles that loaded it
+                    if !js_file.profiles.is_empty() {
+                        packages = packages
+                            .into_iter()
+                            .map(|mut p| {
+                                p.profiles = js_file.profiles.clone();
+                                p
+                            })
+                            .collect();
+                    }
+
                     packages
-                })
+                })))
             })
             .collect();
 
@@ -292,27 +360,76 @@ impl Scanner {
             }
         }
 
+        (js_files_count, all_packages)
+    }
+
+    /// The log of candidates dropped while scanning `target`, with `--explain-filters`.
+    fn filter_log(&self, target: &str) -> Option<Arc<FilterLog>> {
+        self.config
+            .explain_filters
+            .then(|| self.filter_logs.entry(target.to_string()).or_default().clone())
+    }
+
+    /// Keep `target`'s filter log from outliving a scan that stops before [`Self::finish_scan`].
+    fn filter_log_guard(&self, target: &str) -> FilterLogGuard<'_> {
+        FilterLogGuard {
+            logs: &self.filter_logs,
+            target: target.to_string(),
+        }
+    }
+
+    /// Deduplicate and filter extracted packages, check npm, and build the scan result.
+    async fn finish_scan(
+        &self,
+        target: &str,
+        js_files_count: usize,
+        all_packages: HashSet<Package>,
+        errors: Vec<String>,
+        start_time: Instant,
+    ) -> Result<ScanResult> {
+        // This target's log leaves the map; later drops are recorded on it directly
+        let filter_log = self.config.explain_filters.then(|| {
+            self.filter_logs
+                .remove(target)
+                .map(|(_, log)| log)
+                .unwrap_or_default()
+        });
+
         // Deduplicate packages by name, keeping the highest confidence version
-        let all_packages = deduplicate_packages(all_packages);
+        let all_packages = rules::sync_scope(self.filter_rules.clone(), || {
+            explain::sync_scope(filter_log.clone(), || deduplicate_packages(all_packages))
+        });
 
         self.console.print_info(&format!(
             "Extracted {} unique packages",
             all_packages.len()
         ));
 
-        // Filter packages based on configuration
+        // Score each package, then filter based on configuration
         let mut packages_to_check: Vec<Package> = all_packages
             .into_iter()
+            .map(|mut p| {
+                scoring::score_package(&mut p, target);
+                p
+            })
             .filter(|p| {
+                let dropped = |rule: String| {
+                    if let Some(log) = &filter_log {
+                        log.push(&p.name, &rule, Some(&p.source_url));
+                    }
+                    false
+                };
                 if self.config.scoped_only && !p.name.starts_with('@') {
-                    return false;
+                    return dropped("config: --scoped-only".to_string());
                 }
-                // Filter by confidence
-                match self.config.min_confidence.as_str() {
-                    "high" => p.confidence == Confidence::High,
-                    "medium" => p.confidence >= Confidence::Medium,
-                    _ => true,
+                let score = p.score.as_ref().map_or(0, |s| s.value);
+                if score < self.config.min_confidence {
+                    return dropped(format!(
+                        "config: score {} below --min-confidence {}",
+                        score, self.config.min_confidence
+                    ));
                 }
+                true
             })
             .collect();
 
@@ -336,7 +453,7 @@ impl Scanner {
 
             let results: Vec<(Package, NpmCheckResult)> = stream::iter(packages_to_check.iter())
                 .map(|package| async move {
-                    let result = npm_checker.check_package(package).await;
+                    let result = npm_checker.check_package(package, target).await;
                     (package.clone(), result)
                 })
                 .buffer_unordered(concurrency)
@@ -369,10 +486,7 @@ impl Scanner {
                 // Only report valid findings (exploitable + info)
                 if should_report {
                     // Print to console if it's a potential vulnerability
-                    if matches!(
-                        finding.npm_result,
-                        NpmCheckResult::NotFound { .. } | NpmCheckResult::ScopeNotClaimed { .. }
-                    ) {
+                    if finding.is_potential_vulnerability() {
                         self.console.print_finding(&finding);
 
                         // Send Telegram notification for high/critical findings
@@ -401,6 +515,27 @@ impl Scanner {
 
         let duration = start_time.elapsed().as_secs_f64();
 
+        let skipped_requests = self
+            .scope
+            .as_ref()
+            .map(|scope| scope.take_skipped(target))
+            .unwrap_or_default();
+        let resource_blocking = self
+            .resource_blocker
+            .as_ref()
+            .and_then(|blocker| blocker.take_stats(target));
+
+        if let Some(exporter) = &self.source_exporter {
+            let exported = exporter.finish(target).await;
+            if exported > 0 {
+                self.console.print_info(&format!(
+                    "Exported {} original sources to {}",
+                    exported,
+                    exporter.target_dir(target).display()
+                ));
+            }
+        }
+
         let result = ScanResult {
             target: target.to_string(),
             js_files_count,
@@ -408,6 +543,9 @@ impl Scanner {
             findings,
             duration_secs: duration,
             errors,
+            skipped_requests,
+            resource_blocking,
+            filtered: filter_log.map(|log| log.take()).unwrap_or_default(),
         };
 
         self.console.print_summary(&result);
@@ -417,12 +555,7 @@ impl Scanner {
             let vuln_count = result
                 .findings
                 .iter()
-                .filter(|f| {
-                    matches!(
-                        f.npm_result,
-                        NpmCheckResult::NotFound { .. } | NpmCheckResult::ScopeNotClaimed { .. }
-                    )
-                })
+                .filter(|f| f.is_potential_vulnerability())
                 .count();
 
             if let Err(e) = telegram
@@ -438,45 +571,83 @@ impl Scanner {
 
     /// Scan a group of URLs that share the same host using a single browser instance.
     async fn scan_host_group(&self, urls: Vec<String>) -> Vec<ScanResult> {
-        let url_refs: Vec<&str> = urls.iter().map(|s| s.as_str()).collect();
+        let mut results = Vec::with_capacity(urls.len());
+
+        let mut url_refs: Vec<&str> = Vec::with_capacity(urls.len());
+        for url in &urls {
+            match self.out_of_scope_result(url) {
+                Some(result) => results.push(result),
+                None => url_refs.push(url),
+            }
+        }
+        if url_refs.is_empty() {
+            return results;
+        }
+
         let capture_results = self.browser_capture.capture_multiple(&url_refs).await;
 
-        let mut results = Vec::with_capacity(urls.len());
         for (target, capture_result) in capture_results {
-            let start_time = Instant::now();
-            self.console.print_scan_start(&target);
-
-            let (js_files, errors) = match capture_result {
-                Ok(files) => {
-                    self.console.print_progress(&format!(
-                        "Browser captured {} JS files",
-                        files.len()
-                    ));
-                    (files, Vec::new())
-                }
-                Err(e) => (Vec::new(), vec![format!("Browser capture failed: {}", e)]),
-            };
+            results.push(self.process_capture(target, capture_result).await);
+        }
 
-            match self
-                .process_captured_js(&target, js_files, errors, start_time)
-                .await
-            {
-                Ok(result) => results.push(result),
-                Err(e) => results.push(ScanResult {
-                    target,
-                    js_files_count: 0,
-                    packages_found: 0,
-                    findings: vec![],
-                    duration_secs: 0.0,
-                    errors: vec![e.to_string()],
-                }),
+        results
+    }
+
+    /// Scan targets with a pool of browsers and tabs shared across all hosts.
+    ///
+    /// Targets are processed as soon as their capture finishes, while the pool keeps
+    /// loading the rest.
+    async fn scan_pooled(&self, targets: Vec<String>, size: PoolSize) -> Vec<ScanResult> {
+        let parallel_count = self.config.parallel.max(1);
+
+        let mut results = Vec::with_capacity(targets.len());
+        let mut to_capture = Vec::with_capacity(targets.len());
+        for target in targets {
+            match self.out_of_scope_result(&target) {
+                Some(result) => results.push(result),
+                None => to_capture.push(target),
             }
         }
 
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        let capture = self.browser_capture.capture_pooled(to_capture, size, tx);
+        let process = rx
+            .map(|(target, capture_result)| self.process_capture(target, capture_result))
+            .buffer_unordered(parallel_count)
+            .collect::<Vec<_>>();
+
+        let ((), scanned) = tokio::join!(capture, process);
+        results.extend(scanned);
         results
     }
 
-    /// Scan multiple targets, grouping by host to reuse browser instances.
+    /// Run the extraction pipeline on one browser capture.
+    async fn process_capture(&self, target: String, capture_result: Result<Vec<JsFile>>) -> ScanResult {
+        let start_time = Instant::now();
+        self.console.print_scan_start(&target);
+
+        let (js_files, errors) = match capture_result {
+            Ok(files) => {
+                self.console.print_progress(&format!(
+                    "Browser captured {} JS files",
+                    files.len()
+                ));
+                (files, Vec::new())
+            }
+            Err(e) => (Vec::new(), vec![format!("Browser capture failed: {}", e)]),
+        };
+
+        match self
+            .process_captured_js(&target, js_files, errors, start_time)
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => ScanResult::failed(target, e.to_string()),
+        }
+    }
+
+    /// Scan multiple targets, grouping by host to reuse browser instances, or through
+    /// the browser pool when one is configured.
     pub async fn scan_multiple(&self, targets: Vec<String>) -> Vec<ScanResult> {
         let parallel_count = self.config.parallel.max(1);
 
@@ -484,40 +655,40 @@ impl Scanner {
         if targets.len() == 1 {
             let result = match self.scan(&targets[0]).await {
                 Ok(r) => r,
-                Err(e) => ScanResult {
-                    target: targets[0].clone(),
-                    js_files_count: 0,
-                    packages_found: 0,
-                    findings: vec![],
-                    duration_secs: 0.0,
-                    errors: vec![e.to_string()],
-                },
+                Err(e) => ScanResult::failed(targets[0].clone(), e.to_string()),
             };
             return vec![result];
         }
 
-        // Group targets by host for browser reuse
-        let host_groups = crate::browser::group_by_host(&targets);
-
         // Build an index to restore original ordering
         let mut url_to_index: HashMap<String, usize> = HashMap::new();
         for (i, t) in targets.iter().enumerate() {
             url_to_index.insert(t.clone(), i);
         }
 
-        // Process host groups in parallel
-        let all_group_results: Vec<Vec<ScanResult>> = stream::iter(host_groups)
-            .map(|(_host, group_urls)| async move {
-                self.scan_host_group(group_urls).await
-            })
-            .buffer_unordered(parallel_count)
-            .collect()
-            .await;
+        let all_results: Vec<ScanResult> = match self.config.browser_pool_size() {
+            // One pool of browsers and tabs shared by every target
+            Some(size) => self.scan_pooled(targets, size).await,
+            None => {
+                // Group targets by host for browser reuse
+                let host_groups = crate::browser::group_by_host(&targets);
+
+                // Process host groups in parallel, one browser per group
+                let all_group_results: Vec<Vec<ScanResult>> = stream::iter(host_groups)
+                    .map(|(_host, group_urls)| async move {
+                        self.scan_host_group(group_urls).await
+                    })
+                    .buffer_unordered(parallel_count)
+                    .collect()
+                    .await;
+
+                all_group_results.into_iter().flatten().collect()
+            }
+        };
 
-        // Flatten and re-order to match original input order
-        let mut indexed: Vec<(usize, ScanResult)> = all_group_results
+        // Re-order to match original input order
+        let mut indexed: Vec<(usize, ScanResult)> = all_results
             .into_iter()
-            .flatten()
             .map(|r| {
                 let idx = url_to_index.get(&r.target).copied().unwrap_or(usize::MAX);
                 (idx, r)
@@ -528,12 +699,102 @@ impl Scanner {
         indexed.into_iter().map(|(_, r)| r).collect()
     }
 
+    /// Scan a list of JS asset URLs directly, without launching a browser.
+    ///
+    /// URLs are grouped by origin and each origin produces one `ScanResult`.
+    /// Files are fetched and parsed in batches so that very large lists do not
+    /// keep every JS body in memory at once.
+    pub async fn scan_js_urls(&self, js_urls: Vec<String>) -> Vec<ScanResult> {
+        let parallel_count = self.config.parallel.max(1);
+        let origin_groups = crate::browser::group_by_host(&js_urls);
+
+        stream::iter(origin_groups)
+            .map(|(origin, urls)| async move { self.scan_js_url_group(origin, urls).await })
+            .buffered(parallel_count)
+            .collect()
+            .await
+    }
+
+    /// Fetch and scan all JS URLs belonging to a single origin.
+    async fn scan_js_url_group(&self, origin: String, urls: Vec<String>) -> ScanResult {
+        const BATCH_SIZE: usize = 200;
+        const FETCH_CONCURRENCY: usize = 20;
+
+        let start_time = Instant::now();
+        self.console.print_scan_start(&origin);
+        self.console.print_progress(&format!(
+            "Fetching {} JS URLs directly (no browser)...",
+            urls.len()
+        ));
+
+        let _filter_log = self.filter_log_guard(&origin);
+        let mut all_packages: HashSet<Package> = HashSet::new();
+        let mut js_files_count = 0;
+        let mut failed: Vec<&str> = Vec::new();
+        let mut duplicates = 0;
+
+        for batch in urls.chunks(BATCH_SIZE) {
+            let origin = origin.as_str();
+            let outcomes: Vec<(&str, FetchOutcome)> = stream::iter(batch)
+                .map(|url| async move {
+                    (url.as_str(), self.fetcher.fetch(url, JsSource::UrlList, origin).await)
+                })
+                .buffer_unordered(FETCH_CONCURRENCY)
+                .collect()
+                .await;
+
+            let mut js_files = Vec::new();
+            for (url, outcome) in outcomes {
+                match outcome {
+                    FetchOutcome::Fetched(file) => js_files.push(file),
+                    FetchOutcome::Duplicate => duplicates += 1,
+                    FetchOutcome::Failed => failed.push(url),
+                    // Already recorded in the scope report
+                    FetchOutcome::OutOfScope => {}
+                }
+            }
+
+            let (count, packages) = self.extract_from_js(origin, js_files).await;
+            js_files_count += count;
+            all_packages.extend(packages);
+        }
+
+        if duplicates > 0 {
+            self.console.print_progress(&format!(
+                "Skipped {} JS URLs with content already fetched from another URL",
+                duplicates
+            ));
+        }
+
+        let mut errors = Vec::new();
+        if !failed.is_empty() {
+            debug!("JS URLs that could not be fetched for {}: {}", origin, failed.join(", "));
+            errors.push(format!(
+                "{} of {} JS URLs could not be fetched",
+                failed.len(),
+                urls.len()
+            ));
+        }
+
+        match self
+            .finish_scan(&origin, js_files_count, all_packages, errors, start_time)
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => ScanResult {
+                js_files_count,
+                duration_secs: start_time.elapsed().as_secs_f64(),
+                ..ScanResult::failed(origin, e.to_string())
+            },
+        }
+    }
+
     /// Create a finding from a package and npm check result.
     fn create_finding(&self, package: Package, npm_result: NpmCheckResult) -> Finding {
         let severity = match &npm_result {
             NpmCheckResult::ScopeNotClaimed { .. } => Severity::Critical,
             NpmCheckResult::NotFound { .. } => {
-                if is_likely_internal(&package.name) {
+                if is_likely_internal(&package.name) || package.registry.is_some() {
                     Severity::High
                 } else {
                     Severity::Medium
@@ -549,32 +810,75 @@ impl Scanner {
             notes.push("Package name suggests internal/private usage".to_string());
         }
 
+        if let Some(registry) = &package.registry {
+            notes.push(format!(
+                "Embedded package.json publishes to private registry {}",
+                registry
+            ));
+        }
+
         if package.confidence == Confidence::Low {
             notes.push("Low confidence extraction - verify manually".to_string());
         }
 
-        Finding {
+        let mut finding = Finding {
             package,
             npm_result,
             severity,
             notes,
+        };
+
+        if finding.internal_version_ahead() {
+            finding.severity = Severity::Medium;
+            if let (Some(bundled), Some(highest)) =
+                (&finding.package.version, finding.highest_public_version())
+            {
+                let note = format!(
+                    "Bundled version {} is unpublished and newer than the highest public version {} - an internal package may share this public name",
+                    bundled, highest
+                );
+                finding.notes.push(note);
+            }
         }
+
+        finding
+    }
+}
+
+/// Removes a target's filter log from its scanner when dropped.
+///
+/// [`Scanner::finish_scan`] takes the log first; this cleans up after scans that
+/// fail or are cancelled before it.
+struct FilterLogGuard<'a> {
+    logs: &'a DashMap<String, Arc<FilterLog>>,
+    target: String,
+}
+
+impl Drop for FilterLogGuard<'_> {
+    fn drop(&mut self) {
+        self.logs.remove(&self.target);
     }
 }
 
 /// Get extraction method priority for deduplication.
 fn extraction_priority(method: &ExtractionMethod) -> u8 {
     match method {
+        ExtractionMethod::Manifest => 4,
         ExtractionMethod::Import | ExtractionMethod::Require | ExtractionMethod::DynamicImport => 3,
         ExtractionMethod::SourceMap | ExtractionMethod::WebpackChunk => 2,
         ExtractionMethod::Comment | ExtractionMethod::ErrorMessage | ExtractionMethod::Deobfuscate => 1,
     }
 }
 
-/// Check if a package should be filtered out as likely false positive.
-fn should_skip_package(pkg: &Package) -> bool {
-    if is_likely_false_positive(&pkg.name) {
-        return true;
+/// Why a package should be filtered out as likely false positive, if it should.
+fn skip_reason(pkg: &Package) -> Option<String> {
+    // User rules also cover names no extractor filter looked at
+    if let Some(matched) = rules::check_current(&pkg.name, Some(&pkg.source_url)) {
+        return (matched.action == RuleAction::Deny).then(|| format!("rule: {}", matched));
+    }
+
+    if let Some(reason) = false_positive_reason(&pkg.name) {
+        return Some(format!("false positive: {}", reason));
     }
 
     // Filter WebpackChunk extractions without hyphens or scopes
@@ -583,7 +887,7 @@ fn should_skip_package(pkg: &Package) -> bool {
         && !pkg.name.contains('/')
         && pkg.name.len() < 20
     {
-        return true;
+        return Some("skip: plain webpack chunk name".to_string());
     }
 
     // Filter Comment extractions without hyphens or scopes
@@ -591,28 +895,69 @@ fn should_skip_package(pkg: &Package) -> bool {
         && !pkg.name.contains('-')
         && !pkg.name.starts_with('@')
     {
-        return true;
+        return Some("skip: plain name in comment".to_string());
     }
 
-    false
+    None
 }
 
 /// Deduplicate packages by name, keeping the one with highest confidence.
+///
+/// Every copy is kept as an occurrence of the winner, for scoring.
 fn deduplicate_packages(packages: HashSet<Package>) -> HashSet<Package> {
     let mut by_name: HashMap<String, Package> = HashMap::new();
+    let mut occurrences: HashMap<String, HashSet<Occurrence>> = HashMap::new();
+    let mut profiles: HashMap<String, Vec<String>> = HashMap::new();
+    let mut versions: HashMap<String, String> = HashMap::new();
+    let mut registries: HashMap<String, String> = HashMap::new();
 
     for pkg in packages {
-        if should_skip_package(&pkg) {
-            trace!("Skipping artifact: {}", pkg.name);
+        if let Some(reason) = skip_reason(&pkg) {
+            trace!("Skipping artifact: {} ({})", pkg.name, reason);
+            explain::record(&pkg.name, &reason, Some(&pkg.source_url));
             continue;
         }
 
+        // Keep attribution from every copy, not just the one that wins
+        let seen = occurrences.entry(pkg.name.clone()).or_default();
+        if pkg.occurrences.is_empty() {
+            seen.insert(pkg.occurrence());
+        } else {
+            seen.extend(pkg.occurrences.iter().cloned());
+        }
+        profiles
+            .entry(pkg.name.clone())
+            .or_default()
+            .extend(pkg.profiles.iter().cloned());
+
+        // Keep the highest bundled version seen for the name
+        if let Some(ref version) = pkg.version {
+            let higher = versions
+                .get(&pkg.name)
+                .is_none_or(|current| version_is_higher(version, current));
+            if higher {
+                versions.insert(pkg.name.clone(), version.clone());
+            }
+        }
+
+        // A leaked private registry is evidence whichever copy wins
+        if let Some(ref registry) = pkg.registry {
+            registries
+                .entry(pkg.name.clone())
+                .or_insert_with(|| registry.clone());
+        }
+
         let should_insert = match by_name.get(&pkg.name) {
             Some(existing) => {
-                pkg.confidence > existing.confidence
-                    || (pkg.confidence == existing.confidence
-                        && extraction_priority(&pkg.extraction_method)
-                            > extraction_priority(&existing.extraction_method))
+                let rank = |p: &Package| {
+                    (
+                        p.confidence,
+                        extraction_priority(&p.extraction_method),
+                        // Among equals, prefer a copy that points at the original source
+                        p.original.is_some(),
+                    )
+                };
+                rank(&pkg) > rank(existing)
             }
             None => true,
         };
@@ -622,7 +967,31 @@ fn deduplicate_packages(packages: HashSet<Package>) -> HashSet<Package> {
         }
     }
 
-    by_name.into_values().collect()
+    by_name
+        .into_values()
+        .map(|mut pkg| {
+            if let Some(mut names) = profiles.remove(&pkg.name) {
+                names.sort();
+                names.dedup();
+                pkg.profiles = names;
+            }
+            if let Some(version) = versions.remove(&pkg.name) {
+                pkg.version = Some(version);
+            }
+            if let Some(registry) = registries.remove(&pkg.name) {
+                pkg.registry = Some(registry);
+            }
+            if let Some(seen) = occurrences.remove(&pkg.name) {
+                let mut seen: Vec<Occurrence> = seen.into_iter().collect();
+                seen.sort_by(|a, b| {
+                    (&a.source_url, a.span.map(|s| s.start))
+                        .cmp(&(&b.source_url, b.span.map(|s| s.start)))
+                });
+                pkg.occurrences = seen;
+            }
+            pkg
+        })
+        .collect()
 }
 
 /// Discover lazy-loaded chunk URLs referenced in captured JS files.
@@ -666,3 +1035,68 @@ fn discover_lazy_chunks(js_files: &[JsFile], _target: &str) -> Vec<String> {
 
     chunk_urls.into_iter().collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, method: ExtractionMethod, source: &str, confidence: Confidence, start: usize) -> Package {
+        Package::new(name.to_string(), method, source.to_string(), confidence).with_span(start..start + name.len())
+    }
+
+    #[test]
+    fn test_deduplicate_keeps_occurrences() {
+        let packages = HashSet::from([
+            package("@acme/auth-client", ExtractionMethod::Import, "https://example.com/main.js", Confidence::Medium, 120),
+            package("@acme/auth-client", ExtractionMethod::Import, "https://example.com/vendor.js", Confidence::Medium, 40),
+            package("@acme/auth-client", ExtractionMethod::SourceMap, "https://example.com/main.js.map", Confidence::Medium, 10),
+        ]);
+
+        let deduped = deduplicate_packages(packages);
+        assert_eq!(deduped.len(), 1);
+        let mut pkg = deduped.into_iter().next().unwrap();
+        assert_eq!(pkg.occurrences.len(), 3);
+        assert_eq!(pkg.occurrences[0].source_url, "https://example.com/main.js");
+        assert_eq!(pkg.methods(), [&ExtractionMethod::Import, &ExtractionMethod::SourceMap]);
+        // Imports and a source map path agree
+        scoring::score_package(&mut pkg, "https://example.com");
+        assert_eq!(pkg.confidence, Confidence::High);
+    }
+
+    #[test]
+    fn test_combined_confidence_needs_independent_methods() {
+        let packages = HashSet::from([
+            package("acme-telemetry", ExtractionMethod::Comment, "https://example.com/a.js", Confidence::Low, 0),
+            package("acme-telemetry", ExtractionMethod::ErrorMessage, "https://example.com/a.js", Confidence::Low, 300),
+        ]);
+
+        let mut pkg = deduplicate_packages(packages).into_iter().next().unwrap();
+        assert_eq!(pkg.occurrences.len(), 2);
+        scoring::score_package(&mut pkg, "https://example.com");
+        assert_eq!(pkg.confidence, Confidence::Low);
+    }
+
+    #[test]
+    fn test_deduplicate_explains_skips() {
+        let packages = HashSet::from([
+            package("acme.com", ExtractionMethod::Import, "https://example.com/a.js", Confidence::Medium, 0),
+            package("chunkname", ExtractionMethod::WebpackChunk, "https://example.com/b.js", Confidence::Medium, 0),
+            package("@acme/ui", ExtractionMethod::Import, "https://example.com/a.js", Confidence::Medium, 10),
+        ]);
+
+        let log = Arc::new(FilterLog::default());
+        let deduped = explain::sync_scope(Some(log.clone()), || deduplicate_packages(packages));
+        assert_eq!(deduped.len(), 1);
+
+        let filtered = log.take();
+        let rules: Vec<(&str, &str)> = filtered.iter().map(|f| (f.name.as_str(), f.rule.as_str())).collect();
+        assert_eq!(
+            rules,
+            [
+                ("acme.com", "false positive: domain name"),
+                ("chunkname", "skip: plain webpack chunk name"),
+            ]
+        );
+        assert_eq!(filtered[0].source_url.as_deref(), Some("https://example.com/a.js"));
+    }
+}
@@ -0,0 +1,290 @@
+//! Scope enforcement for outbound requests.
+//!
+//! Bug bounty programs define strict scope, so every request made by the fetcher,
+//! the source map prober and the browser is checked against a scope file first.
+//!
+//! Scope file format (one rule per line, `#` starts a comment):
+//!
+//! ```text
+//! example.com            # exact host
+//! *.example.com          # any subdomain (not the apex)
+//! 10.0.0.0/8             # IPv4/IPv6 CIDR
+//! !admin.example.com     # exclusion, wins over any include
+//! !*.corp.example.com
+//! ```
+//!
+//! With no include rules, every host is in scope except the exclusions.
+//!
+//! CIDR rules match hosts written as IP addresses only. Hostnames are not
+//! resolved, so a name that points into an excluded network is not caught by
+//! the CIDR rule; exclude such hosts by name as well.
+
+use crate::types::{DepfusedError, Result};
+use dashmap::DashMap;
+use std::net::IpAddr;
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// A single host rule from the scope file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HostRule {
+    /// Host glob such as `*.example.com` or `cdn-*.example.net`.
+    Glob(String),
+    /// IP network in CIDR notation.
+    Cidr { network: IpAddr, prefix_len: u8 },
+}
+
+impl HostRule {
+    fn parse(rule: &str) -> Result<Self> {
+        if let Some((addr, prefix)) = rule.split_once('/') {
+            let network: IpAddr = addr.parse().map_err(|_| {
+                DepfusedError::ConfigError(format!("Invalid CIDR address in scope: {}", rule))
+            })?;
+            let max_len = if network.is_ipv4() { 32 } else { 128 };
+            let prefix_len: u8 = prefix
+                .parse()
+                .ok()
+                .filter(|len| *len <= max_len)
+                .ok_or_else(|| {
+                    DepfusedError::ConfigError(format!("Invalid CIDR prefix in scope: {}", rule))
+                })?;
+            return Ok(HostRule::Cidr { network, prefix_len });
+        }
+
+        // A colon that isn't part of an IPv6 address means a URL or host:port
+        if rule.contains(':') && rule.parse::<IpAddr>().is_err() {
+            return Err(DepfusedError::ConfigError(format!(
+                "Scope rules must be hosts, globs or CIDRs, not URLs: {}",
+                rule
+            )));
+        }
+
+        Ok(HostRule::Glob(rule.to_ascii_lowercase()))
+    }
+
+    fn matches(&self, host: &str, ip: Option<IpAddr>) -> bool {
+        match self {
+            HostRule::Glob(pattern) => glob_match(pattern, host),
+            HostRule::Cidr { network, prefix_len } => {
+                ip.is_some_and(|ip| ip_in_network(ip, *network, *prefix_len))
+            }
+        }
+    }
+}
+
+/// Central allow/deny check for every outbound request.
+///
+/// Out-of-scope URLs are recorded per target so they can be listed in the
+/// `ScanResult`.
+#[derive(Debug, Default)]
+pub struct ScopeGuard {
+    includes: Vec<HostRule>,
+    excludes: Vec<HostRule>,
+    skipped: DashMap<String, Vec<String>>,
+}
+
+impl ScopeGuard {
+    /// Load a scope file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let guard = Self::parse(&content)?;
+        if guard.has_cidr_rules() {
+            warn!(
+                "Scope CIDR rules only match IP address hosts; hostnames are not resolved, so list excluded hosts by name too"
+            );
+        }
+        Ok(guard)
+    }
+
+    /// Parse scope rules from text.
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut guard = Self::default();
+
+        for line in content.lines() {
+            let rule = line.split('#').next().unwrap_or("").trim();
+            if rule.is_empty() {
+                continue;
+            }
+
+            match rule.strip_prefix('!') {
+                Some(excluded) => guard.excludes.push(HostRule::parse(excluded.trim())?),
+                None => guard.includes.push(HostRule::parse(rule)?),
+            }
+        }
+
+        Ok(guard)
+    }
+
+    /// Whether any rule is a CIDR, which only applies to IP address hosts.
+    pub fn has_cidr_rules(&self) -> bool {
+        self.includes
+            .iter()
+            .chain(&self.excludes)
+            .any(|rule| matches!(rule, HostRule::Cidr { .. }))
+    }
+
+    /// Check whether a URL may be requested. Does not record anything.
+    pub fn is_allowed(&self, url: &str) -> bool {
+        let Ok(parsed) = url::Url::parse(url) else {
+            return false;
+        };
+
+        // Inline resources never leave the browser
+        if matches!(parsed.scheme(), "data" | "blob" | "about") {
+            return true;
+        }
+
+        let Some(host) = parsed.host_str() else {
+            return false;
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase();
+        let ip = host.parse::<IpAddr>().ok();
+
+        if self.excludes.iter().any(|rule| rule.matches(&host, ip)) {
+            return false;
+        }
+
+        self.includes.is_empty() || self.includes.iter().any(|rule| rule.matches(&host, ip))
+    }
+
+    /// Check a URL requested on behalf of `target`, recording it if it is skipped.
+    pub fn check(&self, url: &str, target: &str) -> bool {
+        if self.is_allowed(url) {
+            return true;
+        }
+
+        debug!("Out of scope, skipping: {}", url);
+        let mut skipped = self.skipped.entry(target.to_string()).or_default();
+        if !skipped.iter().any(|u| u == url) {
+            skipped.push(url.to_string());
+        }
+        false
+    }
+
+    /// Take the URLs skipped so far on behalf of `target`.
+    pub fn take_skipped(&self, target: &str) -> Vec<String> {
+        self.skipped
+            .remove(target)
+            .map(|(_, urls)| urls)
+            .unwrap_or_default()
+    }
+}
+
+/// Match a host against a glob where `*` matches any run of characters.
+pub(crate) fn glob_match(pattern: &str, host: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let h: Vec<char> = host.chars().collect();
+
+    // Iterative wildcard matching with backtracking to the last `*`
+    let (mut pi, mut hi) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while hi < h.len() {
+        if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, hi));
+            pi += 1;
+        } else if pi < p.len() && p[pi] == h[hi] {
+            pi += 1;
+            hi += 1;
+        } else if let Some((star_pi, star_hi)) = star {
+            pi = star_pi + 1;
+            hi = star_hi + 1;
+            star = Some((star_pi, star_hi + 1));
+        } else {
+            return false;
+        }
+    }
+
+    while pi < p.len() && p[pi] == '*' {
+        pi += 1;
+    }
+
+    pi == p.len()
+}
+
+/// Check whether `ip` falls inside `network/prefix_len`.
+fn ip_in_network(ip: IpAddr, network: IpAddr, prefix_len: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(net)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(net) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(net)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(net) & mask
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.example.com", "cdn.example.com"));
+        assert!(glob_match("*.example.com", "a.b.example.com"));
+        assert!(!glob_match("*.example.com", "example.com"));
+        assert!(!glob_match("*.example.com", "example.com.evil.net"));
+        assert!(glob_match("cdn-*.example.net", "cdn-eu1.example.net"));
+        assert!(glob_match("example.com", "example.com"));
+    }
+
+    #[test]
+    fn test_scope_rules() {
+        let guard = ScopeGuard::parse(
+            "# program scope\n\
+             example.com\n\
+             *.example.com\n\
+             10.0.0.0/8\n\
+             !admin.example.com   # out of scope per policy\n",
+        )
+        .unwrap();
+
+        assert!(guard.is_allowed("https://example.com/app.js"));
+        assert!(guard.is_allowed("https://cdn.example.com/app.js.map"));
+        assert!(guard.is_allowed("http://10.1.2.3:8080/main.js"));
+        assert!(guard.is_allowed("data:application/json;base64,e30="));
+        assert!(!guard.is_allowed("https://admin.example.com/app.js"));
+        assert!(!guard.is_allowed("https://cdn.thirdparty.net/lib.js"));
+        assert!(!guard.is_allowed("http://192.168.1.1/main.js"));
+        assert!(guard.has_cidr_rules());
+        assert!(!ScopeGuard::parse("example.com").unwrap().has_cidr_rules());
+    }
+
+    #[test]
+    fn test_exclusions_only() {
+        let guard = ScopeGuard::parse("!*.googletagmanager.com\n").unwrap();
+        assert!(guard.is_allowed("https://anything.example.org/x.js"));
+        assert!(!guard.is_allowed("https://www.googletagmanager.com/gtm.js"));
+    }
+
+    #[test]
+    fn test_ipv6_cidr() {
+        let guard = ScopeGuard::parse("2001:db8::/32\n").unwrap();
+        assert!(guard.is_allowed("http://[2001:db8::1]/a.js"));
+        assert!(!guard.is_allowed("http://[2001:db9::1]/a.js"));
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(ScopeGuard::parse("10.0.0.0/33").is_err());
+        assert!(ScopeGuard::parse("https://example.com/path").is_err());
+        assert!(ScopeGuard::parse("example.com:8443").is_err());
+    }
+
+    #[test]
+    fn test_skipped_recorded_per_target() {
+        let guard = ScopeGuard::parse("example.com").unwrap();
+        assert!(!guard.check("https://other.net/a.js", "https://example.com"));
+        assert!(!guard.check("https://other.net/a.js", "https://example.com"));
+        assert!(guard.check("https://example.com/b.js", "https://example.com"));
+
+        assert_eq!(
+            guard.take_skipped("https://example.com"),
+            vec!["https://other.net/a.js".to_string()]
+        );
+        assert!(guard.take_skipped("https://example.com").is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+//! Confidence scoring of extracted packages.
+//!
+//! Each extractor rates its own match as low, medium or high. After duplicates
+//! are merged, that rating is combined with what else is known about the name
+//! into a 0-100 score: how many independent methods and files found it, whether
+//! it looks internal or matches the target's domain, whether it came from a
+//! first- or third-party file, and whether it barely passed the filters. The
+//! confidence tier is then derived from the score.
+
+use crate::parser::{filters, is_likely_internal};
+use crate::types::{Confidence, ConfidenceScore, ExtractionMethod, Package, ScoreSignal};
+use publicsuffix::{List, Psl};
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::OnceLock;
+
+/// Public suffix list (https://publicsuffix.org), parsed on first use.
+static PUBLIC_SUFFIXES: OnceLock<List> = OnceLock::new();
+
+/// Points per independent method beyond the first, and their cap.
+const CORROBORATION_POINTS: i16 = 15;
+const MAX_CORROBORATION_POINTS: i16 = 30;
+
+/// Points per source file beyond the first, and their cap.
+const SPREAD_POINTS: i16 = 5;
+const MAX_SPREAD_POINTS: i16 = 10;
+
+/// Score `package` and set its confidence to the score's tier.
+pub fn score_package(package: &mut Package, target: &str) {
+    let score = score(package, target);
+    package.confidence = Confidence::from_score(score.value);
+    package.score = Some(score);
+}
+
+/// Score a (deduplicated) package found while scanning `target`.
+pub fn score(package: &Package, target: &str) -> ConfidenceScore {
+    let mut breakdown = vec![ScoreSignal {
+        signal: format!(
+            "{:?} extraction ({:?})",
+            package.extraction_method, package.confidence
+        ),
+        points: extraction_points(package.confidence) + method_points(&package.extraction_method),
+    }];
+    let mut add = |signal: String, points: i16| {
+        if points != 0 {
+            breakdown.push(ScoreSignal { signal, points });
+        }
+    };
+
+    // Independent methods agreeing, and the name turning up in several files
+    let families: HashSet<u8> = package
+        .occurrences
+        .iter()
+        .map(|o| o.extraction_method.evidence_family())
+        .collect();
+    let others = families.len().saturating_sub(1) as i16;
+    add(
+        format!("confirmed by {} other method{}", others, plural(others)),
+        (others * CORROBORATION_POINTS).min(MAX_CORROBORATION_POINTS),
+    );
+    let files: HashSet<&str> = package
+        .occurrences
+        .iter()
+        .map(|o| o.source_url.as_str())
+        .collect();
+    add(
+        format!("found in {} files", files.len()),
+        (files.len().saturating_sub(1) as i16 * SPREAD_POINTS).min(MAX_SPREAD_POINTS),
+    );
+
+    if is_likely_internal(&package.name) {
+        add("name suggests internal use".to_string(), 15);
+    }
+    if package.registry.is_some() {
+        add("manifest names a private registry".to_string(), 15);
+    }
+    if let Some((signal, points)) = domain_signal(&package.name, target) {
+        add(signal.to_string(), points);
+    }
+    match same_site(&package.source_url, target) {
+        Some(true) => add("first-party file".to_string(), 5),
+        Some(false) => add("third-party file".to_string(), -10),
+        None => {}
+    }
+    if let Some(reason) = filters::near_miss(&package.name) {
+        add(format!("filter near-miss: {}", reason), -10);
+    }
+
+    let total: i16 = breakdown.iter().map(|s| s.points).sum();
+    ConfidenceScore {
+        value: total.clamp(0, 100) as u8,
+        breakdown,
+    }
+}
+
+/// Points for the extractor's own rating of the match.
+fn extraction_points(confidence: Confidence) -> i16 {
+    match confidence {
+        Confidence::Low => 20,
+        Confidence::Medium => 45,
+        Confidence::High => 65,
+    }
+}
+
+/// Adjustment for how directly a method reads package names.
+fn method_points(method: &ExtractionMethod) -> i16 {
+    match method {
+        ExtractionMethod::Manifest => 10,
+        ExtractionMethod::SourceMap
+        | ExtractionMethod::Import
+        | ExtractionMethod::Require
+        | ExtractionMethod::DynamicImport => 5,
+        ExtractionMethod::WebpackChunk => 0,
+        ExtractionMethod::Deobfuscate | ExtractionMethod::Comment | ExtractionMethod::ErrorMessage => -5,
+    }
+}
+
+fn plural(n: i16) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// A scope (`@acme/ui`) or name prefix (`acme-ui`) matching a label of the
+/// target's host (`shop.acme-corp.com`).
+fn domain_signal(name: &str, target: &str) -> Option<(&'static str, i16)> {
+    let host = url::Url::parse(target).ok()?.host_str()?.to_ascii_lowercase();
+    if host.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+    let mut labels: Vec<String> = host.split('.').map(|l| l.replace('-', "")).collect();
+    labels.pop(); // TLD
+    labels.retain(|l| l != "www");
+
+    let (word, signal) = match name.strip_prefix('@') {
+        Some(scoped) => (scoped.split('/').next()?, ("scope matches target domain", 15)),
+        None => (name.split(['-', '_', '.']).next()?, ("name matches target domain", 10)),
+    };
+    let word = word.replace('-', "");
+    let matches = word.len() >= 3
+        && labels
+            .iter()
+            .any(|label| label.starts_with(&word) || (label.len() >= 3 && word.starts_with(label.as_str())));
+    matches.then_some(signal)
+}
+
+/// Whether a file was served from the target's own site, when both are URLs.
+///
+/// Sites are registrable domains, so `cdn.acme.co.uk` and `www.acme.co.uk` match
+/// while `a.co.uk` and `b.co.uk` (or two `github.io` pages) don't.
+fn same_site(source_url: &str, target: &str) -> Option<bool> {
+    let site = |url: &str| {
+        let host = url::Url::parse(url).ok()?.host_str()?.to_ascii_lowercase();
+        if host.parse::<IpAddr>().is_ok() || host.starts_with('[') {
+            return Some(host);
+        }
+        let suffixes = PUBLIC_SUFFIXES.get_or_init(|| {
+            include_str!("../data/public_suffix_list.dat")
+                .parse()
+                .expect("bundled public suffix list is valid")
+        });
+        // A host that is itself a public suffix has no registrable domain
+        match suffixes.domain(host.as_bytes()) {
+            Some(domain) => Some(String::from_utf8_lossy(domain.as_bytes()).into_owned()),
+            None => Some(host),
+        }
+    };
+    Some(site(source_url)? == site(target)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, method: ExtractionMethod, source: &str, confidence: Confidence) -> Package {
+        let mut pkg = Package::new(name.to_string(), method, source.to_string(), confidence);
+        pkg.occurrences = vec![pkg.occurrence()];
+        pkg
+    }
+
+    #[test]
+    fn test_score_breakdown() {
+        let pkg = package(
+            "@acme/auth-client",
+            ExtractionMethod::Import,
+            "https://static.acme.com/main.js",
+            Confidence::Medium,
+        );
+        let score = score(&pkg, "https://www.acme.com");
+        let signals: Vec<(&str, i16)> = score.breakdown.iter().map(|s| (s.signal.as_str(), s.points)).collect();
+        assert_eq!(
+            signals,
+            [
+                ("Import extraction (Medium)", 50),
+                ("scope matches target domain", 15),
+                ("first-party file", 5),
+            ]
+        );
+        assert_eq!(score.value, 70);
+        assert_eq!(Confidence::from_score(score.value), Confidence::High);
+    }
+
+    #[test]
+    fn test_independent_methods_raise_score() {
+        let mut pkg = package("acme-telemetry", ExtractionMethod::Comment, "https://example.com/a.js", Confidence::Low);
+        let mut error = pkg.occurrence();
+        error.extraction_method = ExtractionMethod::ErrorMessage;
+        pkg.occurrences.push(error);
+        // A comment and an error string are the same kind of evidence
+        assert_eq!(score(&pkg, "https://example.com").value, 20);
+
+        let mut map = pkg.occurrence();
+        map.extraction_method = ExtractionMethod::SourceMap;
+        map.source_url = "https://example.com/a.js.map".to_string();
+        pkg.occurrences.push(map);
+        assert_eq!(score(&pkg, "https://example.com").value, 20 + 15 + 5);
+    }
+
+    #[test]
+    fn test_third_party_and_near_miss() {
+        let mut pkg = package("ui", ExtractionMethod::Import, "https://cdn.jsdelivr.net/npm/x.js", Confidence::Medium);
+        score_package(&mut pkg, "https://example.com");
+        let score = pkg.score.as_ref().unwrap();
+        assert_eq!(score.value, 50 - 10 - 10);
+        assert_eq!(pkg.confidence, Confidence::Low);
+    }
+
+    #[test]
+    fn test_domain_signal() {
+        assert!(domain_signal("@acme/ui", "https://shop.acme-corp.com").is_some());
+        assert!(domain_signal("acmecorp-utils", "https://acme-corp.com/app").is_some());
+        assert!(domain_signal("@babel/core", "https://acme.com").is_none());
+        assert!(domain_signal("@com/x", "https://acme.com").is_none());
+        assert!(domain_signal("@acme/ui", "http://10.0.0.1:8080").is_none());
+    }
+
+    #[test]
+    fn test_same_site() {
+        assert_eq!(same_site("https://cdn.acme.com/a.js", "https://www.acme.com"), Some(true));
+        assert_eq!(same_site("https://unpkg.com/a.js", "https://acme.com"), Some(false));
+        assert_eq!(same_site("inline", "https://acme.com"), None);
+        assert_eq!(same_site("https://cdn.acme.co.uk/a.js", "https://www.acme.co.uk"), Some(true));
+        assert_eq!(same_site("https://a.co.uk/a.js", "https://b.co.uk"), Some(false));
+        assert_eq!(same_site("https://evil.github.io/a.js", "https://acme.github.io"), Some(false));
+    }
+}
@@ -1,7 +1,12 @@
 //! Core types and errors for the dependency confusion scanner.
 
+use crate::audit::AuditLog;
+use crate::scope::ScopeGuard;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::Arc;
 use thiserror::Error;
 
 /// Errors that can occur during scanning.
@@ -31,6 +36,12 @@ pub enum DepfusedError {
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
+    #[error("Out of scope: {0}")]
+    OutOfScope(String),
+
+    #[error("Browser error: {0}")]
+    BrowserError(String),
+
     #[error("Telegram error: {0}")]
     TelegramError(String),
 }
@@ -50,6 +61,25 @@ pub struct JsFile {
     pub source: JsSource,
     /// Associated source map URL if found.
     pub source_map_url: Option<String>,
+    /// Emulation profiles whose page load served this file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<String>,
+    /// HTTP status of the response, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Recorded response headers (lowercase names), see `discovery::RECORDED_HEADERS`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl JsFile {
+    /// Source map reference from the `SourceMap` or deprecated `X-SourceMap` header.
+    pub fn sourcemap_header(&self) -> Option<&str> {
+        self.headers
+            .get("sourcemap")
+            .or_else(|| self.headers.get("x-sourcemap"))
+            .map(String::as_str)
+    }
 }
 
 /// Source of JS file discovery.
@@ -59,6 +89,8 @@ pub enum JsSource {
     Browser,
     /// Discovered through probing source maps.
     Probe,
+    /// Supplied directly in a JS URL list (`--js-urls`).
+    UrlList,
 }
 
 /// Represents an extracted package reference.
@@ -70,8 +102,150 @@ pub struct Package {
     pub extraction_method: ExtractionMethod,
     /// The source JS file URL where it was found.
     pub source_url: String,
-    /// Confidence level of the extraction.
+    /// Confidence level of the extraction; once scored, the tier of `score`.
     pub confidence: Confidence,
+    /// Score combining every signal about the package, set before npm checks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<ConfidenceScore>,
+    /// Emulation profiles whose page load served the source file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<String>,
+    /// Bundled version, when the source path (pnpm store, Yarn cache) or an
+    /// embedded manifest pins one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Private registry an embedded manifest's `publishConfig` declares for the package.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    /// Byte range of the extraction in the file at `source_url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    /// Where the extraction sits in the original source, when a source map resolves it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original: Option<OriginalLocation>,
+    /// The matched code and its position, for triage without fetching the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evidence: Option<Evidence>,
+    /// Every place the name was extracted, once duplicates are merged.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub occurrences: Vec<Occurrence>,
+}
+
+/// One extraction of a package name.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Occurrence {
+    pub source_url: String,
+    pub extraction_method: ExtractionMethod,
+    pub confidence: Confidence,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original: Option<OriginalLocation>,
+}
+
+/// Byte range in a file.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl From<std::ops::Range<usize>> for Span {
+    fn from(range: std::ops::Range<usize>) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+/// Where an extraction matched, in the text it was read from: the fetched file,
+/// or the embedded original source for matches in `sourcesContent`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Evidence {
+    pub span: Span,
+    /// 1-based line.
+    pub line: u32,
+    /// 1-based column, in UTF-16 units as browsers report them.
+    pub column: u32,
+    /// The match with some surrounding code on its line, whitespace collapsed.
+    pub snippet: String,
+}
+
+/// A position in an original (pre-bundling) source file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct OriginalLocation {
+    /// Source path from the map, without bundler prefixes.
+    pub file: String,
+    /// 1-based line.
+    pub line: u32,
+    /// 1-based column.
+    pub column: u32,
+}
+
+impl std::fmt::Display for OriginalLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+impl Package {
+    /// Create a package reference with no profile attribution.
+    pub fn new(
+        name: impl Into<String>,
+        extraction_method: ExtractionMethod,
+        source_url: impl Into<String>,
+        confidence: Confidence,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            extraction_method,
+            source_url: source_url.into(),
+            confidence,
+            score: None,
+            profiles: Vec::new(),
+            version: None,
+            registry: None,
+            span: None,
+            original: None,
+            evidence: None,
+            occurrences: Vec::new(),
+        }
+    }
+
+    /// Set the bundled version.
+    pub fn with_version(mut self, version: Option<String>) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set the byte range of the extraction in the source file.
+    pub fn with_span(mut self, span: impl Into<Span>) -> Self {
+        self.span = Some(span.into());
+        self
+    }
+
+    /// This extraction as an occurrence.
+    pub fn occurrence(&self) -> Occurrence {
+        Occurrence {
+            source_url: self.source_url.clone(),
+            extraction_method: self.extraction_method.clone(),
+            confidence: self.confidence,
+            span: self.span,
+            original: self.original.clone(),
+        }
+    }
+
+    /// Extraction methods that found the package, without repeats.
+    pub fn methods(&self) -> Vec<&ExtractionMethod> {
+        let mut methods: Vec<&ExtractionMethod> = Vec::new();
+        for occurrence in &self.occurrences {
+            if !methods.contains(&&occurrence.extraction_method) {
+                methods.push(&occurrence.extraction_method);
+            }
+        }
+        methods
+    }
 }
 
 /// Method used to extract the package name.
@@ -93,6 +267,8 @@ pub enum ExtractionMethod {
     ErrorMessage,
     /// From deobfuscated/encoded strings.
     Deobfuscate,
+    /// From an embedded package.json (its name or declared dependencies).
+    Manifest,
 }
 
 /// Confidence level of package extraction.
@@ -106,6 +282,72 @@ pub enum Confidence {
     High,
 }
 
+impl ExtractionMethod {
+    /// Methods in one family read the same evidence, so agreeing adds nothing.
+    pub(crate) fn evidence_family(&self) -> u8 {
+        match self {
+            ExtractionMethod::Import | ExtractionMethod::Require | ExtractionMethod::DynamicImport => 0,
+            ExtractionMethod::SourceMap => 1,
+            ExtractionMethod::WebpackChunk => 2,
+            ExtractionMethod::Comment | ExtractionMethod::ErrorMessage => 3,
+            ExtractionMethod::Deobfuscate => 4,
+            ExtractionMethod::Manifest => 5,
+        }
+    }
+}
+
+impl Confidence {
+    /// Lowest score of the medium tier.
+    pub const MEDIUM_SCORE: u8 = 40;
+    /// Lowest score of the high tier.
+    pub const HIGH_SCORE: u8 = 70;
+
+    /// Tier of a 0-100 score.
+    pub fn from_score(score: u8) -> Self {
+        match score {
+            s if s >= Self::HIGH_SCORE => Confidence::High,
+            s if s >= Self::MEDIUM_SCORE => Confidence::Medium,
+            _ => Confidence::Low,
+        }
+    }
+
+    /// Lowest score of the tier.
+    pub fn min_score(self) -> u8 {
+        match self {
+            Confidence::Low => 0,
+            Confidence::Medium => Self::MEDIUM_SCORE,
+            Confidence::High => Self::HIGH_SCORE,
+        }
+    }
+}
+
+impl std::str::FromStr for Confidence {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "low" => Ok(Confidence::Low),
+            "medium" => Ok(Confidence::Medium),
+            "high" => Ok(Confidence::High),
+            _ => Err(format!("unknown confidence level '{}'", s)),
+        }
+    }
+}
+
+/// A 0-100 confidence score and the signals that make it up.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct ConfidenceScore {
+    pub value: u8,
+    pub breakdown: Vec<ScoreSignal>,
+}
+
+/// One signal's contribution to a confidence score.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct ScoreSignal {
+    pub signal: String,
+    pub points: i16,
+}
+
 /// Result of checking a package against npm registry.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum NpmCheckResult {
@@ -113,6 +355,9 @@ pub enum NpmCheckResult {
     Exists {
         name: String,
         latest_version: Option<String>,
+        /// Every version published under this name.
+        #[serde(default)]
+        published_versions: Vec<String>,
     },
     /// Package does not exist (potential vulnerability).
     NotFound { name: String },
@@ -135,6 +380,55 @@ pub struct Finding {
     pub notes: Vec<String>,
 }
 
+impl Finding {
+    /// The bundled version was never published and is newer than every version on
+    /// the public registry, so an internal package probably shares this public name.
+    pub fn internal_version_ahead(&self) -> bool {
+        let (Some(bundled), NpmCheckResult::Exists { published_versions, .. }) =
+            (&self.package.version, &self.npm_result)
+        else {
+            return false;
+        };
+        let Some(bundled) = parse_version(bundled) else {
+            return false;
+        };
+        let published: Vec<_> = published_versions.iter().filter_map(|v| parse_version(v)).collect();
+        !published.contains(&bundled) && published.iter().max().is_some_and(|highest| bundled > *highest)
+    }
+
+    /// The highest version published on the public registry.
+    pub fn highest_public_version(&self) -> Option<&str> {
+        match &self.npm_result {
+            NpmCheckResult::Exists { published_versions, .. } => published_versions
+                .iter()
+                .filter_map(|v| parse_version(v).map(|parsed| (parsed, v.as_str())))
+                .max_by(|(a, _), (b, _)| a.cmp(b))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Whether this finding is a dependency confusion risk rather than informational.
+    pub fn is_potential_vulnerability(&self) -> bool {
+        matches!(
+            self.npm_result,
+            NpmCheckResult::NotFound { .. } | NpmCheckResult::ScopeNotClaimed { .. }
+        ) || self.internal_version_ahead()
+    }
+}
+
+/// Compare two semver versions; anything unparseable never counts as higher.
+pub fn version_is_higher(version: &str, than: &str) -> bool {
+    match (parse_version(version), parse_version(than)) {
+        (Some(version), Some(than)) => version > than,
+        _ => false,
+    }
+}
+
+fn parse_version(version: &str) -> Option<semver::Version> {
+    semver::Version::parse(version.trim().trim_start_matches('v')).ok()
+}
+
 /// Severity level of a finding.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Severity {
@@ -165,17 +459,131 @@ pub struct ScanResult {
     pub duration_secs: f64,
     /// Any errors encountered during scan.
     pub errors: Vec<String>,
+    /// Requests that were not made because they were out of scope.
+    #[serde(default)]
+    pub skipped_requests: Vec<String>,
+    /// Browser resource blocking statistics, if blocking was enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_blocking: Option<BlockStats>,
+    /// Candidates dropped by filters, with `--explain-filters`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filtered: Vec<FilteredPackage>,
+}
+
+/// A candidate package name a filter dropped, and why.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct FilteredPackage {
+    pub name: String,
+    /// The dropping rule, e.g. `filter: css class` or `rule: deny glob *-theme`.
+    pub rule: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+}
+
+/// Requests the browser was stopped from loading for one target.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BlockStats {
+    /// Total blocked requests.
+    pub blocked_requests: usize,
+    /// Blocked requests by resource type (`Image`, `Font`, ...).
+    pub by_type: BTreeMap<String, usize>,
+    /// Blocked requests by denied host.
+    pub by_host: BTreeMap<String, usize>,
+    /// Rough estimate of the response bytes not downloaded, from typical sizes of
+    /// the blocked resource types. Host-blocked requests are not counted.
+    pub estimated_bytes_saved: u64,
+    /// Time spent loading the page in the browser with blocking on, in seconds.
+    /// This is the load time, not the time saved; compare it with a scan without
+    /// blocking to see the difference.
+    pub page_load_secs: f64,
+}
+
+impl ScanResult {
+    /// Result for a target that could not be scanned.
+    pub fn failed(target: impl Into<String>, error: impl Into<String>) -> Self {
+        Self {
+            target: target.into(),
+            js_files_count: 0,
+            packages_found: 0,
+            findings: vec![],
+            duration_secs: 0.0,
+            errors: vec![error.into()],
+            skipped_requests: vec![],
+            resource_blocking: None,
+            filtered: vec![],
+        }
+    }
 }
 
 /// Deduplication set for content hashes.
 pub type ContentHashSet = HashSet<String>;
 
+/// A `--resolve host:port:ip` mapping that pins a hostname to an address.
+///
+/// The mapping applies to the hostname on every port: reqwest can only override
+/// DNS per hostname, so the browser rule is port-agnostic as well and both reach
+/// the same server. The port is kept for curl-compatible syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveOverride {
+    pub host: String,
+    pub port: u16,
+    pub ip: IpAddr,
+}
+
+impl ResolveOverride {
+    /// Chromium `--host-resolver-rules` entry for this mapping, matching any port.
+    pub fn host_resolver_rule(&self) -> String {
+        match self.ip {
+            IpAddr::V4(ip) => format!("MAP {} {}", self.host, ip),
+            IpAddr::V6(ip) => format!("MAP {} [{}]", self.host, ip),
+        }
+    }
+}
+
+impl FromStr for ResolveOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, ':');
+        let (Some(host), Some(port), Some(ip)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(format!("expected host:port:ip, got '{}'", s));
+        };
+
+        let host = host.trim().to_ascii_lowercase();
+        if host.is_empty() {
+            return Err(format!("missing host in '{}'", s));
+        }
+        let port = port
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid port in '{}'", s))?;
+        let ip = ip
+            .trim()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse()
+            .map_err(|_| format!("invalid IP address in '{}'", s))?;
+
+        Ok(Self { host, port, ip })
+    }
+}
+
 /// Configuration for HTTP requests.
 #[derive(Debug, Clone)]
 pub struct HttpConfig {
     pub timeout_secs: u64,
     pub max_retries: u32,
     pub user_agent: String,
+    /// Maximum concurrent requests to a single host.
+    pub max_in_flight_per_host: usize,
+    /// Extra static headers sent with every request.
+    pub headers: Vec<(String, String)>,
+    /// Hostnames pinned to specific addresses.
+    pub resolve: Vec<ResolveOverride>,
+    /// Scope every request (including redirects) must stay within.
+    pub scope: Option<Arc<ScopeGuard>>,
+    /// Audit log every request is recorded to.
+    pub audit_log: Option<Arc<AuditLog>>,
 }
 
 impl Default for HttpConfig {
@@ -184,6 +592,11 @@ impl Default for HttpConfig {
             timeout_secs: 30,
             max_retries: 3,
             user_agent: "Mozilla/5.0 (compatible; depfused/0.1)".to_string(),
+            max_in_flight_per_host: 6,
+            headers: Vec::new(),
+            resolve: Vec::new(),
+            scope: None,
+            audit_log: None,
         }
     }
 }